@@ -0,0 +1,3 @@
+pub mod connection;
+pub mod result_set;
+pub mod statement;
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	buffer::manager::BufferMgr,
+	file::manager::FileMgr,
+	log::manager::LogMgr,
+	metadata::metadata_mgr::MetadataMgr,
+	plan::planner::Planner,
+	tx::transaction::Transaction,
+};
+
+use super::statement::Statement;
+
+pub const BLOCK_SIZE: u64 = 400;
+pub const BUFFER_SIZE: usize = 8;
+pub const LOG_FILE: &str = "simpledb.log";
+
+// An embedded connection to the database in a directory.
+// Statements run in the current tx, which is committed or rolled back
+// once each statement is done, and replaced by a new one.
+pub struct Connection {
+	fm: Arc<Mutex<FileMgr>>,
+	lm: Arc<Mutex<LogMgr>>,
+	bm: Arc<Mutex<BufferMgr>>,
+	planner: Arc<Planner>,
+	tx: Arc<Mutex<Transaction>>,
+}
+
+impl Connection {
+	pub fn new(dirname: &str) -> Result<Self> {
+		Self::with_config(dirname, BLOCK_SIZE, BUFFER_SIZE)
+	}
+
+	// recovers the database first, unless it is new
+	pub fn with_config(dirname: &str, blocksize: u64, buffsize: usize) -> Result<Self> {
+		let fm = Arc::new(Mutex::new(FileMgr::new(dirname, blocksize)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), LOG_FILE)?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), buffsize)?));
+		let is_new = fm.lock().unwrap().is_new();
+
+		let tx = Arc::new(Mutex::new(Transaction::new(fm.clone(), lm.clone(), bm.clone())?));
+		if !is_new {
+			tx.lock().unwrap().recover()?;
+		}
+		let mdm = Arc::new(MetadataMgr::new(is_new, tx.clone())?);
+		tx.lock().unwrap().commit()?;
+
+		let tx = Arc::new(Mutex::new(Transaction::new(fm.clone(), lm.clone(), bm.clone())?));
+		Ok(Self {
+			fm,
+			lm,
+			bm,
+			planner: Arc::new(Planner::new(mdm)),
+			tx,
+		})
+	}
+
+	pub fn create_statement(&mut self) -> Statement<'_> {
+		Statement::new(self)
+	}
+
+	pub fn commit(&mut self) -> Result<()> {
+		self.tx.lock().unwrap().commit()?;
+		self.tx = self.new_tx()?;
+
+		Ok(())
+	}
+
+	pub fn rollback(&mut self) -> Result<()> {
+		self.tx.lock().unwrap().rollback()?;
+		self.tx = self.new_tx()?;
+
+		Ok(())
+	}
+
+	pub fn close(self) -> Result<()> {
+		self.tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+
+	pub(crate) fn planner(&self) -> Arc<Planner> {
+		self.planner.clone()
+	}
+
+	pub(crate) fn transaction(&self) -> Arc<Mutex<Transaction>> {
+		self.tx.clone()
+	}
+
+	fn new_tx(&self) -> Result<Arc<Mutex<Transaction>>> {
+		let tx = Transaction::new(self.fm.clone(), self.lm.clone(), self.bm.clone())?;
+		Ok(Arc::new(Mutex::new(tx)))
+	}
+}
@@ -0,0 +1,119 @@
+use anyhow::Result;
+use core::fmt;
+
+use crate::{
+	query::scan::Scan,
+	record::schema::{FieldType, Schema},
+};
+
+use super::connection::Connection;
+
+#[derive(Debug)]
+enum ResultSetError {
+	RolledBack,
+	TypeMismatch(String, FieldType),
+}
+
+impl std::error::Error for ResultSetError {}
+impl fmt::Display for ResultSetError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ResultSetError::RolledBack => write!(f, "result set was rolled back after a failure"),
+			ResultSetError::TypeMismatch(fldname, fldtype) => {
+				write!(f, "field {} is not of type {:?}", fldname, fldtype)
+			}
+		}
+	}
+}
+
+// The records output by a query.
+// Field names are case-insensitive, as they are in SQL.
+// The first failure rolls back the tx of the query, after which
+// the result set only errors and closing it does nothing.
+pub struct ResultSet<'a> {
+	s: Box<dyn Scan>,
+	sch: Schema,
+	conn: &'a mut Connection,
+	rolled_back: bool,
+}
+
+impl<'a> ResultSet<'a> {
+	pub fn new(s: Box<dyn Scan>, sch: Schema, conn: &'a mut Connection) -> Self {
+		Self {
+			s,
+			sch,
+			conn,
+			rolled_back: false,
+		}
+	}
+
+	// fallible, unlike Iterator::next
+	#[allow(clippy::should_implement_trait)]
+	pub fn next(&mut self) -> Result<bool> {
+		self.check_open()?;
+		let res = self.s.next();
+		self.rollback_on_err(res)
+	}
+
+	pub fn get_i32(&mut self, fldname: &str) -> Result<i32> {
+		self.check_open()?;
+		let fldname = fldname.to_lowercase();
+		let res = self
+			.check_type(&fldname, FieldType::INTEGER)
+			.and_then(|_| self.s.get_i32(&fldname));
+		self.rollback_on_err(res)
+	}
+
+	pub fn get_string(&mut self, fldname: &str) -> Result<String> {
+		self.check_open()?;
+		let fldname = fldname.to_lowercase();
+		let res = self
+			.check_type(&fldname, FieldType::VARCHAR)
+			.and_then(|_| self.s.get_string(&fldname));
+		self.rollback_on_err(res)
+	}
+
+	// the output fields
+	pub fn schema(&self) -> &Schema {
+		&self.sch
+	}
+
+	// commits the tx of the query, or rolls it back if the scan fails to close
+	pub fn close(mut self) -> Result<()> {
+		if self.rolled_back {
+			return Ok(());
+		}
+		match self.s.close() {
+			Ok(()) => self.conn.commit(),
+			Err(e) => {
+				self.conn.rollback()?;
+				Err(e)
+			}
+		}
+	}
+
+	fn check_open(&self) -> Result<()> {
+		if self.rolled_back {
+			return Err(From::from(ResultSetError::RolledBack));
+		}
+		Ok(())
+	}
+
+	// the scan reads whatever bytes the field holds, whatever its type
+	fn check_type(&self, fldname: &str, fldtype: FieldType) -> Result<()> {
+		if self.sch.field_type(fldname)? != fldtype {
+			return Err(From::from(ResultSetError::TypeMismatch(fldname.to_string(), fldtype)));
+		}
+		Ok(())
+	}
+
+	fn rollback_on_err<T>(&mut self, res: Result<T>) -> Result<T> {
+		if res.is_err() {
+			self.rolled_back = true;
+			// the original error is the one worth reporting
+			let _ = self.s.close();
+			self.conn.rollback()?;
+		}
+		res
+	}
+}
@@ -0,0 +1,45 @@
+use anyhow::Result;
+
+use super::{connection::Connection, result_set::ResultSet};
+
+// Runs SQL on a connection. An update is committed at once,
+// a query when its result set is closed; either is rolled back if it fails,
+// a query also if any call on its result set fails.
+pub struct Statement<'a> {
+	conn: &'a mut Connection,
+}
+
+impl<'a> Statement<'a> {
+	pub fn new(conn: &'a mut Connection) -> Self {
+		Self { conn }
+	}
+
+	pub fn execute_query(&mut self, qry: &str) -> Result<ResultSet<'_>> {
+		let opened = self
+			.conn
+			.planner()
+			.create_query_plan(qry, self.conn.transaction())
+			.and_then(|p| Ok((p.open()?, p.schema().clone())));
+		match opened {
+			Ok((s, sch)) => Ok(ResultSet::new(s, sch, self.conn)),
+			Err(e) => {
+				self.conn.rollback()?;
+				Err(e)
+			}
+		}
+	}
+
+	// returns the number of records affected
+	pub fn execute_update(&mut self, cmd: &str) -> Result<usize> {
+		match self.conn.planner().execute_update(cmd, self.conn.transaction()) {
+			Ok(count) => {
+				self.conn.commit()?;
+				Ok(count)
+			}
+			Err(e) => {
+				self.conn.rollback()?;
+				Err(e)
+			}
+		}
+	}
+}
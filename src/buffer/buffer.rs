@@ -1,6 +1,6 @@
 use anyhow::Result;
 use core::fmt;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use crate::{
 	file::{block_id::BlockId, manager::FileMgr, page::Page},
@@ -27,7 +27,7 @@ impl fmt::Display for BufferError {
 pub struct Buffer {
 	fm: Arc<Mutex<FileMgr>>,
 	lm: Arc<Mutex<LogMgr>>,
-	contents: Page,
+	contents: Arc<RwLock<Page>>,
 	blk: Option<BlockId>,
 	pins: u64,
 	txnum: i32,
@@ -37,7 +37,7 @@ pub struct Buffer {
 impl Buffer {
 	pub fn new(fm: Arc<Mutex<FileMgr>>, lm: Arc<Mutex<LogMgr>>) -> Self {
 		let blksize = fm.lock().unwrap().blocksize() as usize;
-		let contents = Page::new_from_size(blksize);
+		let contents = Arc::new(RwLock::new(Page::new_from_size(blksize)));
 
 		Self {
 			fm,
@@ -50,8 +50,12 @@ impl Buffer {
 		}
 	}
 
-	pub fn contents(&mut self) -> &mut Page {
-		&mut self.contents
+	pub fn contents(&mut self) -> RwLockWriteGuard<'_, Page> {
+		self.contents.write().unwrap()
+	}
+
+	pub fn shared_contents(&self) -> Arc<RwLock<Page>> {
+		Arc::clone(&self.contents)
 	}
 
 	pub fn block(&self) -> Option<&BlockId> {
@@ -73,9 +77,15 @@ impl Buffer {
 		self.txnum
 	}
 
+	pub fn is_dirty(&self) -> bool {
+		self.txnum >= 0
+	}
+
 	pub fn assign_to_block(&mut self, b: BlockId) -> Result<()> {
-		self.flush()?;
-		self.fm.lock().unwrap().read(&b, &mut self.contents)?;
+		if self.is_dirty() {
+			self.flush()?;
+		}
+		self.fm.lock().unwrap().read(&b, &mut self.contents.write().unwrap())?;
 		self.blk = Some(b);
 		self.pins = 0;
 
@@ -83,16 +93,28 @@ impl Buffer {
 	}
 
 	pub fn flush(&mut self) -> Result<()> {
-		if self.txnum >= 0 {
+		if self.is_dirty() {
+			// write-ahead logging: the log must reach disk before the page does
 			self.lm.lock().unwrap().flush(self.lsn as u64)?;
+			self.write_contents()?;
+		}
+
+		Ok(())
+	}
 
-			match self.blk.as_ref() {
-				Some(blk) => {
-					self.fm.lock().unwrap().write(blk, &mut self.contents)?;
-					self.txnum = -1;
-				}
-				None => return Err(From::from(BufferError::BlockNotFound)),
+	fn write_contents(&mut self) -> Result<()> {
+		debug_assert!(
+			self.lsn < 0 || self.lm.lock().unwrap().last_saved_lsn() >= self.lsn as u64,
+			"WAL violation: page with lsn {} written before its log record",
+			self.lsn,
+		);
+
+		match self.blk.as_ref() {
+			Some(blk) => {
+				self.fm.lock().unwrap().write(blk, &mut self.contents.write().unwrap())?;
+				self.txnum = -1;
 			}
+			None => return Err(From::from(BufferError::BlockNotFound)),
 		}
 
 		Ok(())
@@ -107,12 +129,61 @@ impl Buffer {
 	}
 }
 
+// A read-only view of a pinned buffer.
+// Readers share the page lock, so they do not block each other.
+#[derive(Debug, Clone)]
+pub struct SharedBuffer {
+	buff: Arc<Mutex<Buffer>>,
+	contents: Arc<RwLock<Page>>,
+	blk: BlockId,
+}
+
+impl SharedBuffer {
+	pub fn new(buff: Arc<Mutex<Buffer>>) -> Result<Self> {
+		let b = buff.lock().unwrap();
+		let contents = b.shared_contents();
+		let blk = match b.block() {
+			Some(blk) => blk.clone(),
+			None => return Err(From::from(BufferError::BlockNotFound)),
+		};
+		drop(b);
+
+		Ok(Self { buff, contents, blk })
+	}
+
+	pub fn page(&self) -> RwLockReadGuard<'_, Page> {
+		self.contents.read().unwrap()
+	}
+
+	pub fn get_i32(&self, offset: usize) -> Result<i32> {
+		self.page().get_i32(offset)
+	}
+
+	pub fn get_string(&self, offset: usize) -> Result<String> {
+		self.page().get_string(offset)
+	}
+
+	pub fn block(&self) -> &BlockId {
+		&self.blk
+	}
+
+	// the underlying buffer, to be handed back to BufferMgr::unpin
+	pub fn buffer(&self) -> Arc<Mutex<Buffer>> {
+		Arc::clone(&self.buff)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use crate::file::{block_id::BlockId, manager::FileMgr, page::PageSetter};
 	use crate::log::manager::LogMgr;
 	use crate::buffer::manager::BufferMgr;
+	use std::{
+		sync::{mpsc, Barrier},
+		thread,
+		time::Duration,
+	};
 
 	static LOG_FILE: &str = "simpledb.log";
 
@@ -122,15 +193,16 @@ mod tests {
 		let fm_arc = Arc::new(Mutex::new(fm));
 		let lm = LogMgr::new(Arc::clone(&fm_arc), LOG_FILE).unwrap();
 		let lm_arc = Arc::new(Mutex::new(lm));
-		let mut bm = BufferMgr::new(fm_arc, lm_arc, 3);
+		let mut bm = BufferMgr::new(fm_arc, lm_arc, 3).unwrap();
 
 		let buff1 = bm.pin(&BlockId::new("testfile", 1)).unwrap();
 		{
 			// In this block, buff1 is borrowed and cannot be used
 			let mut b1 = buff1.lock().unwrap();
-			let p = b1.contents();
+			let mut p = b1.contents();
 			let n = p.get_i32(80).unwrap();
 			let _ = p.set(80, n+1);
+			drop(p);
 			b1.set_modified(1, 0);
 			println!("The new value is {}", n + 1);
 		}
@@ -146,10 +218,93 @@ mod tests {
 		{
 			// In this block, buff2 is borrowed and cannot be used
 			let mut b2 = buff2.lock().unwrap();
-			let p = b2.contents();
+			let mut p = b2.contents();
 			let _ = p.set(80, 9999);
+			drop(p);
 			b2.set_modified(1, 0);
 		}
 		let _ = bm.unpin(buff2);
 	}
+
+	#[test]
+	fn reassign_clean_buffer_test() {
+		let fm = FileMgr::new("buffertest/reassign", 400).unwrap();
+		let fm_arc = Arc::new(Mutex::new(fm));
+		let lm = LogMgr::new(Arc::clone(&fm_arc), LOG_FILE).unwrap();
+		let lm_arc = Arc::new(Mutex::new(lm));
+		let mut buff = Buffer::new(Arc::clone(&fm_arc), lm_arc);
+		assert!(!buff.is_dirty());
+
+		let written = fm_arc.lock().unwrap().blocks_written();
+		buff.assign_to_block(BlockId::new("testfile", 1)).unwrap();
+		buff.assign_to_block(BlockId::new("testfile", 2)).unwrap();
+		assert_eq!(fm_arc.lock().unwrap().blocks_written(), written);
+
+		buff.set_modified(1, 0);
+		assert!(buff.is_dirty());
+		buff.assign_to_block(BlockId::new("testfile", 3)).unwrap();
+		assert_eq!(fm_arc.lock().unwrap().blocks_written(), written + 1);
+		assert!(!buff.is_dirty());
+	}
+
+	#[test]
+	#[cfg(debug_assertions)]
+	#[should_panic(expected = "WAL violation")]
+	fn write_before_log_flush_test() {
+		let fm = FileMgr::new("buffertest/wal", 400).unwrap();
+		let fm_arc = Arc::new(Mutex::new(fm));
+		let lm = LogMgr::new(Arc::clone(&fm_arc), LOG_FILE).unwrap();
+		let lm_arc = Arc::new(Mutex::new(lm));
+		let mut buff = Buffer::new(fm_arc, Arc::clone(&lm_arc));
+		buff.assign_to_block(BlockId::new("testfile", 1)).unwrap();
+
+		let lsn = lm_arc.lock().unwrap().append(&mut vec![0u8; 4]).unwrap();
+		buff.set_modified(1, lsn as i32);
+		// skip the log flush that flush() would have done first
+		let _ = buff.write_contents();
+	}
+
+	#[test]
+	fn shared_buffer_test() {
+		let fm = FileMgr::new("buffertest/shared", 400).unwrap();
+		let fm_arc = Arc::new(Mutex::new(fm));
+		let lm = LogMgr::new(Arc::clone(&fm_arc), LOG_FILE).unwrap();
+		let lm_arc = Arc::new(Mutex::new(lm));
+		let mut bm = BufferMgr::new(fm_arc, lm_arc, 3).unwrap();
+
+		let blk = BlockId::new("testfile", 1);
+		let buff = bm.pin(&blk).unwrap();
+		{
+			let mut b = buff.lock().unwrap();
+			let _ = b.contents().set(80, 12345);
+			b.set_modified(1, 0);
+		}
+		let _ = bm.unpin(buff);
+
+		let reader1 = bm.pin_shared(&blk).unwrap();
+		let reader2 = bm.pin_shared(&blk).unwrap();
+		assert_eq!(reader1.block(), &blk);
+		assert_eq!(bm.available().unwrap(), 2);
+
+		// Both readers hold the page at the same time before either reads;
+		// if they were serialized, neither would get past the barrier.
+		let barrier = Arc::new(Barrier::new(2));
+		let (tx, rx) = mpsc::channel();
+		for reader in [reader1.clone(), reader2.clone()] {
+			let barrier = Arc::clone(&barrier);
+			let tx = tx.clone();
+			thread::spawn(move || {
+				let page = reader.page();
+				barrier.wait();
+				tx.send(page.get_i32(80).unwrap()).unwrap();
+			});
+		}
+		for _ in 0..2 {
+			assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), 12345);
+		}
+
+		let _ = bm.unpin(reader1.buffer());
+		let _ = bm.unpin(reader2.buffer());
+		assert_eq!(bm.available().unwrap(), 3);
+	}
 }
@@ -1,12 +1,13 @@
 use anyhow::Result;
 use core::fmt;
 use std::{
+	collections::HashMap,
 	sync::{Arc, Mutex},
 	thread,
 	time::{Duration, SystemTime},
 };
 
-use super::buffer::Buffer;
+use super::buffer::{Buffer, SharedBuffer};
 use crate::{
 	file::{block_id::BlockId, manager::FileMgr},
 	log::manager::LogMgr,
@@ -15,29 +16,43 @@ use crate::{
 const MAX_TIME: i64 = 10_000; // 10 seconds
 
 #[derive(Debug)]
-enum BufferMgrError {
+pub enum BufferMgrError {
+	NoBuffers,
 	LockFailed(String),
-	BufferAbort,
+	BufferAbort { blk: BlockId, waited: Duration },
 }
 
 impl std::error::Error for BufferMgrError {}
 impl fmt::Display for BufferMgrError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		match self {
+			BufferMgrError::NoBuffers => {
+				write!(f, "buffer pool needs at least one buffer")
+			}
 			BufferMgrError::LockFailed(s) => {
 				write!(f, "lock failed: {}", s)
 			}
-			BufferMgrError::BufferAbort => {
-				write!(f, "buffer abort")
+			BufferMgrError::BufferAbort { blk, waited } => {
+				write!(f, "buffer abort: {} (waited {:?})", blk, waited)
 			}
 		}
 	}
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BufferStats {
+	pub pin_requests: u64,
+	// buffers taken from one block and reassigned to another
+	pub evictions: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct BufferMgr {
 	bufferpool: Vec<Arc<Mutex<Buffer>>>,
 	num_available: Arc<Mutex<usize>>,
+	// resident blocks and their indices in bufferpool
+	buffer_index: Arc<Mutex<HashMap<BlockId, usize>>>,
+	stats: Arc<Mutex<BufferStats>>,
 }
 
 impl BufferMgr {
@@ -45,15 +60,22 @@ impl BufferMgr {
 		fm: Arc<Mutex<FileMgr>>,
 		lm: Arc<Mutex<LogMgr>>,
 		numbuffs: usize,
-	) -> Self {
+	) -> Result<Self> {
+		// an empty pool could never satisfy a pin
+		if numbuffs == 0 {
+			return Err(From::from(BufferMgrError::NoBuffers));
+		}
+
 		let bufferpool = (0..numbuffs)
 			.map(|_| Arc::new(Mutex::new(Buffer::new(Arc::clone(&fm), Arc::clone(&lm)))))
 			.collect();
 
-		Self {
+		Ok(Self {
 			bufferpool,
 			num_available: Arc::new(Mutex::new(numbuffs)),
-		}
+			buffer_index: Arc::new(Mutex::new(HashMap::new())),
+			stats: Arc::new(Mutex::new(BufferStats::default())),
+		})
 	}
 
 	pub fn available(&self) -> Result<usize> {
@@ -61,6 +83,10 @@ impl BufferMgr {
 		Ok(*num)
 	}
 
+	pub fn stats(&self) -> BufferStats {
+		*self.stats.lock().unwrap()
+	}
+
 	pub fn flush_all(&mut self, txnum: i32) -> Result<()> {
 		for i in 0..self.bufferpool.len() {
 			let mut buff = self.bufferpool[i].lock().unwrap();
@@ -71,28 +97,66 @@ impl BufferMgr {
 		Ok(())
 	}
 
-	pub fn unpin(&mut self, buff: Arc<Mutex<Buffer>>) -> Result<()> {
-		let mut b = buff.lock().unwrap();
-		b.unpin();
-		if !b.is_pinned() {
-			*(self.num_available.lock().unwrap()) += 1;
+	// Flushes every modified buffer regardless of its transaction, as a checkpoint needs.
+	// Buffer::flush forces the log before writing each page.
+	pub fn flush_all_dirty(&mut self) -> Result<()> {
+		for buff in self.bufferpool.iter() {
+			let mut b = buff.lock().unwrap();
+			if b.is_dirty() {
+				b.flush()?;
+			}
+		}
+		Ok(())
+	}
+
+	// Writes the block to disk if it is resident and modified.
+	// A block which is not in the pool is left alone.
+	pub fn flush_block(&mut self, blk: &BlockId) -> Result<()> {
+		if let Some(buff) = self.find_existing_buffer(blk) {
+			buff.lock().unwrap().flush()?;
 		}
 		Ok(())
 	}
 
+	pub fn unpin(&mut self, buff: Arc<Mutex<Buffer>>) -> Result<()> {
+		unpin_buffer(&buff, &self.num_available);
+		Ok(())
+	}
+
+	// Same as pin, but the buffer is unpinned when the returned guard is dropped.
+	pub fn pin_guarded(&mut self, blk: &BlockId) -> Result<PinnedBuffer> {
+		let buff = self.pin(blk)?;
+
+		Ok(PinnedBuffer {
+			buff,
+			num_available: Arc::clone(&self.num_available),
+		})
+	}
+
 	pub fn pin(&mut self, blk: &BlockId) -> Result<Arc<Mutex<Buffer>>> {
+		self.stats.lock().unwrap().pin_requests += 1;
 		let timestamp = SystemTime::now();
 		while !waiting_too_long(timestamp) {
-			if let Ok(buff) = self.try_to_pin(blk) {
+			if let Some(buff) = self.try_to_pin(blk) {
 				return Ok(buff);
 			}
 			thread::sleep(Duration::new(1, 0))
 		}
 
-		Err(From::from(BufferMgrError::BufferAbort))
+		Err(From::from(BufferMgrError::BufferAbort {
+			blk: blk.clone(),
+			waited: SystemTime::now().duration_since(timestamp).unwrap(),
+		}))
 	}
 
-	fn try_to_pin(&mut self, blk: &BlockId) -> Result<Arc<Mutex<Buffer>>> {
+	// Pins the block for reading only.
+	// The returned view must be unpinned with `unpin(view.buffer())`.
+	pub fn pin_shared(&mut self, blk: &BlockId) -> Result<SharedBuffer> {
+		let buff = self.pin(blk)?;
+		SharedBuffer::new(buff)
+	}
+
+	fn try_to_pin(&mut self, blk: &BlockId) -> Option<Arc<Mutex<Buffer>>> {
 		if let Some(buff) = self.pickup_pinnable_buffer(blk) {
 			let mut b = buff.lock().unwrap();
 			
@@ -105,10 +169,10 @@ impl BufferMgr {
 			b.pin();
 
 			drop(b); // release
-			return Ok(buff);
+			return Some(buff);
 		}
 
-		Err(From::from(BufferMgrError::BufferAbort))
+		None
 	}
 
 	fn pickup_pinnable_buffer(&mut self, blk: &BlockId) -> Option<Arc<Mutex<Buffer>>> {
@@ -116,13 +180,22 @@ impl BufferMgr {
 			return Some(buff);
 		}
 
-		if let Some(buff) = self.choose_unpinned_buffer() {
+		if let Some(idx) = self.choose_unpinned_buffer() {
+			let buff = Arc::clone(&self.bufferpool[idx]);
 			let mut b = buff.lock().unwrap();
+			let old_blk = b.block().cloned();
 
 			if let Err(e) = b.assign_to_block(blk.clone()) {
 				eprintln!("failed to assign to block: {}", e);
 				return None
 			}
+
+			let mut index = self.buffer_index.lock().unwrap();
+			if let Some(old_blk) = old_blk {
+				index.remove(&old_blk);
+				self.stats.lock().unwrap().evictions += 1;
+			}
+			index.insert(blk.clone(), idx);
 			
 			drop(b);
 			return Some(buff);
@@ -131,22 +204,18 @@ impl BufferMgr {
 	}
 
 	fn find_existing_buffer(&mut self, blk: &BlockId) -> Option<Arc<Mutex<Buffer>>> {
-		for i in 0..self.bufferpool.len() {
-			let buff = self.bufferpool[i].lock().unwrap();
-			if let Some(b) = buff.block() {
-				if *b == *blk {
-					return Some(Arc::clone(&self.bufferpool[i]))
-				}
-			}
-		}
-		None
+		self.buffer_index
+			.lock()
+			.unwrap()
+			.get(blk)
+			.map(|&idx| Arc::clone(&self.bufferpool[idx]))
 	}
 
-	fn choose_unpinned_buffer(&mut self) -> Option<Arc<Mutex<Buffer>>> {
+	fn choose_unpinned_buffer(&mut self) -> Option<usize> {
 		for i in 0..self.bufferpool.len() {
 			let buff = self.bufferpool[i].lock().unwrap();
 			if !buff.is_pinned() {
-				return Some(Arc::clone(&self.bufferpool[i]));
+				return Some(i);
 			}
 		}
 
@@ -154,6 +223,32 @@ impl BufferMgr {
 	}
 }
 
+#[derive(Debug)]
+pub struct PinnedBuffer {
+	buff: Arc<Mutex<Buffer>>,
+	num_available: Arc<Mutex<usize>>,
+}
+
+impl PinnedBuffer {
+	pub fn buffer(&self) -> &Arc<Mutex<Buffer>> {
+		&self.buff
+	}
+}
+
+impl Drop for PinnedBuffer {
+	fn drop(&mut self) {
+		unpin_buffer(&self.buff, &self.num_available);
+	}
+}
+
+fn unpin_buffer(buff: &Arc<Mutex<Buffer>>, num_available: &Arc<Mutex<usize>>) {
+	let mut b = buff.lock().unwrap();
+	b.unpin();
+	if !b.is_pinned() {
+		*(num_available.lock().unwrap()) += 1;
+	}
+}
+
 fn waiting_too_long(starttime: SystemTime) -> bool {
 	let now = SystemTime::now();
 	let diff = now.duration_since(starttime).unwrap();
@@ -165,7 +260,7 @@ fn waiting_too_long(starttime: SystemTime) -> bool {
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::file::{block_id::BlockId, manager::FileMgr};
+	use crate::file::{block_id::BlockId, manager::FileMgr, page::{Page, PageSetter}};
 	use crate::log::manager::LogMgr;
 	use crate::buffer::manager::BufferMgr;
 
@@ -179,7 +274,7 @@ mod tests {
 		let fm_arc = Arc::new(Mutex::new(fm));
 		let lm = LogMgr::new(Arc::clone(&fm_arc), LOG_FILE).unwrap();
 		let lm_arc = Arc::new(Mutex::new(lm));
-		let mut bm = BufferMgr::new(fm_arc, lm_arc, 3);
+		let mut bm = BufferMgr::new(fm_arc, lm_arc, 3)?;
 		
 		let mut buffs: Vec<Option<Arc<Mutex<Buffer>>>> = vec![None; 6];
 		buffs[0] = bm.pin(&BlockId::new("testfile", 0))?.into();
@@ -197,7 +292,13 @@ mod tests {
 		println!("Abailable buffers: {}", bm.available()?);
 		println!("Attempting to pin block 3...");
 		let result = bm.pin(&BlockId::new("testfile", 3));
-		assert!(result.is_err());
+		match result.unwrap_err().downcast_ref::<BufferMgrError>() {
+			Some(BufferMgrError::BufferAbort { blk, waited }) => {
+				assert_eq!(*blk, BlockId::new("testfile", 3));
+				assert!(waited.as_millis() as i64 > MAX_TIME);
+			}
+			e => panic!("unexpected error: {:?}", e),
+		}
 
 		bm.unpin(Arc::clone(&buffs[2].clone().unwrap()))?;
 		buffs[2] = None;
@@ -220,6 +321,173 @@ mod tests {
 		Ok(())
 	}
 
+	#[test]
+	fn buffermgr_large_pool_test() -> Result<()> {
+		let fm = FileMgr::new("buffermgrtest/largepool", 400).unwrap();
+		let fm_arc = Arc::new(Mutex::new(fm));
+		let lm = LogMgr::new(Arc::clone(&fm_arc), LOG_FILE).unwrap();
+		let lm_arc = Arc::new(Mutex::new(lm));
+		let numbuffs = 1000;
+		let mut bm = BufferMgr::new(fm_arc, lm_arc, numbuffs)?;
+
+		let buffs = (0..numbuffs)
+			.map(|i| bm.pin(&BlockId::new("testfile", i as u64)))
+			.collect::<Result<Vec<_>>>()?;
+		assert_eq!(bm.available()?, 0);
+
+		// every block is resident, so pinning again only looks up the index
+		for (i, buff) in buffs.iter().enumerate() {
+			let again = bm.pin(&BlockId::new("testfile", i as u64))?;
+			assert!(Arc::ptr_eq(buff, &again));
+			bm.unpin(again)?;
+		}
+		assert_eq!(bm.available()?, 0);
+		assert_eq!(bm.stats(), BufferStats { pin_requests: 2 * numbuffs as u64, evictions: 0 });
+
+		// reassigned buffers must no longer be found under their old block
+		bm.unpin(Arc::clone(&buffs[0]))?;
+		let buff = bm.pin(&BlockId::new("testfile", numbuffs as u64))?;
+		assert!(Arc::ptr_eq(&buffs[0], &buff));
+		assert!(bm.find_existing_buffer(&BlockId::new("testfile", 0)).is_none());
+		assert_eq!(bm.stats().evictions, 1);
+
+		Ok(())
+	}
+
+	#[test]
+	fn buffermgr_flush_block_test() -> Result<()> {
+		let fm = FileMgr::new("buffermgrtest/flushblock", 400).unwrap();
+		let fm_arc = Arc::new(Mutex::new(fm));
+		let lm = LogMgr::new(Arc::clone(&fm_arc), LOG_FILE).unwrap();
+		let lm_arc = Arc::new(Mutex::new(lm));
+		let mut bm = BufferMgr::new(Arc::clone(&fm_arc), lm_arc, 3)?;
+
+		let blk = BlockId::new("testfile", 1);
+		let buff = bm.pin(&blk)?;
+		let n = rand::random::<i32>();
+		{
+			let mut b = buff.lock().unwrap();
+			b.contents().set(80, n)?;
+			b.set_modified(1, 0);
+		}
+
+		bm.flush_block(&blk)?;
+		assert_eq!(buff.lock().unwrap().modifying_tx(), -1);
+
+		let mut p = Page::new_from_size(400);
+		fm_arc.lock().unwrap().read(&blk, &mut p)?;
+		assert_eq!(p.get_i32(80)?, n);
+
+		// not resident: nothing to do
+		bm.flush_block(&BlockId::new("testfile", 2))?;
+		bm.unpin(buff)?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn buffermgr_flush_all_dirty_test() -> Result<()> {
+		let fm = FileMgr::new("buffermgrtest/flushalldirty", 400).unwrap();
+		let fm_arc = Arc::new(Mutex::new(fm));
+		let lm = LogMgr::new(Arc::clone(&fm_arc), LOG_FILE).unwrap();
+		let lm_arc = Arc::new(Mutex::new(lm));
+		let mut bm = BufferMgr::new(Arc::clone(&fm_arc), lm_arc, 3)?;
+
+		let mut buffs = vec![];
+		for txnum in 1..4 {
+			let buff = bm.pin(&BlockId::new("testfile", txnum as u64))?;
+			{
+				let mut b = buff.lock().unwrap();
+				b.contents().set(80, txnum * 100)?;
+				b.set_modified(txnum, 0);
+			}
+			buffs.push(buff);
+		}
+
+		bm.flush_all_dirty()?;
+
+		for (i, buff) in buffs.into_iter().enumerate() {
+			assert_eq!(buff.lock().unwrap().modifying_tx(), -1);
+
+			let mut p = Page::new_from_size(400);
+			fm_arc.lock().unwrap().read(&BlockId::new("testfile", i as u64 + 1), &mut p)?;
+			assert_eq!(p.get_i32(80)?, (i as i32 + 1) * 100);
+			bm.unpin(buff)?;
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn buffermgr_pin_guarded_test() -> Result<()> {
+		let fm = FileMgr::new("buffermgrtest/guarded", 400).unwrap();
+		let fm_arc = Arc::new(Mutex::new(fm));
+		let lm = LogMgr::new(Arc::clone(&fm_arc), LOG_FILE).unwrap();
+		let lm_arc = Arc::new(Mutex::new(lm));
+		let mut bm = BufferMgr::new(fm_arc, lm_arc, 3)?;
+
+		{
+			let guard1 = bm.pin_guarded(&BlockId::new("testfile", 1))?;
+			let _guard2 = bm.pin_guarded(&BlockId::new("testfile", 2))?;
+			assert_eq!(bm.available()?, 1);
+			assert_eq!(
+				guard1.buffer().lock().unwrap().block(),
+				Some(&BlockId::new("testfile", 1)),
+			);
+
+			drop(guard1);
+			assert_eq!(bm.available()?, 2);
+		}
+		assert_eq!(bm.available()?, 3);
+
+		Ok(())
+	}
+
+	#[test]
+	fn buffermgr_evictions_test() -> Result<()> {
+		let fm = FileMgr::new("buffermgrtest/evictions", 400).unwrap();
+		let fm_arc = Arc::new(Mutex::new(fm));
+		let lm = LogMgr::new(Arc::clone(&fm_arc), LOG_FILE).unwrap();
+		let lm_arc = Arc::new(Mutex::new(lm));
+		let mut bm = BufferMgr::new(fm_arc, lm_arc, 3)?;
+
+		// filling empty buffers evicts nothing
+		let buffs = (0..3)
+			.map(|i| bm.pin(&BlockId::new("testfile", i)))
+			.collect::<Result<Vec<_>>>()?;
+		for buff in buffs {
+			bm.unpin(buff)?;
+		}
+		assert_eq!(bm.stats(), BufferStats { pin_requests: 3, evictions: 0 });
+
+		// re-pinning a resident block evicts nothing either
+		let buff = bm.pin(&BlockId::new("testfile", 0))?;
+		bm.unpin(buff)?;
+		assert_eq!(bm.stats().evictions, 0);
+
+		let n = 5;
+		for i in 3..(3 + n) {
+			let buff = bm.pin(&BlockId::new("testfile", i))?;
+			bm.unpin(buff)?;
+		}
+		assert_eq!(bm.stats(), BufferStats { pin_requests: 4 + n, evictions: n });
+
+		Ok(())
+	}
+
+	#[test]
+	fn buffermgr_rejects_empty_pool() {
+		let fm = FileMgr::new("buffermgrtest/empty", 400).unwrap();
+		let fm_arc = Arc::new(Mutex::new(fm));
+		let lm = LogMgr::new(Arc::clone(&fm_arc), LOG_FILE).unwrap();
+		let lm_arc = Arc::new(Mutex::new(lm));
+
+		let start = SystemTime::now();
+		let e = BufferMgr::new(fm_arc, lm_arc, 0).unwrap_err();
+		assert!(matches!(e.downcast_ref::<BufferMgrError>(), Some(BufferMgrError::NoBuffers)));
+		assert!(SystemTime::now().duration_since(start).unwrap() < Duration::from_secs(1));
+	}
+
 	trait BufferAssertion {
 		fn assert_buffer(&self, buff: &Option<Arc<Mutex<Buffer>>>);
 	}
@@ -1,6 +1,7 @@
 use core::fmt;
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+// ordered by filename, then block number
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct BlockId {
 	filename: String,
 	blknum: u64,
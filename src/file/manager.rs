@@ -34,6 +34,9 @@ pub struct FileMgr {
 	blocksize: u64,
 	is_new: bool,
 	open_files: HashMap<String, Arc<Mutex<File>>>,
+	// I/O counters
+	blocks_read: u64,
+	blocks_written: u64,
 }
 
 impl FileMgr {
@@ -47,7 +50,8 @@ impl FileMgr {
 
 		for entry in fs::read_dir(path)? {
 			let entry_path = entry?.path();
-			let filename = match entry_path.as_path().to_str() {
+			// only the name is matched, not the directory
+			let filename = match entry_path.file_name().and_then(|s| s.to_str()) {
 				Some(s) => s.to_string(),
 				None => return Err(From::from(FileMgrError::ParseFailed)),
 			};
@@ -62,6 +66,8 @@ impl FileMgr {
 			blocksize,
 			is_new,
 			open_files: HashMap::new(),
+			blocks_read: 0,
+			blocks_written: 0,
 		})
 	}
 
@@ -80,6 +86,8 @@ impl FileMgr {
 					p.contents()[i] = 0;
 				}
 			}
+			drop(f);
+			self.blocks_read += 1;
 
 			return Ok(());
 		}
@@ -97,6 +105,8 @@ impl FileMgr {
 			let mut f = file.lock().unwrap();
 			f.seek(SeekFrom::Start(offset))?;
 			f.write_all(&b)?;
+			drop(f);
+			self.blocks_written += 1;
 
 			return Ok(blk);
 		}
@@ -110,6 +120,8 @@ impl FileMgr {
 			let mut f = file.lock().unwrap();
 			f.seek(SeekFrom::Start(offset))?;
 			f.write_all(p.contents())?;
+			drop(f);
+			self.blocks_written += 1;
 
 			return Ok(());
 		}
@@ -153,6 +165,14 @@ impl FileMgr {
 		self.is_new
 	}
 
+	pub fn blocks_read(&self) -> u64 {
+		self.blocks_read
+	}
+
+	pub fn blocks_written(&self) -> u64 {
+		self.blocks_written
+	}
+
 }
 
 
@@ -179,4 +199,33 @@ mod tests {
 		assert_eq!("abcdefghijklm".to_string(), p2.get_string(pos1).unwrap());
 		assert_eq!(345, p2.get_i32(pos2).unwrap());
 	}
+
+	#[test]
+	fn io_counters() {
+		let mut fm = FileMgr::new("filetest/counters", 400).unwrap();
+		let blk = fm.append("testfile").unwrap();
+		let mut p = Page::new_from_size(fm.blocksize() as usize);
+		let _ = fm.write(&blk, &mut p);
+		let _ = fm.read(&blk, &mut p);
+
+		assert_eq!(fm.blocks_written(), 2);
+		assert_eq!(fm.blocks_read(), 1);
+	}
+
+	#[test]
+	fn temp_files_removed_on_restart() {
+		let dir = "filetest/restart";
+		let _ = fs::remove_dir_all(dir);
+		let _ = FileMgr::new(dir, 400).unwrap();
+		for filename in ["temp1", "temp23", "student.tbl", "simpledb.log"] {
+			File::create(Path::new(dir).join(filename)).unwrap();
+		}
+
+		let fm = FileMgr::new(dir, 400).unwrap();
+		assert!(!fm.is_new());
+		assert!(!Path::new(dir).join("temp1").exists());
+		assert!(!Path::new(dir).join("temp23").exists());
+		assert!(Path::new(dir).join("student.tbl").exists());
+		assert!(Path::new(dir).join("simpledb.log").exists());
+	}
 }
@@ -73,6 +73,33 @@ impl Page {
 		self.set(offset, n)
 	}
 
+	pub fn get_i64(&self, offset: usize) -> Result<i64> {
+		let i64_size = mem::size_of::<i64>();
+
+		if offset + i64_size - 1 < self.bb.len() {
+			let bytes = &self.bb[offset..offset + i64_size];
+			Ok(i64::from_be_bytes((*bytes).try_into()?))
+		} else {
+			Err(PageError::BufferSizeExceeded.into())
+		}
+	}
+
+	pub fn set_i64(&mut self, offset: usize, n: i64) -> Result<usize> {
+		self.set(offset, n)
+	}
+
+	// a bool takes a single byte
+	pub fn get_bool(&self, offset: usize) -> Result<bool> {
+		match self.bb.get(offset) {
+			Some(b) => Ok(*b != 0),
+			None => Err(PageError::BufferSizeExceeded.into()),
+		}
+	}
+
+	pub fn set_bool(&mut self, offset: usize, b: bool) -> Result<usize> {
+		self.set(offset, b)
+	}
+
 	pub fn get_bytes(&self, offset: usize) -> Result<&[u8]> {
 		let len = self.get_i32(offset)? as usize;
 		let new_offset = offset + mem::size_of::<i32>();
@@ -169,6 +196,30 @@ mod tests {
 		assert_eq!(0x789ABCDE, i1);
 	}
 
+	#[test]
+	fn test_set_and_get_i64() {
+		let mut p = Page::new_from_size(12);
+		let _ = p.set(2, 0x0102030405060708i64);
+		assert_eq!(
+			p.contents()[2..10],
+			[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08],
+		);
+		assert_eq!(0x0102030405060708i64, p.get_i64(2).unwrap());
+		assert!(p.get_i64(5).is_err());
+	}
+
+	#[test]
+	fn test_set_and_get_bool() {
+		let mut p = Page::new_from_size(4);
+		let _ = p.set_bool(1, true);
+		let _ = p.set_bool(3, false);
+		assert_eq!(p.contents()[..], [0x00, 0x01, 0x00, 0x00]);
+		assert!(p.get_bool(1).unwrap());
+		assert!(!p.get_bool(3).unwrap());
+		assert!(p.get_bool(4).is_err());
+		assert!(p.set_bool(4, true).is_err());
+	}
+
 	#[test]
 	fn test_get_string() {
 		let test_binary: Vec<u8> = vec![
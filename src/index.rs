@@ -0,0 +1,35 @@
+pub mod btree_dir;
+pub mod btree_index;
+pub mod btree_leaf;
+pub mod btree_page;
+pub mod hash_index;
+
+use anyhow::Result;
+
+use crate::{
+	query::constant::Constant,
+	record::{layout::Layout, rid::RID, schema::{FieldType, Schema}},
+};
+
+// An index maps the values of a field to the records having them.
+// Its entries are looked up through a cursor:
+// before_first positions it on a key, and next moves to each entry of the key.
+pub trait Index {
+	fn before_first(&mut self, searchkey: &Constant) -> Result<()>;
+	fn next(&mut self) -> Result<bool>;
+	// the record of the current entry
+	fn get_data_rid(&self) -> Result<RID>;
+	fn insert(&mut self, dataval: &Constant, datarid: RID) -> Result<()>;
+	fn delete(&mut self, dataval: &Constant, datarid: RID) -> Result<()>;
+	fn close(&mut self) -> Result<()>;
+}
+
+// The layout of an index entry: the rid of the record (block, id) and the key.
+pub fn index_layout(keytype: FieldType, keylength: usize) -> Result<Layout> {
+	let mut sch = Schema::new();
+	sch.add_i32_field("block");
+	sch.add_i32_field("id");
+	sch.add_field("dataval", keytype, keylength);
+
+	Layout::new(sch)
+}
@@ -0,0 +1,102 @@
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	file::block_id::BlockId,
+	query::constant::Constant,
+	record::layout::Layout,
+	tx::transaction::Transaction,
+};
+
+use super::btree_page::{BTreePage, DirEntry};
+
+// A directory block of a B-tree, routing a search key to a child block.
+pub struct BTreeDir {
+	tx: Arc<Mutex<Transaction>>,
+	layout: Arc<Layout>,
+	contents: BTreePage,
+	filename: String,
+}
+
+impl BTreeDir {
+	pub fn new(tx: Arc<Mutex<Transaction>>, blk: BlockId, layout: Arc<Layout>) -> Result<Self> {
+		let filename = blk.file_name();
+		let contents = BTreePage::new(tx.clone(), blk, layout.clone())?;
+
+		Ok(Self {
+			tx,
+			layout,
+			contents,
+			filename,
+		})
+	}
+
+	pub fn close(&mut self) -> Result<()> {
+		self.contents.close()
+	}
+
+	// the number of the leaf block where searchkey belongs
+	pub fn search(&mut self, searchkey: &Constant) -> Result<u64> {
+		let mut childblk = self.find_child_block(searchkey)?;
+		while self.contents.get_flag()? > 0 {
+			self.contents.close()?;
+			self.contents = BTreePage::new(self.tx.clone(), childblk, self.layout.clone())?;
+			childblk = self.find_child_block(searchkey)?;
+		}
+
+		Ok(childblk.number())
+	}
+
+	// Called on the root when it has been split:
+	// its entries move to a new block, so that the root stays at block 0.
+	pub fn make_new_root(&mut self, e: DirEntry) -> Result<()> {
+		let firstval = self.contents.get_data_val(0)?;
+		let level = self.contents.get_flag()?;
+		let newblk = self.contents.split(0, level)?;
+		let oldroot = DirEntry::new(firstval, newblk.number());
+		self.insert_entry(&oldroot)?;
+		self.insert_entry(&e)?;
+		self.contents.set_flag(level + 1)
+	}
+
+	// Inserts the entry of a new child block below this one.
+	// If a block had to be split, returns the entry of the new block for the parent.
+	pub fn insert(&mut self, e: &DirEntry) -> Result<Option<DirEntry>> {
+		if self.contents.get_flag()? == 0 {
+			return self.insert_entry(e);
+		}
+		let childblk = self.find_child_block(e.data_val())?;
+		let mut child = BTreeDir::new(self.tx.clone(), childblk, self.layout.clone())?;
+		let myentry = child.insert(e)?;
+		child.close()?;
+		match myentry {
+			Some(myentry) => self.insert_entry(&myentry),
+			None => Ok(None),
+		}
+	}
+
+	fn insert_entry(&mut self, e: &DirEntry) -> Result<Option<DirEntry>> {
+		let newslot = 1 + self.contents.find_slot_before(e.data_val())?;
+		self.contents.insert_dir(newslot, e.data_val(), e.block_number())?;
+		if !self.contents.is_full()? {
+			return Ok(None);
+		}
+
+		let level = self.contents.get_flag()?;
+		let splitpos = self.contents.get_num_recs()? / 2;
+		let splitval = self.contents.get_data_val(splitpos)?;
+		let newblk = self.contents.split(splitpos, level)?;
+
+		Ok(Some(DirEntry::new(splitval, newblk.number())))
+	}
+
+	fn find_child_block(&self, searchkey: &Constant) -> Result<BlockId> {
+		let mut slot = self.contents.find_slot_before(searchkey)?;
+		if slot + 1 < self.contents.get_num_recs()? && self.contents.get_data_val(slot + 1)? == *searchkey {
+			slot += 1;
+		}
+		let blknum = self.contents.get_child_num(slot)?;
+
+		Ok(BlockId::new(&self.filename, blknum))
+	}
+}
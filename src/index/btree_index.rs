@@ -0,0 +1,233 @@
+use anyhow::Result;
+use core::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	file::block_id::BlockId,
+	query::constant::Constant,
+	record::{layout::Layout, rid::RID, schema::{FieldType, Schema}},
+	tx::transaction::Transaction,
+};
+
+use super::{
+	btree_dir::BTreeDir,
+	btree_leaf::BTreeLeaf,
+	btree_page::{BTreePage, DirEntry},
+	Index,
+};
+
+#[derive(Debug)]
+enum BTreeIndexError {
+	NotPositioned,
+}
+
+impl std::error::Error for BTreeIndexError {}
+impl fmt::Display for BTreeIndexError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			BTreeIndexError::NotPositioned => {
+				write!(f, "btree index is not positioned on a search key")
+			}
+		}
+	}
+}
+
+// A B-tree index, kept in two files:
+// "<idxname>leaf" for the leaf blocks and "<idxname>dir" for the directory,
+// whose root is always block 0.
+pub struct BTreeIndex {
+	tx: Arc<Mutex<Transaction>>,
+	dir_layout: Arc<Layout>,
+	leaf_layout: Arc<Layout>,
+	leaftbl: String,
+	leaf: Option<BTreeLeaf>,
+	rootblk: BlockId,
+}
+
+impl BTreeIndex {
+	pub fn new(tx: Arc<Mutex<Transaction>>, idxname: &str, leaf_layout: Arc<Layout>) -> Result<Self> {
+		let leaftbl = format!("{}leaf", idxname);
+		if tx.lock().unwrap().size(&leaftbl)? == 0 {
+			let blk = tx.lock().unwrap().append(&leaftbl)?;
+			let mut node = BTreePage::new(tx.clone(), blk.clone(), leaf_layout.clone())?;
+			node.format(&blk, -1)?;
+			node.close()?;
+		}
+
+		let leafsch = leaf_layout.schema();
+		let mut dirsch = Schema::new();
+		dirsch.add("block", leafsch)?;
+		dirsch.add("dataval", leafsch)?;
+		let dir_layout = Arc::new(Layout::new(dirsch)?);
+
+		let dirtbl = format!("{}dir", idxname);
+		let rootblk = BlockId::new(&dirtbl, 0);
+		if tx.lock().unwrap().size(&dirtbl)? == 0 {
+			// the root starts with one entry pointing at the first leaf
+			tx.lock().unwrap().append(&dirtbl)?;
+			let mut node = BTreePage::new(tx.clone(), rootblk.clone(), dir_layout.clone())?;
+			node.format(&rootblk, 0)?;
+			let minval = match leafsch.field_type("dataval")? {
+				FieldType::INTEGER => Constant::Int(i32::MIN),
+				FieldType::VARCHAR => Constant::Str("".to_string()),
+			};
+			node.insert_dir(0, &minval, 0)?;
+			node.close()?;
+		}
+
+		Ok(Self {
+			tx,
+			dir_layout,
+			leaf_layout,
+			leaftbl,
+			leaf: None,
+			rootblk,
+		})
+	}
+
+	fn leaf(&mut self) -> Result<&mut BTreeLeaf> {
+		match &mut self.leaf {
+			Some(leaf) => Ok(leaf),
+			None => Err(From::from(BTreeIndexError::NotPositioned)),
+		}
+	}
+}
+
+impl Index for BTreeIndex {
+	fn before_first(&mut self, searchkey: &Constant) -> Result<()> {
+		self.close()?;
+		let mut root = BTreeDir::new(self.tx.clone(), self.rootblk.clone(), self.dir_layout.clone())?;
+		let blknum = root.search(searchkey)?;
+		root.close()?;
+		let leafblk = BlockId::new(&self.leaftbl, blknum);
+		self.leaf = Some(BTreeLeaf::new(
+			self.tx.clone(),
+			leafblk,
+			self.leaf_layout.clone(),
+			searchkey.clone(),
+		)?);
+
+		Ok(())
+	}
+
+	fn next(&mut self) -> Result<bool> {
+		self.leaf()?.next()
+	}
+
+	fn get_data_rid(&self) -> Result<RID> {
+		match &self.leaf {
+			Some(leaf) => leaf.get_data_rid(),
+			None => Err(From::from(BTreeIndexError::NotPositioned)),
+		}
+	}
+
+	fn insert(&mut self, dataval: &Constant, datarid: RID) -> Result<()> {
+		self.before_first(dataval)?;
+		let e = self.leaf()?.insert(datarid)?;
+		self.close()?;
+		let e = match e {
+			Some(e) => e,
+			None => return Ok(()),
+		};
+
+		let mut root = BTreeDir::new(self.tx.clone(), self.rootblk.clone(), self.dir_layout.clone())?;
+		let e2: Option<DirEntry> = root.insert(&e)?;
+		if let Some(e2) = e2 {
+			root.make_new_root(e2)?;
+		}
+		root.close()
+	}
+
+	fn delete(&mut self, dataval: &Constant, datarid: RID) -> Result<()> {
+		self.before_first(dataval)?;
+		self.leaf()?.delete(datarid)?;
+		self.close()
+	}
+
+	fn close(&mut self) -> Result<()> {
+		if let Some(mut leaf) = self.leaf.take() {
+			leaf.close()?;
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::fs;
+
+	use crate::{
+		buffer::manager::BufferMgr,
+		file::manager::FileMgr,
+		index::index_layout,
+		log::manager::LogMgr,
+	};
+
+	fn lookup(idx: &mut BTreeIndex, key: i32) -> Result<Vec<RID>> {
+		idx.before_first(&Constant::Int(key))?;
+		let mut rids = vec![];
+		while idx.next()? {
+			rids.push(idx.get_data_rid()?);
+		}
+		rids.sort_by_key(|rid| (rid.block_number(), rid.slot()));
+
+		Ok(rids)
+	}
+
+	#[test]
+	fn test_lookup_after_splits() -> Result<()> {
+		let dir = "indextest/btreeindex";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+
+		let leaf_layout = Arc::new(index_layout(FieldType::INTEGER, 0)?);
+		let mut idx = BTreeIndex::new(tx.clone(), "btidx", leaf_layout.clone())?;
+		// 24 entries fit in a leaf block; the keys come in no particular order
+		for i in 0..100 {
+			let key = (i * 37) % 100;
+			idx.insert(&Constant::Int(key), RID::new(key as u64, 0))?;
+		}
+		// more entries of one key than a block holds go to overflow blocks
+		for i in 1..=30 {
+			idx.insert(&Constant::Int(50), RID::new(50, i))?;
+		}
+		assert!(tx.lock().unwrap().size("btidxleaf")? > 1);
+
+		for key in 0..100 {
+			let expected = if key == 50 {
+				(0..=30).map(|i| RID::new(50, i)).collect::<Vec<_>>()
+			} else {
+				vec![RID::new(key as u64, 0)]
+			};
+			assert_eq!(lookup(&mut idx, key)?, expected);
+		}
+		assert!(lookup(&mut idx, 100)?.is_empty());
+
+		// every leaf block keeps its entries sorted
+		let numleaves = tx.lock().unwrap().size("btidxleaf")?;
+		for blknum in 0..numleaves {
+			let blk = BlockId::new("btidxleaf", blknum);
+			let mut page = BTreePage::new(tx.clone(), blk, leaf_layout.clone())?;
+			let keys = (0..page.get_num_recs()?)
+				.map(|slot| page.get_data_val(slot))
+				.collect::<Result<Vec<_>>>()?;
+			assert!(keys.windows(2).all(|w| w[0] <= w[1]));
+			page.close()?;
+		}
+
+		idx.delete(&Constant::Int(7), RID::new(7, 0))?;
+		assert!(lookup(&mut idx, 7)?.is_empty());
+		assert_eq!(lookup(&mut idx, 8)?, [RID::new(8, 0)]);
+		idx.close()?;
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,136 @@
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	file::block_id::BlockId,
+	query::constant::Constant,
+	record::{layout::Layout, rid::RID},
+	tx::transaction::Transaction,
+};
+
+use super::btree_page::{BTreePage, DirEntry};
+
+// A cursor over the entries of one search key in a leaf block
+// and the overflow blocks chained to it.
+pub struct BTreeLeaf {
+	tx: Arc<Mutex<Transaction>>,
+	layout: Arc<Layout>,
+	searchkey: Constant,
+	contents: BTreePage,
+	currentslot: i32,
+	filename: String,
+}
+
+impl BTreeLeaf {
+	pub fn new(
+		tx: Arc<Mutex<Transaction>>,
+		blk: BlockId,
+		layout: Arc<Layout>,
+		searchkey: Constant,
+	) -> Result<Self> {
+		let filename = blk.file_name();
+		let contents = BTreePage::new(tx.clone(), blk, layout.clone())?;
+		let currentslot = contents.find_slot_before(&searchkey)?;
+
+		Ok(Self {
+			tx,
+			layout,
+			searchkey,
+			contents,
+			currentslot,
+			filename,
+		})
+	}
+
+	pub fn close(&mut self) -> Result<()> {
+		self.contents.close()
+	}
+
+	// moves to the next entry of the search key; false when there is none left
+	#[allow(clippy::should_implement_trait)]
+	pub fn next(&mut self) -> Result<bool> {
+		self.currentslot += 1;
+		if self.currentslot >= self.contents.get_num_recs()? {
+			self.try_overflow()
+		} else if self.contents.get_data_val(self.currentslot)? == self.searchkey {
+			Ok(true)
+		} else {
+			self.try_overflow()
+		}
+	}
+
+	pub fn get_data_rid(&self) -> Result<RID> {
+		self.contents.get_data_rid(self.currentslot)
+	}
+
+	pub fn delete(&mut self, datarid: RID) -> Result<()> {
+		while self.next()? {
+			if self.get_data_rid()? == datarid {
+				return self.contents.delete(self.currentslot);
+			}
+		}
+
+		Ok(())
+	}
+
+	// Inserts an entry for the search key.
+	// If the block had to be split, returns the entry of the new block for the directory.
+	pub fn insert(&mut self, datarid: RID) -> Result<Option<DirEntry>> {
+		// an overflow block whose key is greater than the new one:
+		// its entries move to a new block, and the new entry is put in this one
+		if self.contents.get_flag()? >= 0 && self.contents.get_data_val(0)? > self.searchkey {
+			let firstval = self.contents.get_data_val(0)?;
+			let newblk = self.contents.split(0, self.contents.get_flag()?)?;
+			self.currentslot = 0;
+			self.contents.set_flag(-1)?;
+			self.contents.insert_leaf(self.currentslot, &self.searchkey, datarid)?;
+			return Ok(Some(DirEntry::new(firstval, newblk.number())));
+		}
+
+		self.currentslot += 1;
+		self.contents.insert_leaf(self.currentslot, &self.searchkey, datarid)?;
+		if !self.contents.is_full()? {
+			return Ok(None);
+		}
+
+		let firstkey = self.contents.get_data_val(0)?;
+		let lastkey = self.contents.get_data_val(self.contents.get_num_recs()? - 1)?;
+		if lastkey == firstkey {
+			// all the entries have the same key: chain an overflow block
+			let newblk = self.contents.split(1, self.contents.get_flag()?)?;
+			self.contents.set_flag(newblk.number() as i32)?;
+			return Ok(None);
+		}
+
+		// split so that the entries of a key stay in one block
+		let mut splitpos = self.contents.get_num_recs()? / 2;
+		let mut splitkey = self.contents.get_data_val(splitpos)?;
+		if splitkey == firstkey {
+			while self.contents.get_data_val(splitpos)? == splitkey {
+				splitpos += 1;
+			}
+			splitkey = self.contents.get_data_val(splitpos)?;
+		} else {
+			while self.contents.get_data_val(splitpos - 1)? == splitkey {
+				splitpos -= 1;
+			}
+		}
+		let newblk = self.contents.split(splitpos, -1)?;
+
+		Ok(Some(DirEntry::new(splitkey, newblk.number())))
+	}
+
+	fn try_overflow(&mut self) -> Result<bool> {
+		let firstkey = self.contents.get_data_val(0)?;
+		let flag = self.contents.get_flag()?;
+		if self.searchkey != firstkey || flag < 0 {
+			return Ok(false);
+		}
+		self.contents.close()?;
+		let nextblk = BlockId::new(&self.filename, flag as u64);
+		self.contents = BTreePage::new(self.tx.clone(), nextblk, self.layout.clone())?;
+		self.currentslot = 0;
+
+		Ok(true)
+	}
+}
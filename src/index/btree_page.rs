@@ -0,0 +1,278 @@
+use anyhow::Result;
+use core::fmt;
+use std::{
+	mem,
+	sync::{Arc, Mutex},
+};
+
+use crate::{
+	file::block_id::BlockId,
+	query::constant::Constant,
+	record::{layout::Layout, rid::RID, schema::FieldType},
+	tx::transaction::Transaction,
+};
+
+#[derive(Debug)]
+enum BTreePageError {
+	Closed,
+}
+
+impl std::error::Error for BTreePageError {}
+impl fmt::Display for BTreePageError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			BTreePageError::Closed => {
+				write!(f, "btree page already closed")
+			}
+		}
+	}
+}
+
+// An entry of a directory page: the first key of a child block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+	dataval: Constant,
+	blocknum: u64,
+}
+
+impl DirEntry {
+	pub fn new(dataval: Constant, blocknum: u64) -> Self {
+		Self { dataval, blocknum }
+	}
+
+	pub fn data_val(&self) -> &Constant {
+		&self.dataval
+	}
+	pub fn block_number(&self) -> u64 {
+		self.blocknum
+	}
+}
+
+// A block of a B-tree, holding its records sorted by dataval.
+// The block starts with a flag and the number of records:
+// - for a directory page, the flag is its level (0 right above the leaves),
+// - for a leaf page, it is the block of its overflow chain, or -1.
+pub struct BTreePage {
+	tx: Arc<Mutex<Transaction>>,
+	// None once the page is closed
+	currentblk: Option<BlockId>,
+	layout: Arc<Layout>,
+}
+
+const FLAG_POS: i32 = 0;
+const NUMRECS_POS: i32 = mem::size_of::<i32>() as i32;
+const HEADER_SIZE: i32 = 2 * mem::size_of::<i32>() as i32;
+
+impl BTreePage {
+	pub fn new(tx: Arc<Mutex<Transaction>>, currentblk: BlockId, layout: Arc<Layout>) -> Result<Self> {
+		tx.lock().unwrap().pin(&currentblk)?;
+
+		Ok(Self {
+			tx,
+			currentblk: Some(currentblk),
+			layout,
+		})
+	}
+
+	// the last slot whose key is less than searchkey, or -1
+	pub fn find_slot_before(&self, searchkey: &Constant) -> Result<i32> {
+		let mut slot = 0;
+		while slot < self.get_num_recs()? && self.get_data_val(slot)? < *searchkey {
+			slot += 1;
+		}
+
+		Ok(slot - 1)
+	}
+
+	pub fn close(&mut self) -> Result<()> {
+		if let Some(blk) = self.currentblk.take() {
+			self.tx.lock().unwrap().unpin(&blk)?;
+		}
+
+		Ok(())
+	}
+
+	pub fn is_full(&self) -> Result<bool> {
+		let blocksize = self.tx.lock().unwrap().block_size();
+		Ok(self.slotpos(self.get_num_recs()? + 1) as u64 >= blocksize)
+	}
+
+	// moves the records from splitpos on into a new block, and returns the block
+	pub fn split(&self, splitpos: i32, flag: i32) -> Result<BlockId> {
+		let newblk = self.append_new(flag)?;
+		let mut newpage = BTreePage::new(self.tx.clone(), newblk.clone(), self.layout.clone())?;
+		self.transfer_recs(splitpos, &newpage)?;
+		newpage.set_flag(flag)?;
+		newpage.close()?;
+
+		Ok(newblk)
+	}
+
+	pub fn get_data_val(&self, slot: i32) -> Result<Constant> {
+		self.get_val(slot, "dataval")
+	}
+
+	pub fn get_flag(&self) -> Result<i32> {
+		let blk = self.blk()?;
+		self.tx.lock().unwrap().get_i32(blk, FLAG_POS)
+	}
+
+	pub fn set_flag(&self, val: i32) -> Result<()> {
+		let blk = self.blk()?;
+		self.tx.lock().unwrap().set_i32(blk, FLAG_POS, val, true)
+	}
+
+	// appends a new, formatted block to the file of this page
+	pub fn append_new(&self, flag: i32) -> Result<BlockId> {
+		let blk = self.tx.lock().unwrap().append(&self.blk()?.file_name())?;
+		self.tx.lock().unwrap().pin(&blk)?;
+		self.format(&blk, flag)?;
+		self.tx.lock().unwrap().unpin(&blk)?;
+
+		Ok(blk)
+	}
+
+	// Not logged, as with RecordPage::format.
+	pub fn format(&self, blk: &BlockId, flag: i32) -> Result<()> {
+		let mut tx = self.tx.lock().unwrap();
+		tx.set_i32(blk, FLAG_POS, flag, false)?;
+		tx.set_i32(blk, NUMRECS_POS, 0, false)?;
+		let recsize = self.layout.slot_size() as i32;
+		let blocksize = tx.block_size() as i32;
+		let sch = self.layout.schema();
+		let mut pos = HEADER_SIZE;
+		while pos + recsize <= blocksize {
+			for fldname in sch.fields() {
+				let fldpos = pos + self.layout.offset(fldname)? as i32;
+				match sch.field_type(fldname)? {
+					FieldType::INTEGER => tx.set_i32(blk, fldpos, 0, false)?,
+					FieldType::VARCHAR => tx.set_string(blk, fldpos, "", false)?,
+				}
+			}
+			pos += recsize;
+		}
+
+		Ok(())
+	}
+
+	// for directory pages
+
+	pub fn get_child_num(&self, slot: i32) -> Result<u64> {
+		Ok(self.get_i32(slot, "block")? as u64)
+	}
+
+	pub fn insert_dir(&self, slot: i32, val: &Constant, blknum: u64) -> Result<()> {
+		self.insert(slot)?;
+		self.set_val(slot, "dataval", val)?;
+		self.set_i32(slot, "block", blknum as i32)
+	}
+
+	// for leaf pages
+
+	pub fn get_data_rid(&self, slot: i32) -> Result<RID> {
+		Ok(RID::new(self.get_i32(slot, "block")? as u64, self.get_i32(slot, "id")?))
+	}
+
+	pub fn insert_leaf(&self, slot: i32, val: &Constant, rid: RID) -> Result<()> {
+		self.insert(slot)?;
+		self.set_val(slot, "dataval", val)?;
+		self.set_i32(slot, "block", rid.block_number() as i32)?;
+		self.set_i32(slot, "id", rid.slot())
+	}
+
+	pub fn delete(&self, slot: i32) -> Result<()> {
+		let numrecs = self.get_num_recs()?;
+		for i in slot + 1..numrecs {
+			self.copy_record(i, i - 1)?;
+		}
+		self.set_num_recs(numrecs - 1)
+	}
+
+	pub fn get_num_recs(&self) -> Result<i32> {
+		let blk = self.blk()?;
+		self.tx.lock().unwrap().get_i32(blk, NUMRECS_POS)
+	}
+
+	pub fn block(&self) -> Result<&BlockId> {
+		self.blk()
+	}
+
+	fn blk(&self) -> Result<&BlockId> {
+		match &self.currentblk {
+			Some(blk) => Ok(blk),
+			None => Err(From::from(BTreePageError::Closed)),
+		}
+	}
+
+	fn get_i32(&self, slot: i32, fldname: &str) -> Result<i32> {
+		let pos = self.fldpos(slot, fldname)?;
+		self.tx.lock().unwrap().get_i32(self.blk()?, pos)
+	}
+
+	fn get_val(&self, slot: i32, fldname: &str) -> Result<Constant> {
+		let pos = self.fldpos(slot, fldname)?;
+		let mut tx = self.tx.lock().unwrap();
+		match self.layout.schema().field_type(fldname)? {
+			FieldType::INTEGER => Ok(Constant::Int(tx.get_i32(self.blk()?, pos)?)),
+			FieldType::VARCHAR => Ok(Constant::Str(tx.get_string(self.blk()?, pos)?)),
+		}
+	}
+
+	fn set_i32(&self, slot: i32, fldname: &str, val: i32) -> Result<()> {
+		let pos = self.fldpos(slot, fldname)?;
+		self.tx.lock().unwrap().set_i32(self.blk()?, pos, val, true)
+	}
+
+	fn set_val(&self, slot: i32, fldname: &str, val: &Constant) -> Result<()> {
+		let pos = self.fldpos(slot, fldname)?;
+		let mut tx = self.tx.lock().unwrap();
+		match self.layout.schema().field_type(fldname)? {
+			FieldType::INTEGER => tx.set_i32(self.blk()?, pos, val.as_i32()?, true),
+			FieldType::VARCHAR => tx.set_string(self.blk()?, pos, val.as_string()?, true),
+		}
+	}
+
+	fn set_num_recs(&self, n: i32) -> Result<()> {
+		let blk = self.blk()?;
+		self.tx.lock().unwrap().set_i32(blk, NUMRECS_POS, n, true)
+	}
+
+	// makes room for a record at slot by shifting the later ones
+	fn insert(&self, slot: i32) -> Result<()> {
+		let numrecs = self.get_num_recs()?;
+		for i in (slot + 1..=numrecs).rev() {
+			self.copy_record(i - 1, i)?;
+		}
+		self.set_num_recs(numrecs + 1)
+	}
+
+	fn copy_record(&self, from: i32, to: i32) -> Result<()> {
+		for fldname in self.layout.schema().fields() {
+			self.set_val(to, fldname, &self.get_val(from, fldname)?)?;
+		}
+
+		Ok(())
+	}
+
+	fn transfer_recs(&self, slot: i32, dest: &BTreePage) -> Result<()> {
+		let mut destslot = 0;
+		while slot < self.get_num_recs()? {
+			dest.insert(destslot)?;
+			for fldname in self.layout.schema().fields() {
+				dest.set_val(destslot, fldname, &self.get_val(slot, fldname)?)?;
+			}
+			self.delete(slot)?;
+			destslot += 1;
+		}
+
+		Ok(())
+	}
+
+	fn fldpos(&self, slot: i32, fldname: &str) -> Result<i32> {
+		Ok(self.slotpos(slot) + self.layout.offset(fldname)? as i32)
+	}
+
+	fn slotpos(&self, slot: i32) -> i32 {
+		HEADER_SIZE + slot * self.layout.slot_size() as i32
+	}
+}
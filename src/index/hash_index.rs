@@ -0,0 +1,189 @@
+use anyhow::Result;
+use core::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	query::{
+		constant::Constant,
+		scan::{Scan, UpdateScan},
+	},
+	record::{layout::Layout, rid::RID, table_scan::TableScan},
+	tx::transaction::Transaction,
+};
+
+use super::Index;
+
+pub const NUM_BUCKETS: u64 = 100;
+
+#[derive(Debug)]
+enum HashIndexError {
+	NotPositioned,
+}
+
+impl std::error::Error for HashIndexError {}
+impl fmt::Display for HashIndexError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			HashIndexError::NotPositioned => {
+				write!(f, "hash index is not positioned on a search key")
+			}
+		}
+	}
+}
+
+// A static hash index: the entries are spread over NUM_BUCKETS tables,
+// named "<idxname><bucket>", by the hash of their key.
+pub struct HashIndex {
+	tx: Arc<Mutex<Transaction>>,
+	idxname: String,
+	layout: Arc<Layout>,
+	searchkey: Option<Constant>,
+	ts: Option<TableScan>,
+}
+
+impl HashIndex {
+	pub fn new(tx: Arc<Mutex<Transaction>>, idxname: &str, layout: Arc<Layout>) -> Self {
+		Self {
+			tx,
+			idxname: idxname.to_string(),
+			layout,
+			searchkey: None,
+			ts: None,
+		}
+	}
+
+	// The bucket files stay on disk, so the hash must not depend on the process.
+	fn bucket(key: &Constant) -> u64 {
+		let hash = match key {
+			Constant::Int(i) => *i as u64,
+			Constant::Str(s) => s.bytes().fold(0u64, |h, b| h.wrapping_mul(31).wrapping_add(b as u64)),
+		};
+		hash % NUM_BUCKETS
+	}
+
+	fn ts(&mut self) -> Result<&mut TableScan> {
+		match &mut self.ts {
+			Some(ts) => Ok(ts),
+			None => Err(From::from(HashIndexError::NotPositioned)),
+		}
+	}
+}
+
+impl Index for HashIndex {
+	fn before_first(&mut self, searchkey: &Constant) -> Result<()> {
+		self.close()?;
+		let tblname = format!("{}{}", self.idxname, Self::bucket(searchkey));
+		self.ts = Some(TableScan::new(self.tx.clone(), &tblname, self.layout.clone())?);
+		self.searchkey = Some(searchkey.clone());
+
+		Ok(())
+	}
+
+	fn next(&mut self) -> Result<bool> {
+		let searchkey = self.searchkey.clone();
+		let ts = self.ts()?;
+		while ts.next()? {
+			if Some(ts.get_val("dataval")?) == searchkey {
+				return Ok(true);
+			}
+		}
+
+		Ok(false)
+	}
+
+	fn get_data_rid(&self) -> Result<RID> {
+		match &self.ts {
+			Some(ts) => {
+				let blknum = ts.get_i32("block")?;
+				let id = ts.get_i32("id")?;
+				Ok(RID::new(blknum as u64, id))
+			}
+			None => Err(From::from(HashIndexError::NotPositioned)),
+		}
+	}
+
+	fn insert(&mut self, dataval: &Constant, datarid: RID) -> Result<()> {
+		self.before_first(dataval)?;
+		let ts = self.ts()?;
+		ts.insert()?;
+		ts.set_i32("block", datarid.block_number() as i32)?;
+		ts.set_i32("id", datarid.slot())?;
+		ts.set_val("dataval", dataval)
+	}
+
+	fn delete(&mut self, dataval: &Constant, datarid: RID) -> Result<()> {
+		self.before_first(dataval)?;
+		while self.next()? {
+			if self.get_data_rid()? == datarid {
+				return self.ts()?.delete();
+			}
+		}
+
+		Ok(())
+	}
+
+	fn close(&mut self) -> Result<()> {
+		if let Some(mut ts) = self.ts.take() {
+			ts.close()?;
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::fs;
+
+	use crate::{
+		buffer::manager::BufferMgr,
+		file::manager::FileMgr,
+		index::index_layout,
+		log::manager::LogMgr,
+		record::schema::FieldType,
+	};
+
+	#[test]
+	fn test_lookup_returns_inserted_rids() -> Result<()> {
+		let dir = "indextest/hashindex";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+
+		let layout = Arc::new(index_layout(FieldType::VARCHAR, 10)?);
+		let mut idx = HashIndex::new(tx.clone(), "nameidx", layout);
+		for i in 0..30 {
+			let key = Constant::from(format!("name{}", i % 10).as_str());
+			idx.insert(&key, RID::new(i / 10, i as i32))?;
+		}
+
+		let key = Constant::from("name3");
+		idx.before_first(&key)?;
+		let mut rids = vec![];
+		while idx.next()? {
+			rids.push(idx.get_data_rid()?);
+		}
+		assert_eq!(rids, [RID::new(0, 3), RID::new(1, 13), RID::new(2, 23)]);
+
+		idx.delete(&key, RID::new(1, 13))?;
+		idx.before_first(&key)?;
+		let mut rids = vec![];
+		while idx.next()? {
+			rids.push(idx.get_data_rid()?);
+		}
+		assert_eq!(rids, [RID::new(0, 3), RID::new(2, 23)]);
+
+		idx.before_first(&Constant::from("none"))?;
+		assert!(!idx.next()?);
+		idx.close()?;
+		assert!(idx.next().is_err());
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
@@ -1,8 +1,16 @@
 pub mod types;
+pub mod api;
 pub mod buffer;
 pub mod file;
 pub mod log;
+pub mod materialize;
 pub mod tx;
+pub mod record;
+pub mod query;
+pub mod metadata;
+pub mod index;
+pub mod parse;
+pub mod plan;
 
 #[cfg(test)]
 mod tests {
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use core::fmt;
+use std::collections::BTreeSet;
 use std::mem;
 use std::sync::{Arc, Mutex};
 
@@ -12,6 +13,7 @@ use super::iterator::LogIterator;
 #[derive(Debug)]
 enum LogMgrError {
 	LogPageAccessFailed,
+	RecordTooLarge(usize),
 }
 
 impl std::error::Error for LogMgrError {}
@@ -19,6 +21,9 @@ impl fmt::Display for LogMgrError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		match self {
 			LogMgrError::LogPageAccessFailed => write!(f, "log access failed"),
+			LogMgrError::RecordTooLarge(size) => {
+				write!(f, "log record of {} bytes does not fit in a block", size)
+			}
 		}
 	}
 }
@@ -32,6 +37,11 @@ pub struct LogMgr {
 	// latest log sequence number
 	latest_lsn: u64,
 	last_saved_lsn: u64,
+	// a checkpoint is due once this many records follow the last one
+	checkpoint_interval: Option<u64>,
+	checkpoint_lsn: u64,
+	// the txs of this log that have written START but neither COMMIT nor ROLLBACK yet
+	active_txs: Arc<Mutex<BTreeSet<i32>>>,
 }
 
 impl LogMgr {
@@ -55,6 +65,9 @@ impl LogMgr {
 				current_blk: blk,
 				latest_lsn: 0,
 				last_saved_lsn: 0,
+				checkpoint_interval: None,
+				checkpoint_lsn: 0,
+				active_txs: Arc::new(Mutex::new(BTreeSet::new())),
 			};
 		} else {
 			let newblk = BlockId::new(logfile, logsize - 1);
@@ -68,6 +81,9 @@ impl LogMgr {
 				current_blk: newblk,
 				latest_lsn: 0,
 				last_saved_lsn: 0,
+				checkpoint_interval: None,
+				checkpoint_lsn: 0,
+				active_txs: Arc::new(Mutex::new(BTreeSet::new())),
 			};
 		}
 
@@ -94,6 +110,10 @@ impl LogMgr {
 		let recsize = logrec.len() as i32;
 		let int32_size = mem::size_of::<i32>() as i32;
 		let bytes_needed = recsize + int32_size;
+		// even an empty block keeps its boundary in the first 4 bytes
+		if bytes_needed + int32_size > self.logpage.contents().len() as i32 {
+			return Err(From::from(LogMgrError::RecordTooLarge(logrec.len())));
+		}
 
 		if boundary - bytes_needed < int32_size {
 			self.flush_to_fm()?;
@@ -106,13 +126,43 @@ impl LogMgr {
 		self.logpage.set_i32(0, recpos as i32)?;
 		self.latest_lsn += 1;
 
-		Ok(self.last_saved_lsn)
+		Ok(self.latest_lsn)
+	}
+
+	pub fn last_saved_lsn(&self) -> u64 {
+		self.last_saved_lsn
+	}
+
+	pub fn latest_lsn(&self) -> u64 {
+		self.latest_lsn
+	}
+
+	pub fn set_checkpoint_interval(&mut self, records: u64) {
+		self.checkpoint_interval = Some(records);
+	}
+
+	// the number of records appended since the last checkpoint
+	pub fn record_count(&self) -> u64 {
+		self.latest_lsn - self.checkpoint_lsn
+	}
+
+	pub fn needs_checkpoint(&self) -> bool {
+		self.checkpoint_interval.is_some_and(|n| self.record_count() >= n)
+	}
+
+	pub fn checkpointed(&mut self, lsn: u64) {
+		self.checkpoint_lsn = lsn;
+	}
+
+	pub fn active_txs(&self) -> Arc<Mutex<BTreeSet<i32>>> {
+		Arc::clone(&self.active_txs)
 	}
 
 	fn flush_to_fm(&mut self) -> Result<()> {
 		let mut filemgr = self.fm.lock().unwrap();
 
 		filemgr.write(&self.current_blk, &mut self.logpage)?;
+		self.last_saved_lsn = self.latest_lsn;
 
 		Ok(())
 	}
@@ -158,6 +208,30 @@ mod tests {
 		let _ = assert_log_records(&mut lm, 70, 1);
 	}
 
+	#[test]
+	fn flush_advances_last_saved_lsn() {
+		let fm = FileMgr::new("logtest/lsn", 400).unwrap();
+		let mut lm = LogMgr::new(Arc::new(Mutex::new(fm)), LOG_FILE).unwrap();
+		let mut rec = create_log_record("record", 100).unwrap();
+
+		let lsn1 = lm.append(&mut rec).unwrap();
+		let lsn2 = lm.append(&mut rec).unwrap();
+		assert_eq!(lsn1 + 1, lsn2);
+		assert!(lm.last_saved_lsn() < lsn1);
+
+		lm.flush(lsn1).unwrap();
+		assert!(lm.last_saved_lsn() >= lsn2);
+	}
+
+	#[test]
+	fn append_rejects_record_larger_than_block() {
+		let fm = FileMgr::new("logtest/large", 400).unwrap();
+		let mut lm = LogMgr::new(Arc::new(Mutex::new(fm)), LOG_FILE).unwrap();
+
+		assert!(lm.append(&mut vec![0u8; 393]).is_err());
+		assert!(lm.append(&mut vec![0u8; 392]).is_ok());
+	}
+
 	fn print_log_records(lm: &mut LogMgr, msg: &str) -> Result<()> {
 		println!("{}", msg);
 		let iter = lm.iterator()?;
@@ -0,0 +1,4 @@
+pub mod aggregation_fn;
+pub mod group_by;
+pub mod sort;
+pub mod temp_table;
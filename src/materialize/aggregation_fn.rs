@@ -0,0 +1,221 @@
+use anyhow::Result;
+use core::fmt;
+
+use crate::{
+	query::{constant::Constant, scan::Scan},
+	record::schema::Schema,
+};
+
+#[derive(Debug)]
+enum AggregationFnError {
+	SumOverflow(String),
+}
+
+impl std::error::Error for AggregationFnError {}
+impl fmt::Display for AggregationFnError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			AggregationFnError::SumOverflow(fldname) => {
+				write!(f, "sum of {} overflows an integer", fldname)
+			}
+		}
+	}
+}
+
+// An aggregate over the records of a group.
+// process_first starts a new group, process_next adds a record to it.
+pub trait AggregationFn {
+	fn process_first(&mut self, s: &dyn Scan) -> Result<()>;
+	fn process_next(&mut self, s: &dyn Scan) -> Result<()>;
+	// the name of the output field
+	fn field_name(&self) -> String;
+	// adds the output field to out, an integer unless the aggregate
+	// takes the type of its field in the input schema
+	fn add_field(&self, out: &mut Schema, _input: &Schema) -> Result<()> {
+		out.add_i32_field(&self.field_name());
+		Ok(())
+	}
+	// None until a group is processed
+	fn value(&self) -> Option<Constant>;
+	// the same aggregate, with no group processed
+	fn box_clone(&self) -> Box<dyn AggregationFn>;
+}
+
+pub struct CountFn {
+	fldname: String,
+	count: Option<i32>,
+}
+
+impl CountFn {
+	pub fn new(fldname: &str) -> Self {
+		Self {
+			fldname: fldname.to_string(),
+			count: None,
+		}
+	}
+}
+
+impl AggregationFn for CountFn {
+	fn process_first(&mut self, _s: &dyn Scan) -> Result<()> {
+		self.count = Some(1);
+		Ok(())
+	}
+
+	fn process_next(&mut self, _s: &dyn Scan) -> Result<()> {
+		self.count = self.count.map(|c| c + 1);
+		Ok(())
+	}
+
+	fn field_name(&self) -> String {
+		format!("countof{}", self.fldname)
+	}
+
+	fn value(&self) -> Option<Constant> {
+		self.count.map(Constant::Int)
+	}
+
+	fn box_clone(&self) -> Box<dyn AggregationFn> {
+		Box::new(Self::new(&self.fldname))
+	}
+}
+
+pub struct MaxFn {
+	fldname: String,
+	val: Option<Constant>,
+}
+
+impl MaxFn {
+	pub fn new(fldname: &str) -> Self {
+		Self {
+			fldname: fldname.to_string(),
+			val: None,
+		}
+	}
+}
+
+impl AggregationFn for MaxFn {
+	fn process_first(&mut self, s: &dyn Scan) -> Result<()> {
+		self.val = Some(s.get_val(&self.fldname)?);
+		Ok(())
+	}
+
+	fn process_next(&mut self, s: &dyn Scan) -> Result<()> {
+		let newval = s.get_val(&self.fldname)?;
+		if self.val.as_ref().is_none_or(|val| newval > *val) {
+			self.val = Some(newval);
+		}
+		Ok(())
+	}
+
+	fn field_name(&self) -> String {
+		format!("maxof{}", self.fldname)
+	}
+
+	fn add_field(&self, out: &mut Schema, input: &Schema) -> Result<()> {
+		let fldtype = input.field_type(&self.fldname)?;
+		let length = input.field_length(&self.fldname)?;
+		out.add_field(&self.field_name(), fldtype, length);
+		Ok(())
+	}
+
+	fn value(&self) -> Option<Constant> {
+		self.val.clone()
+	}
+
+	fn box_clone(&self) -> Box<dyn AggregationFn> {
+		Box::new(Self::new(&self.fldname))
+	}
+}
+
+pub struct MinFn {
+	fldname: String,
+	val: Option<Constant>,
+}
+
+impl MinFn {
+	pub fn new(fldname: &str) -> Self {
+		Self {
+			fldname: fldname.to_string(),
+			val: None,
+		}
+	}
+}
+
+impl AggregationFn for MinFn {
+	fn process_first(&mut self, s: &dyn Scan) -> Result<()> {
+		self.val = Some(s.get_val(&self.fldname)?);
+		Ok(())
+	}
+
+	fn process_next(&mut self, s: &dyn Scan) -> Result<()> {
+		let newval = s.get_val(&self.fldname)?;
+		if self.val.as_ref().is_none_or(|val| newval < *val) {
+			self.val = Some(newval);
+		}
+		Ok(())
+	}
+
+	fn field_name(&self) -> String {
+		format!("minof{}", self.fldname)
+	}
+
+	fn add_field(&self, out: &mut Schema, input: &Schema) -> Result<()> {
+		let fldtype = input.field_type(&self.fldname)?;
+		let length = input.field_length(&self.fldname)?;
+		out.add_field(&self.field_name(), fldtype, length);
+		Ok(())
+	}
+
+	fn value(&self) -> Option<Constant> {
+		self.val.clone()
+	}
+
+	fn box_clone(&self) -> Box<dyn AggregationFn> {
+		Box::new(Self::new(&self.fldname))
+	}
+}
+
+// The field must be an integer.
+pub struct SumFn {
+	fldname: String,
+	sum: Option<i32>,
+}
+
+impl SumFn {
+	pub fn new(fldname: &str) -> Self {
+		Self {
+			fldname: fldname.to_string(),
+			sum: None,
+		}
+	}
+}
+
+impl AggregationFn for SumFn {
+	fn process_first(&mut self, s: &dyn Scan) -> Result<()> {
+		self.sum = Some(s.get_i32(&self.fldname)?);
+		Ok(())
+	}
+
+	fn process_next(&mut self, s: &dyn Scan) -> Result<()> {
+		let val = s.get_i32(&self.fldname)?;
+		let sum = self
+			.sum
+			.unwrap_or(0)
+			.checked_add(val)
+			.ok_or_else(|| AggregationFnError::SumOverflow(self.fldname.clone()))?;
+		self.sum = Some(sum);
+		Ok(())
+	}
+
+	fn field_name(&self) -> String {
+		format!("sumof{}", self.fldname)
+	}
+
+	fn value(&self) -> Option<Constant> {
+		self.sum.map(Constant::Int)
+	}
+
+	fn box_clone(&self) -> Box<dyn AggregationFn> {
+		Box::new(Self::new(&self.fldname))
+	}
+}
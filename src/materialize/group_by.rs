@@ -0,0 +1,371 @@
+use anyhow::Result;
+use core::fmt;
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex},
+};
+
+use crate::{
+	plan::Plan,
+	query::{constant::Constant, scan::Scan},
+	record::schema::Schema,
+	tx::transaction::Transaction,
+};
+
+use super::{aggregation_fn::AggregationFn, sort::SortPlan};
+
+#[derive(Debug)]
+enum GroupByScanError {
+	FieldNotFound(String),
+	NotPositioned,
+}
+
+impl std::error::Error for GroupByScanError {}
+impl fmt::Display for GroupByScanError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			GroupByScanError::FieldNotFound(fldname) => write!(f, "field not found: {}", fldname),
+			GroupByScanError::NotPositioned => write!(f, "group by scan is not positioned on a group"),
+		}
+	}
+}
+
+// One record per group of the records of a plan, holding the group fields
+// and the aggregates over the group. The plan is sorted on the group fields first.
+pub struct GroupByPlan {
+	p: Box<dyn Plan>,
+	groupfields: Vec<String>,
+	aggfns: Vec<Box<dyn AggregationFn>>,
+	schema: Schema,
+}
+
+impl GroupByPlan {
+	pub fn new(
+		tx: Arc<Mutex<Transaction>>,
+		p: Box<dyn Plan>,
+		groupfields: Vec<String>,
+		aggfns: Vec<Box<dyn AggregationFn>>,
+	) -> Result<Self> {
+		let p: Box<dyn Plan> = Box::new(SortPlan::new(tx, p, groupfields.clone()));
+		let mut schema = Schema::new();
+		for fldname in groupfields.iter() {
+			schema.add(fldname, p.schema())?;
+		}
+		for aggfn in aggfns.iter() {
+			aggfn.add_field(&mut schema, p.schema())?;
+		}
+
+		Ok(Self {
+			p,
+			groupfields,
+			aggfns,
+			schema,
+		})
+	}
+}
+
+impl Plan for GroupByPlan {
+	fn open(&self) -> Result<Box<dyn Scan>> {
+		let s = self.p.open()?;
+		let aggfns = self.aggfns.iter().map(|aggfn| aggfn.box_clone()).collect();
+		Ok(Box::new(GroupByScan::new(s, self.groupfields.clone(), aggfns)?))
+	}
+
+	fn blocks_accessed(&self) -> u64 {
+		self.p.blocks_accessed()
+	}
+
+	// at most one group for each combination of the group values
+	fn records_output(&self) -> u64 {
+		self.groupfields
+			.iter()
+			.fold(1, |n, fldname| n.saturating_mul(self.p.distinct_values(fldname)))
+	}
+
+	fn distinct_values(&self, fldname: &str) -> u64 {
+		if self.p.schema().has_field(fldname) {
+			self.p.distinct_values(fldname)
+		} else {
+			self.records_output()
+		}
+	}
+
+	fn schema(&self) -> &Schema {
+		&self.schema
+	}
+}
+
+// The group its current record belongs to.
+fn group_value(s: &dyn Scan, groupfields: &[String]) -> Result<HashMap<String, Constant>> {
+	groupfields
+		.iter()
+		.map(|fldname| Ok((fldname.clone(), s.get_val(fldname)?)))
+		.collect()
+}
+
+// Groups the records of a scan sorted on the group fields.
+pub struct GroupByScan<S> {
+	s: S,
+	groupfields: Vec<String>,
+	aggfns: Vec<Box<dyn AggregationFn>>,
+	groupval: Option<HashMap<String, Constant>>,
+	moregroups: bool,
+}
+
+impl<S: Scan> GroupByScan<S> {
+	pub fn new(s: S, groupfields: Vec<String>, aggfns: Vec<Box<dyn AggregationFn>>) -> Result<Self> {
+		let mut s = Self {
+			s,
+			groupfields,
+			aggfns,
+			groupval: None,
+			moregroups: false,
+		};
+		s.before_first()?;
+
+		Ok(s)
+	}
+}
+
+impl<S: Scan> Scan for GroupByScan<S> {
+	fn before_first(&mut self) -> Result<()> {
+		self.groupval = None;
+		self.s.before_first()?;
+		self.moregroups = self.s.next()?;
+
+		Ok(())
+	}
+
+	// reads the records of the next group, leaving s on the first record of the group after
+	fn next(&mut self) -> Result<bool> {
+		if !self.moregroups {
+			return Ok(false);
+		}
+		for aggfn in self.aggfns.iter_mut() {
+			aggfn.process_first(&self.s)?;
+		}
+		let groupval = group_value(&self.s, &self.groupfields)?;
+		loop {
+			self.moregroups = self.s.next()?;
+			if !self.moregroups || group_value(&self.s, &self.groupfields)? != groupval {
+				break;
+			}
+			for aggfn in self.aggfns.iter_mut() {
+				aggfn.process_next(&self.s)?;
+			}
+		}
+		self.groupval = Some(groupval);
+
+		Ok(true)
+	}
+
+	fn get_i32(&self, fldname: &str) -> Result<i32> {
+		self.get_val(fldname)?.as_i32()
+	}
+
+	fn get_string(&self, fldname: &str) -> Result<String> {
+		Ok(self.get_val(fldname)?.as_string()?.to_string())
+	}
+
+	fn get_val(&self, fldname: &str) -> Result<Constant> {
+		let groupval = match &self.groupval {
+			Some(groupval) => groupval,
+			None => return Err(From::from(GroupByScanError::NotPositioned)),
+		};
+		if let Some(val) = groupval.get(fldname) {
+			return Ok(val.clone());
+		}
+		match self.aggfns.iter().find(|aggfn| aggfn.field_name() == fldname) {
+			Some(aggfn) => aggfn
+				.value()
+				.ok_or_else(|| From::from(GroupByScanError::NotPositioned)),
+			None => Err(From::from(GroupByScanError::FieldNotFound(fldname.to_string()))),
+		}
+	}
+
+	fn has_field(&self, fldname: &str) -> bool {
+		self.groupfields.iter().any(|f| f == fldname) || self.aggfns.iter().any(|aggfn| aggfn.field_name() == fldname)
+	}
+
+	fn close(&mut self) -> Result<()> {
+		self.s.close()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::fs;
+
+	use crate::{
+		buffer::manager::BufferMgr,
+		file::manager::FileMgr,
+		log::manager::LogMgr,
+		materialize::aggregation_fn::{CountFn, MaxFn, MinFn, SumFn},
+		metadata::metadata_mgr::MetadataMgr,
+		plan::table_plan::TablePlan,
+		query::scan::UpdateScan,
+		record::{schema::FieldType, table_scan::TableScan},
+	};
+
+	#[test]
+	fn test_group_counts_and_maxima() -> Result<()> {
+		let dir = "materializetest/groupby";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+		let mdm = MetadataMgr::new(true, tx.clone())?;
+
+		let mut sch = Schema::new();
+		sch.add_string_field("dept", 9);
+		sch.add_i32_field("salary");
+		mdm.create_table("emp", &sch, tx.clone())?;
+		let layout = Arc::new(mdm.get_layout("emp", tx.clone())?);
+		let mut ts = TableScan::new(tx.clone(), "emp", layout)?;
+		let depts = ["math", "art", "cs"];
+		for i in 0..60 {
+			ts.insert()?;
+			ts.set_string("dept", depts[i % 3])?;
+			ts.set_i32("salary", (i * 7 % 60) as i32)?;
+		}
+		ts.close()?;
+
+		let p = TablePlan::new(tx.clone(), "emp", &mdm)?;
+		let aggfns: Vec<Box<dyn AggregationFn>> = vec![
+			Box::new(CountFn::new("salary")),
+			Box::new(MaxFn::new("salary")),
+			Box::new(MinFn::new("salary")),
+			Box::new(SumFn::new("salary")),
+		];
+		let gp = GroupByPlan::new(tx.clone(), Box::new(p), vec!["dept".to_string()], aggfns)?;
+		assert_eq!(gp.schema().fields(), ["dept", "countofsalary", "maxofsalary", "minofsalary", "sumofsalary"]);
+
+		let mut s = gp.open()?;
+		let mut groups = vec![];
+		while s.next()? {
+			assert!(!s.has_field("salary"));
+			groups.push((
+				s.get_string("dept")?,
+				s.get_i32("countofsalary")?,
+				s.get_i32("maxofsalary")?,
+				s.get_i32("minofsalary")?,
+				s.get_i32("sumofsalary")?,
+			));
+		}
+		s.close()?;
+
+		// i*7 mod 60 takes each of 0..60 once, and keeps i mod 3
+		assert_eq!(
+			groups,
+			[
+				("art".to_string(), 20, 58, 1, 590),
+				("cs".to_string(), 20, 59, 2, 610),
+				("math".to_string(), 20, 57, 0, 570),
+			]
+		);
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_max_and_min_of_string_field() -> Result<()> {
+		let dir = "materializetest/groupbystring";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+		let mdm = MetadataMgr::new(true, tx.clone())?;
+
+		let mut sch = Schema::new();
+		sch.add_string_field("dept", 9);
+		sch.add_i32_field("grade");
+		mdm.create_table("emp", &sch, tx.clone())?;
+		let layout = Arc::new(mdm.get_layout("emp", tx.clone())?);
+		let mut ts = TableScan::new(tx.clone(), "emp", layout)?;
+		let depts = ["math", "art", "cs", "physics"];
+		for i in 0..20 {
+			ts.insert()?;
+			ts.set_string("dept", depts[i % 4])?;
+			ts.set_i32("grade", (i % 2) as i32)?;
+		}
+		ts.close()?;
+
+		let p = TablePlan::new(tx.clone(), "emp", &mdm)?;
+		let aggfns: Vec<Box<dyn AggregationFn>> = vec![
+			Box::new(CountFn::new("dept")),
+			Box::new(MaxFn::new("dept")),
+			Box::new(MinFn::new("dept")),
+		];
+		let gp = GroupByPlan::new(tx.clone(), Box::new(p), vec!["grade".to_string()], aggfns)?;
+		assert_eq!(gp.schema().field_type("countofdept")?, FieldType::INTEGER);
+		assert_eq!(gp.schema().field_type("maxofdept")?, FieldType::VARCHAR);
+		assert_eq!(gp.schema().field_length("maxofdept")?, 9);
+		assert_eq!(gp.schema().field_type("minofdept")?, FieldType::VARCHAR);
+
+		// the groups are copied into a temp table with the plan's layout
+		let sp = SortPlan::new(tx.clone(), Box::new(gp), vec!["maxofdept".to_string()]);
+		let mut s = sp.open()?;
+		let mut groups = vec![];
+		while s.next()? {
+			groups.push((
+				s.get_i32("grade")?,
+				s.get_i32("countofdept")?,
+				s.get_string("maxofdept")?,
+				s.get_string("minofdept")?,
+			));
+		}
+		s.close()?;
+
+		assert_eq!(
+			groups,
+			[
+				(0, 10, "math".to_string(), "cs".to_string()),
+				(1, 10, "physics".to_string(), "art".to_string()),
+			]
+		);
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_sum_overflow_is_an_error() -> Result<()> {
+		let dir = "materializetest/sumoverflow";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+		let mdm = MetadataMgr::new(true, tx.clone())?;
+
+		let mut sch = Schema::new();
+		sch.add_i32_field("salary");
+		mdm.create_table("emp", &sch, tx.clone())?;
+		let layout = Arc::new(mdm.get_layout("emp", tx.clone())?);
+		let mut ts = TableScan::new(tx.clone(), "emp", layout)?;
+		for _ in 0..2 {
+			ts.insert()?;
+			ts.set_i32("salary", i32::MAX)?;
+		}
+		ts.close()?;
+
+		// a single group holding every record
+		let p = TablePlan::new(tx.clone(), "emp", &mdm)?;
+		let aggfns: Vec<Box<dyn AggregationFn>> = vec![Box::new(SumFn::new("salary"))];
+		let gp = GroupByPlan::new(tx.clone(), Box::new(p), vec![], aggfns)?;
+		let mut s = gp.open()?;
+		assert!(s.next().is_err());
+		s.close()?;
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,361 @@
+use anyhow::Result;
+use core::fmt;
+use std::{
+	cmp::Ordering,
+	sync::{Arc, Mutex},
+};
+
+use crate::{
+	plan::Plan,
+	query::{
+		constant::Constant,
+		scan::{Scan, UpdateScan},
+	},
+	record::{layout::Layout, schema::Schema, table_scan::TableScan},
+	tx::transaction::Transaction,
+};
+
+use super::temp_table::TempTable;
+
+#[derive(Debug)]
+enum SortScanError {
+	NotPositioned,
+}
+
+impl std::error::Error for SortScanError {}
+impl fmt::Display for SortScanError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			SortScanError::NotPositioned => write!(f, "sort scan is not positioned on a record"),
+		}
+	}
+}
+
+// Orders the current records of two scans by the sort fields, in turn.
+#[derive(Debug, Clone)]
+pub struct RecordComparator {
+	fields: Vec<String>,
+}
+
+impl RecordComparator {
+	pub fn new(fields: Vec<String>) -> Self {
+		Self { fields }
+	}
+
+	pub fn compare(&self, s1: &dyn Scan, s2: &dyn Scan) -> Result<Ordering> {
+		for fldname in self.fields.iter() {
+			let ord = s1.get_val(fldname)?.cmp(&s2.get_val(fldname)?);
+			if ord != Ordering::Equal {
+				return Ok(ord);
+			}
+		}
+
+		Ok(Ordering::Equal)
+	}
+}
+
+// The records of a plan, sorted by external merge sort.
+// The sorted runs are materialized into temporary tables when the plan is opened.
+pub struct SortPlan {
+	tx: Arc<Mutex<Transaction>>,
+	p: Box<dyn Plan>,
+	comp: RecordComparator,
+}
+
+impl SortPlan {
+	pub fn new(tx: Arc<Mutex<Transaction>>, p: Box<dyn Plan>, sortfields: Vec<String>) -> Self {
+		Self {
+			tx,
+			p,
+			comp: RecordComparator::new(sortfields),
+		}
+	}
+
+	// each run is a maximal sequence of ascending records of src
+	fn split_into_runs(&self, src: &mut dyn Scan) -> Result<Vec<TempTable>> {
+		let mut temps = vec![];
+		src.before_first()?;
+		let mut currenttemp = TempTable::new(self.tx.clone(), self.p.schema())?;
+		let mut currentscan = currenttemp.open()?;
+		if src.next()? {
+			self.copy(src, &mut currentscan)?;
+			while src.next()? {
+				if self.comp.compare(src, &currentscan)? == Ordering::Less {
+					// the run is over
+					currentscan.close()?;
+					temps.push(currenttemp);
+					currenttemp = TempTable::new(self.tx.clone(), self.p.schema())?;
+					currentscan = currenttemp.open()?;
+				}
+				self.copy(src, &mut currentscan)?;
+			}
+		}
+		currentscan.close()?;
+		temps.push(currenttemp);
+
+		Ok(temps)
+	}
+
+	// merges the runs pairwise, halving their number
+	fn do_merge_iteration(&self, runs: Vec<TempTable>) -> Result<Vec<TempTable>> {
+		let mut result = vec![];
+		let mut runs = runs.into_iter();
+		while let Some(p1) = runs.next() {
+			match runs.next() {
+				Some(p2) => result.push(self.merge_two_runs(&p1, &p2)?),
+				None => result.push(p1),
+			}
+		}
+
+		Ok(result)
+	}
+
+	fn merge_two_runs(&self, p1: &TempTable, p2: &TempTable) -> Result<TempTable> {
+		let mut src1 = p1.open()?;
+		let mut src2 = p2.open()?;
+		let result = TempTable::new(self.tx.clone(), self.p.schema())?;
+		let mut dest = result.open()?;
+
+		let mut hasmore1 = src1.next()?;
+		let mut hasmore2 = src2.next()?;
+		while hasmore1 && hasmore2 {
+			if self.comp.compare(&src1, &src2)? == Ordering::Less {
+				self.copy(&src1, &mut dest)?;
+				hasmore1 = src1.next()?;
+			} else {
+				self.copy(&src2, &mut dest)?;
+				hasmore2 = src2.next()?;
+			}
+		}
+		while hasmore1 {
+			self.copy(&src1, &mut dest)?;
+			hasmore1 = src1.next()?;
+		}
+		while hasmore2 {
+			self.copy(&src2, &mut dest)?;
+			hasmore2 = src2.next()?;
+		}
+		src1.close()?;
+		src2.close()?;
+		dest.close()?;
+
+		Ok(result)
+	}
+
+	// appends the current record of src to dest
+	fn copy(&self, src: &dyn Scan, dest: &mut TableScan) -> Result<()> {
+		dest.insert()?;
+		for fldname in self.p.schema().fields() {
+			dest.set_val(fldname, &src.get_val(fldname)?)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl Plan for SortPlan {
+	fn open(&self) -> Result<Box<dyn Scan>> {
+		let mut src = self.p.open()?;
+		let mut runs = self.split_into_runs(src.as_mut())?;
+		src.close()?;
+		while runs.len() > 2 {
+			runs = self.do_merge_iteration(runs)?;
+		}
+
+		Ok(Box::new(SortScan::new(runs, self.comp.clone())?))
+	}
+
+	// the cost of reading the sorted records, not of sorting them
+	fn blocks_accessed(&self) -> u64 {
+		let slotsize = match Layout::new(self.p.schema().clone()) {
+			Ok(layout) => layout.slot_size() as u64,
+			Err(_) => return 0,
+		};
+		let blocksize = self.tx.lock().unwrap().block_size();
+		let rpb = (blocksize / slotsize).max(1);
+
+		self.p.records_output().div_ceil(rpb)
+	}
+
+	fn records_output(&self) -> u64 {
+		self.p.records_output()
+	}
+
+	fn distinct_values(&self, fldname: &str) -> u64 {
+		self.p.distinct_values(fldname)
+	}
+
+	fn schema(&self) -> &Schema {
+		self.p.schema()
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Side {
+	Left,
+	Right,
+}
+
+// Merges the last one or two sorted runs while they are scanned.
+pub struct SortScan {
+	s1: TableScan,
+	s2: Option<TableScan>,
+	currentscan: Option<Side>,
+	hasmore1: bool,
+	hasmore2: bool,
+	comp: RecordComparator,
+}
+
+impl SortScan {
+	// runs must hold one or two tables
+	pub fn new(runs: Vec<TempTable>, comp: RecordComparator) -> Result<Self> {
+		let s1 = runs[0].open()?;
+		let s2 = match runs.get(1) {
+			Some(run) => Some(run.open()?),
+			None => None,
+		};
+		let mut s = Self {
+			s1,
+			s2,
+			currentscan: None,
+			hasmore1: false,
+			hasmore2: false,
+			comp,
+		};
+		s.before_first()?;
+
+		Ok(s)
+	}
+
+	fn current(&self) -> Result<&TableScan> {
+		match (self.currentscan, &self.s2) {
+			(Some(Side::Left), _) => Ok(&self.s1),
+			(Some(Side::Right), Some(s2)) => Ok(s2),
+			_ => Err(From::from(SortScanError::NotPositioned)),
+		}
+	}
+}
+
+impl Scan for SortScan {
+	fn before_first(&mut self) -> Result<()> {
+		self.currentscan = None;
+		self.s1.before_first()?;
+		self.hasmore1 = self.s1.next()?;
+		if let Some(s2) = self.s2.as_mut() {
+			s2.before_first()?;
+			self.hasmore2 = s2.next()?;
+		}
+
+		Ok(())
+	}
+
+	fn next(&mut self) -> Result<bool> {
+		match (self.currentscan, self.s2.as_mut()) {
+			(Some(Side::Left), _) => self.hasmore1 = self.s1.next()?,
+			(Some(Side::Right), Some(s2)) => self.hasmore2 = s2.next()?,
+			_ => {}
+		}
+
+		self.currentscan = match (self.hasmore1, self.hasmore2, &self.s2) {
+			(false, false, _) => return Ok(false),
+			(true, true, Some(s2)) => {
+				if self.comp.compare(&self.s1, s2)? == Ordering::Less {
+					Some(Side::Left)
+				} else {
+					Some(Side::Right)
+				}
+			}
+			(true, _, _) => Some(Side::Left),
+			(false, true, _) => Some(Side::Right),
+		};
+
+		Ok(true)
+	}
+
+	fn get_i32(&self, fldname: &str) -> Result<i32> {
+		self.current()?.get_i32(fldname)
+	}
+
+	fn get_string(&self, fldname: &str) -> Result<String> {
+		self.current()?.get_string(fldname)
+	}
+
+	fn get_val(&self, fldname: &str) -> Result<Constant> {
+		self.current()?.get_val(fldname)
+	}
+
+	fn has_field(&self, fldname: &str) -> bool {
+		self.s1.has_field(fldname)
+	}
+
+	fn close(&mut self) -> Result<()> {
+		self.s1.close()?;
+		if let Some(s2) = self.s2.as_mut() {
+			s2.close()?;
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::fs;
+
+	use crate::{
+		buffer::manager::BufferMgr,
+		file::manager::FileMgr,
+		log::manager::LogMgr,
+		metadata::metadata_mgr::MetadataMgr,
+		plan::table_plan::TablePlan,
+	};
+
+	#[test]
+	fn test_sort_unsorted_records() -> Result<()> {
+		let dir = "materializetest/sort";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+		let mdm = MetadataMgr::new(true, tx.clone())?;
+
+		let mut sch = Schema::new();
+		sch.add_i32_field("grp");
+		sch.add_i32_field("a");
+		sch.add_string_field("b", 9);
+		mdm.create_table("t", &sch, tx.clone())?;
+		let layout = Arc::new(mdm.get_layout("t", tx.clone())?);
+		let mut ts = TableScan::new(tx.clone(), "t", layout)?;
+		for i in 0..300 {
+			let a = (i * 37) % 300;
+			ts.insert()?;
+			ts.set_i32("grp", a % 7)?;
+			ts.set_i32("a", a)?;
+			ts.set_string("b", &format!("rec{}", a))?;
+		}
+		ts.close()?;
+
+		let p = TablePlan::new(tx.clone(), "t", &mdm)?;
+		let sp = SortPlan::new(tx.clone(), Box::new(p), vec!["grp".to_string(), "a".to_string()]);
+		let mut s = sp.open()?;
+		let mut rows = vec![];
+		while s.next()? {
+			let a = s.get_i32("a")?;
+			assert_eq!(s.get_string("b")?, format!("rec{}", a));
+			rows.push((s.get_i32("grp")?, a));
+		}
+		s.close()?;
+
+		assert_eq!(rows.len(), 300);
+		assert!(rows.windows(2).all(|w| w[0] < w[1]));
+		assert_eq!(rows[0], (0, 0));
+		assert_eq!(rows[299], (6, 293));
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,44 @@
+use anyhow::Result;
+use std::sync::{
+	atomic::{AtomicI32, Ordering},
+	Arc, Mutex,
+};
+
+use crate::{
+	record::{layout::Layout, schema::Schema, table_scan::TableScan},
+	tx::transaction::Transaction,
+};
+
+static NEXT_TABLE_NUM: AtomicI32 = AtomicI32::new(0);
+
+// A table holding intermediate results.
+// It is not in the catalog, and its file is removed when the database restarts.
+pub struct TempTable {
+	tx: Arc<Mutex<Transaction>>,
+	tblname: String,
+	layout: Arc<Layout>,
+}
+
+impl TempTable {
+	pub fn new(tx: Arc<Mutex<Transaction>>, sch: &Schema) -> Result<Self> {
+		let tblnum = NEXT_TABLE_NUM.fetch_add(1, Ordering::SeqCst) + 1;
+
+		Ok(Self {
+			tx,
+			tblname: format!("temp{}", tblnum),
+			layout: Arc::new(Layout::new(sch.clone())?),
+		})
+	}
+
+	pub fn open(&self) -> Result<TableScan> {
+		TableScan::new(self.tx.clone(), &self.tblname, self.layout.clone())
+	}
+
+	pub fn table_name(&self) -> &str {
+		&self.tblname
+	}
+
+	pub fn layout(&self) -> Arc<Layout> {
+		self.layout.clone()
+	}
+}
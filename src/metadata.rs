@@ -0,0 +1,4 @@
+pub mod metadata_mgr;
+pub mod stat_mgr;
+pub mod table_mgr;
+pub mod view_mgr;
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	record::{layout::Layout, schema::Schema},
+	tx::transaction::Transaction,
+};
+
+use super::{
+	stat_mgr::{StatInfo, StatMgr},
+	table_mgr::TableMgr,
+	view_mgr::ViewMgr,
+};
+
+// The single entry point to the metadata of the database.
+pub struct MetadataMgr {
+	tm: Arc<TableMgr>,
+	vm: ViewMgr,
+	sm: Mutex<StatMgr>,
+}
+
+impl MetadataMgr {
+	pub fn new(is_new: bool, tx: Arc<Mutex<Transaction>>) -> Result<Self> {
+		let tm = Arc::new(TableMgr::new(is_new, tx.clone())?);
+		let vm = ViewMgr::new(is_new, tm.clone(), tx.clone())?;
+		let sm = StatMgr::new(tm.clone(), tx)?;
+
+		Ok(Self {
+			tm,
+			vm,
+			sm: Mutex::new(sm),
+		})
+	}
+
+	pub fn create_table(&self, tblname: &str, sch: &Schema, tx: Arc<Mutex<Transaction>>) -> Result<()> {
+		self.tm.create_table(tblname, sch, tx)
+	}
+
+	pub fn get_layout(&self, tblname: &str, tx: Arc<Mutex<Transaction>>) -> Result<Layout> {
+		self.tm.get_layout(tblname, tx)
+	}
+
+	pub fn create_view(&self, viewname: &str, viewdef: &str, tx: Arc<Mutex<Transaction>>) -> Result<()> {
+		self.vm.create_view(viewname, viewdef, tx)
+	}
+
+	pub fn get_view_def(&self, viewname: &str, tx: Arc<Mutex<Transaction>>) -> Result<Option<String>> {
+		self.vm.get_view_def(viewname, tx)
+	}
+
+	pub fn get_stat_info(
+		&self,
+		tblname: &str,
+		layout: Arc<Layout>,
+		tx: Arc<Mutex<Transaction>>,
+	) -> Result<StatInfo> {
+		self.sm.lock().unwrap().get_stat_info(tblname, layout, tx)
+	}
+}
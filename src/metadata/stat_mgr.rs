@@ -0,0 +1,196 @@
+use anyhow::Result;
+use std::{
+	collections::{HashMap, HashSet},
+	sync::{Arc, Mutex},
+};
+
+use crate::{
+	query::{
+		constant::Constant,
+		scan::{Scan, UpdateScan},
+	},
+	record::{layout::Layout, table_scan::TableScan},
+	tx::transaction::Transaction,
+};
+
+use super::table_mgr::TableMgr;
+
+// the statistics are recomputed after this many requests
+const REFRESH_CALLS: u64 = 100;
+
+// The size of a table and the number of distinct values of each field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatInfo {
+	num_blocks: u64,
+	num_recs: u64,
+	distinct: HashMap<String, u64>,
+}
+
+impl StatInfo {
+	pub fn new(num_blocks: u64, num_recs: u64, distinct: HashMap<String, u64>) -> Self {
+		Self {
+			num_blocks,
+			num_recs,
+			distinct,
+		}
+	}
+
+	pub fn blocks_accessed(&self) -> u64 {
+		self.num_blocks
+	}
+	pub fn records_output(&self) -> u64 {
+		self.num_recs
+	}
+	// 0 for a field the table does not have
+	pub fn distinct_values(&self, fldname: &str) -> u64 {
+		self.distinct.get(fldname).copied().unwrap_or(0)
+	}
+}
+
+// Caches the statistics of every table.
+// They are not updated on each change, but recomputed from time to time.
+pub struct StatMgr {
+	tm: Arc<TableMgr>,
+	tablestats: HashMap<String, StatInfo>,
+	numcalls: u64,
+}
+
+impl StatMgr {
+	pub fn new(tm: Arc<TableMgr>, tx: Arc<Mutex<Transaction>>) -> Result<Self> {
+		let mut sm = Self {
+			tm,
+			tablestats: HashMap::new(),
+			numcalls: 0,
+		};
+		sm.refresh_statistics(tx)?;
+
+		Ok(sm)
+	}
+
+	pub fn get_stat_info(
+		&mut self,
+		tblname: &str,
+		layout: Arc<Layout>,
+		tx: Arc<Mutex<Transaction>>,
+	) -> Result<StatInfo> {
+		self.numcalls += 1;
+		if self.numcalls > REFRESH_CALLS {
+			self.refresh_statistics(tx.clone())?;
+		}
+		if let Some(si) = self.tablestats.get(tblname) {
+			return Ok(si.clone());
+		}
+
+		let si = Self::calc_table_stats(tblname, layout, tx)?;
+		self.tablestats.insert(tblname.to_string(), si.clone());
+
+		Ok(si)
+	}
+
+	fn refresh_statistics(&mut self, tx: Arc<Mutex<Transaction>>) -> Result<()> {
+		self.tablestats.clear();
+		self.numcalls = 0;
+
+		let tcat_layout = Arc::new(self.tm.get_layout("tblcat", tx.clone())?);
+		let mut tblnames = vec![];
+		let mut tcat = TableScan::new(tx.clone(), "tblcat", tcat_layout)?;
+		while tcat.next()? {
+			tblnames.push(tcat.get_string("tblname")?);
+		}
+		tcat.close()?;
+
+		for tblname in tblnames {
+			let layout = Arc::new(self.tm.get_layout(&tblname, tx.clone())?);
+			let si = Self::calc_table_stats(&tblname, layout, tx.clone())?;
+			self.tablestats.insert(tblname, si);
+		}
+
+		Ok(())
+	}
+
+	fn calc_table_stats(tblname: &str, layout: Arc<Layout>, tx: Arc<Mutex<Transaction>>) -> Result<StatInfo> {
+		let sch = layout.schema().clone();
+		let mut values: HashMap<&str, HashSet<Constant>> = HashMap::new();
+		let mut num_recs = 0;
+		let mut num_blocks = 0;
+
+		let mut ts = TableScan::new(tx, tblname, layout)?;
+		while ts.next()? {
+			num_recs += 1;
+			num_blocks = ts.get_rid()?.block_number() + 1;
+			for fldname in sch.fields() {
+				values.entry(fldname).or_default().insert(ts.get_val(fldname)?);
+			}
+		}
+		ts.close()?;
+
+		let distinct = values
+			.into_iter()
+			.map(|(fldname, vals)| (fldname.to_string(), vals.len() as u64))
+			.collect();
+
+		Ok(StatInfo::new(num_blocks, num_recs, distinct))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::fs;
+
+	use crate::{
+		buffer::manager::BufferMgr,
+		file::manager::FileMgr,
+		log::manager::LogMgr,
+		record::schema::Schema,
+	};
+
+	#[test]
+	fn test_stat_info() -> Result<()> {
+		let dir = "metadatatest/statmgr";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+
+		let tm = Arc::new(TableMgr::new(true, tx.clone())?);
+		let mut sch = Schema::new();
+		sch.add_i32_field("A");
+		sch.add_string_field("B", 9);
+		tm.create_table("T", &sch, tx.clone())?;
+		let layout = Arc::new(tm.get_layout("T", tx.clone())?);
+
+		let mut ts = TableScan::new(tx.clone(), "T", layout.clone())?;
+		for i in 0..50 {
+			ts.insert()?;
+			ts.set_i32("A", i)?;
+			ts.set_string("B", &format!("rec{}", i % 5))?;
+		}
+		ts.close()?;
+
+		let mut sm = StatMgr::new(tm, tx.clone())?;
+		let si = sm.get_stat_info("T", layout.clone(), tx.clone())?;
+		// 19 records fit in a block
+		assert_eq!(si.blocks_accessed(), 3);
+		assert_eq!(si.records_output(), 50);
+		assert_eq!(si.distinct_values("A"), 50);
+		assert_eq!(si.distinct_values("B"), 5);
+		assert_eq!(si.distinct_values("C"), 0);
+
+		// new records show up only once the statistics are refreshed
+		let mut ts = TableScan::new(tx.clone(), "T", layout.clone())?;
+		ts.insert()?;
+		ts.close()?;
+		for _ in 1..REFRESH_CALLS {
+			assert_eq!(sm.get_stat_info("T", layout.clone(), tx.clone())?, si);
+		}
+		let si = sm.get_stat_info("T", layout, tx.clone())?;
+		assert_eq!(si.records_output(), 51);
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
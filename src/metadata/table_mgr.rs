@@ -0,0 +1,176 @@
+use anyhow::Result;
+use core::fmt;
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex},
+};
+
+use crate::{
+	query::scan::{Scan, UpdateScan},
+	record::{
+		layout::Layout,
+		schema::{FieldType, Schema},
+		table_scan::TableScan,
+	},
+	tx::transaction::Transaction,
+};
+
+// the max length of table and field names
+pub const MAX_NAME: usize = 16;
+
+#[derive(Debug)]
+enum TableMgrError {
+	TableNotFound(String),
+}
+
+impl std::error::Error for TableMgrError {}
+impl fmt::Display for TableMgrError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			TableMgrError::TableNotFound(tblname) => {
+				write!(f, "table {} not found in the catalog", tblname)
+			}
+		}
+	}
+}
+
+// Keeps the layout of every table in two catalog tables:
+// tblcat has a record per table, fldcat a record per field.
+pub struct TableMgr {
+	tcat_layout: Arc<Layout>,
+	fcat_layout: Arc<Layout>,
+}
+
+impl TableMgr {
+	pub fn new(is_new: bool, tx: Arc<Mutex<Transaction>>) -> Result<Self> {
+		let mut tcat_schema = Schema::new();
+		tcat_schema.add_string_field("tblname", MAX_NAME);
+		tcat_schema.add_i32_field("slotsize");
+		let tcat_layout = Arc::new(Layout::new(tcat_schema.clone())?);
+
+		let mut fcat_schema = Schema::new();
+		fcat_schema.add_string_field("tblname", MAX_NAME);
+		fcat_schema.add_string_field("fldname", MAX_NAME);
+		fcat_schema.add_i32_field("type");
+		fcat_schema.add_i32_field("length");
+		fcat_schema.add_i32_field("offset");
+		let fcat_layout = Arc::new(Layout::new(fcat_schema.clone())?);
+
+		let tm = Self {
+			tcat_layout,
+			fcat_layout,
+		};
+		if is_new {
+			tm.create_table("tblcat", &tcat_schema, tx.clone())?;
+			tm.create_table("fldcat", &fcat_schema, tx)?;
+		}
+
+		Ok(tm)
+	}
+
+	pub fn create_table(&self, tblname: &str, sch: &Schema, tx: Arc<Mutex<Transaction>>) -> Result<()> {
+		let layout = Layout::new(sch.clone())?;
+
+		let mut tcat = TableScan::new(tx.clone(), "tblcat", self.tcat_layout.clone())?;
+		tcat.insert()?;
+		tcat.set_string("tblname", tblname)?;
+		tcat.set_i32("slotsize", layout.slot_size() as i32)?;
+		tcat.close()?;
+
+		let mut fcat = TableScan::new(tx, "fldcat", self.fcat_layout.clone())?;
+		for fldname in sch.fields() {
+			fcat.insert()?;
+			fcat.set_string("tblname", tblname)?;
+			fcat.set_string("fldname", fldname)?;
+			fcat.set_i32("type", sch.field_type(fldname)? as i32)?;
+			fcat.set_i32("length", sch.field_length(fldname)? as i32)?;
+			fcat.set_i32("offset", layout.offset(fldname)? as i32)?;
+		}
+		fcat.close()?;
+
+		Ok(())
+	}
+
+	pub fn get_layout(&self, tblname: &str, tx: Arc<Mutex<Transaction>>) -> Result<Layout> {
+		let mut slotsize = None;
+		let mut tcat = TableScan::new(tx.clone(), "tblcat", self.tcat_layout.clone())?;
+		while tcat.next()? {
+			if tcat.get_string("tblname")? == tblname {
+				slotsize = Some(tcat.get_i32("slotsize")? as usize);
+				break;
+			}
+		}
+		tcat.close()?;
+		let slotsize = match slotsize {
+			Some(slotsize) => slotsize,
+			None => return Err(From::from(TableMgrError::TableNotFound(tblname.to_string()))),
+		};
+
+		let mut sch = Schema::new();
+		let mut offsets = HashMap::new();
+		let mut fcat = TableScan::new(tx, "fldcat", self.fcat_layout.clone())?;
+		while fcat.next()? {
+			if fcat.get_string("tblname")? == tblname {
+				let fldname = fcat.get_string("fldname")?;
+				let fldtype = FieldType::try_from(fcat.get_i32("type")?)?;
+				let length = fcat.get_i32("length")? as usize;
+				offsets.insert(fldname.clone(), fcat.get_i32("offset")? as usize);
+				sch.add_field(&fldname, fldtype, length);
+			}
+		}
+		fcat.close()?;
+
+		Ok(Layout::with_offsets(sch, offsets, slotsize))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::fs;
+
+	use crate::{buffer::manager::BufferMgr, file::manager::FileMgr, log::manager::LogMgr};
+
+	fn new_tx(dir: &str) -> Result<Arc<Mutex<Transaction>>> {
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		Ok(Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?)))
+	}
+
+	#[test]
+	fn test_layout_survives_reopening() -> Result<()> {
+		let dir = "metadatatest/tablemgr";
+		let _ = fs::remove_dir_all(dir);
+
+		let mut sch = Schema::new();
+		sch.add_i32_field("A");
+		sch.add_string_field("B", 9);
+		let layout = Layout::new(sch.clone())?;
+
+		let tx = new_tx(dir)?;
+		let tm = TableMgr::new(true, tx.clone())?;
+		tm.create_table("MyTable", &sch, tx.clone())?;
+		tx.lock().unwrap().commit()?;
+		drop(tm);
+
+		// a new set of managers over the same directory, as after a restart
+		let tx = new_tx(dir)?;
+		let tm = TableMgr::new(false, tx.clone())?;
+		let saved = tm.get_layout("MyTable", tx.clone())?;
+		assert_eq!(saved.slot_size(), layout.slot_size());
+		assert_eq!(saved.schema().fields(), ["A", "B"]);
+		for fldname in ["A", "B"] {
+			assert_eq!(saved.offset(fldname)?, layout.offset(fldname)?);
+			assert_eq!(saved.schema().field_type(fldname)?, sch.field_type(fldname)?);
+			assert_eq!(saved.schema().field_length(fldname)?, sch.field_length(fldname)?);
+		}
+		// the catalog describes itself too
+		assert_eq!(tm.get_layout("fldcat", tx.clone())?.schema().fields().len(), 5);
+		assert!(tm.get_layout("NoTable", tx.clone()).is_err());
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use core::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	query::scan::{Scan, UpdateScan},
+	record::{schema::Schema, table_scan::TableScan},
+	tx::transaction::Transaction,
+};
+
+use super::table_mgr::{TableMgr, MAX_NAME};
+
+// the max length of a view definition; a definition is a whole query,
+// so it gets far more room than a name
+pub const MAX_VIEWDEF: usize = 300;
+
+#[derive(Debug)]
+enum ViewMgrError {
+	ViewDefTooLong(usize),
+}
+
+impl std::error::Error for ViewMgrError {}
+impl fmt::Display for ViewMgrError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ViewMgrError::ViewDefTooLong(len) => {
+				write!(f, "view definition of {} bytes is longer than {}", len, MAX_VIEWDEF)
+			}
+		}
+	}
+}
+
+// Keeps the defining query of every view in the catalog table viewcat.
+pub struct ViewMgr {
+	tm: Arc<TableMgr>,
+}
+
+impl ViewMgr {
+	pub fn new(is_new: bool, tm: Arc<TableMgr>, tx: Arc<Mutex<Transaction>>) -> Result<Self> {
+		if is_new {
+			let mut sch = Schema::new();
+			sch.add_string_field("viewname", MAX_NAME);
+			sch.add_string_field("viewdef", MAX_VIEWDEF);
+			tm.create_table("viewcat", &sch, tx)?;
+		}
+
+		Ok(Self { tm })
+	}
+
+	pub fn create_view(&self, vname: &str, vdef: &str, tx: Arc<Mutex<Transaction>>) -> Result<()> {
+		if vdef.len() > MAX_VIEWDEF {
+			return Err(From::from(ViewMgrError::ViewDefTooLong(vdef.len())));
+		}
+
+		let layout = Arc::new(self.tm.get_layout("viewcat", tx.clone())?);
+		let mut ts = TableScan::new(tx, "viewcat", layout)?;
+		ts.insert()?;
+		ts.set_string("viewname", vname)?;
+		ts.set_string("viewdef", vdef)?;
+		ts.close()
+	}
+
+	pub fn get_view_def(&self, vname: &str, tx: Arc<Mutex<Transaction>>) -> Result<Option<String>> {
+		let layout = Arc::new(self.tm.get_layout("viewcat", tx.clone())?);
+		let mut result = None;
+		let mut ts = TableScan::new(tx, "viewcat", layout)?;
+		while ts.next()? {
+			if ts.get_string("viewname")? == vname {
+				result = Some(ts.get_string("viewdef")?);
+				break;
+			}
+		}
+		ts.close()?;
+
+		Ok(result)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::fs;
+
+	use crate::{buffer::manager::BufferMgr, file::manager::FileMgr, log::manager::LogMgr};
+
+	#[test]
+	fn test_view_def_round_trip() -> Result<()> {
+		let dir = "metadatatest/viewmgr";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+
+		let tm = Arc::new(TableMgr::new(true, tx.clone())?);
+		let vm = ViewMgr::new(true, tm, tx.clone())?;
+
+		let vdef = "select sname, dname from student, dept where majorid = did and gradyear = 2020 and sname <> 'joe'";
+		// longer than any name in the catalog
+		assert!(vdef.len() > MAX_NAME);
+		vm.create_view("joeless", vdef, tx.clone())?;
+		vm.create_view("other", "select a from t", tx.clone())?;
+
+		assert_eq!(vm.get_view_def("joeless", tx.clone())?.as_deref(), Some(vdef));
+		assert_eq!(vm.get_view_def("other", tx.clone())?.as_deref(), Some("select a from t"));
+		assert_eq!(vm.get_view_def("noview", tx.clone())?, None);
+		assert!(vm.create_view("toolong", &"x".repeat(MAX_VIEWDEF + 1), tx.clone()).is_err());
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
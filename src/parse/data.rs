@@ -0,0 +1,197 @@
+use core::fmt;
+
+use crate::{
+	query::{
+		constant::Constant,
+		predicate::{Expression, Predicate},
+	},
+	record::schema::Schema,
+};
+
+// The parts of a SQL select statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryData {
+	fields: Vec<String>,
+	tables: Vec<String>,
+	pred: Predicate,
+}
+
+impl QueryData {
+	pub fn new(fields: Vec<String>, tables: Vec<String>, pred: Predicate) -> Self {
+		Self { fields, tables, pred }
+	}
+
+	pub fn fields(&self) -> &[String] {
+		&self.fields
+	}
+	pub fn tables(&self) -> &[String] {
+		&self.tables
+	}
+	pub fn pred(&self) -> &Predicate {
+		&self.pred
+	}
+}
+
+// back in SQL, e.g. to be kept as the definition of a view
+impl fmt::Display for QueryData {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "select {} from {}", self.fields.join(", "), self.tables.join(", "))?;
+		let predstring = self.pred.to_string();
+		if !predstring.is_empty() {
+			write!(f, " where {}", predstring)?;
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InsertData {
+	tblname: String,
+	flds: Vec<String>,
+	vals: Vec<Constant>,
+}
+
+impl InsertData {
+	pub fn new(tblname: String, flds: Vec<String>, vals: Vec<Constant>) -> Self {
+		Self { tblname, flds, vals }
+	}
+
+	pub fn table_name(&self) -> &str {
+		&self.tblname
+	}
+	pub fn fields(&self) -> &[String] {
+		&self.flds
+	}
+	pub fn vals(&self) -> &[Constant] {
+		&self.vals
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeleteData {
+	tblname: String,
+	pred: Predicate,
+}
+
+impl DeleteData {
+	pub fn new(tblname: String, pred: Predicate) -> Self {
+		Self { tblname, pred }
+	}
+
+	pub fn table_name(&self) -> &str {
+		&self.tblname
+	}
+	pub fn pred(&self) -> &Predicate {
+		&self.pred
+	}
+}
+
+// an update statement, setting one field
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModifyData {
+	tblname: String,
+	fldname: String,
+	newval: Expression,
+	pred: Predicate,
+}
+
+impl ModifyData {
+	pub fn new(tblname: String, fldname: String, newval: Expression, pred: Predicate) -> Self {
+		Self {
+			tblname,
+			fldname,
+			newval,
+			pred,
+		}
+	}
+
+	pub fn table_name(&self) -> &str {
+		&self.tblname
+	}
+	pub fn target_field(&self) -> &str {
+		&self.fldname
+	}
+	pub fn new_value(&self) -> &Expression {
+		&self.newval
+	}
+	pub fn pred(&self) -> &Predicate {
+		&self.pred
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateTableData {
+	tblname: String,
+	sch: Schema,
+}
+
+impl CreateTableData {
+	pub fn new(tblname: String, sch: Schema) -> Self {
+		Self { tblname, sch }
+	}
+
+	pub fn table_name(&self) -> &str {
+		&self.tblname
+	}
+	pub fn new_schema(&self) -> &Schema {
+		&self.sch
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateViewData {
+	viewname: String,
+	qrydata: QueryData,
+}
+
+impl CreateViewData {
+	pub fn new(viewname: String, qrydata: QueryData) -> Self {
+		Self { viewname, qrydata }
+	}
+
+	pub fn view_name(&self) -> &str {
+		&self.viewname
+	}
+	pub fn view_def(&self) -> String {
+		self.qrydata.to_string()
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateIndexData {
+	idxname: String,
+	tblname: String,
+	fldname: String,
+}
+
+impl CreateIndexData {
+	pub fn new(idxname: String, tblname: String, fldname: String) -> Self {
+		Self {
+			idxname,
+			tblname,
+			fldname,
+		}
+	}
+
+	pub fn index_name(&self) -> &str {
+		&self.idxname
+	}
+	pub fn table_name(&self) -> &str {
+		&self.tblname
+	}
+	pub fn field_name(&self) -> &str {
+		&self.fldname
+	}
+}
+
+// a statement that changes the database rather than querying it
+#[derive(Debug, Clone)]
+pub enum UpdateCmd {
+	Insert(InsertData),
+	Delete(DeleteData),
+	Modify(ModifyData),
+	CreateTable(CreateTableData),
+	CreateView(CreateViewData),
+	CreateIndex(CreateIndexData),
+}
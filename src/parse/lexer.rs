@@ -0,0 +1,252 @@
+use anyhow::Result;
+use core::fmt;
+
+const KEYWORDS: [&str; 18] = [
+	"select", "from", "where", "and", "insert", "into", "values", "delete", "update", "set",
+	"create", "table", "int", "varchar", "view", "as", "index", "on",
+];
+
+const DELIMITERS: &str = ",()=*;.";
+
+#[derive(Debug)]
+enum LexerError {
+	// the position in the input where a token was expected
+	BadSyntax(usize),
+}
+
+impl std::error::Error for LexerError {}
+impl fmt::Display for LexerError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			LexerError::BadSyntax(pos) => {
+				write!(f, "bad syntax at position {}", pos)
+			}
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+	Delim(char),
+	IntConstant(i32),
+	StringConstant(String),
+	Keyword(String),
+	Id(String),
+	Eof,
+}
+
+// Splits a SQL statement into tokens, and lets a parser consume them one by one.
+// Keywords and identifiers are case-insensitive, and are kept in lower case.
+pub struct Lexer {
+	// each token with its position in the input
+	tokens: Vec<(Token, usize)>,
+	current: usize,
+}
+
+impl Lexer {
+	pub fn new(s: &str) -> Result<Self> {
+		Ok(Self {
+			tokens: Self::tokenize(s)?,
+			current: 0,
+		})
+	}
+
+	pub fn match_delim(&self, d: char) -> bool {
+		self.token() == &Token::Delim(d)
+	}
+	pub fn match_int_constant(&self) -> bool {
+		matches!(self.token(), Token::IntConstant(_))
+	}
+	pub fn match_string_constant(&self) -> bool {
+		matches!(self.token(), Token::StringConstant(_))
+	}
+	pub fn match_keyword(&self, w: &str) -> bool {
+		matches!(self.token(), Token::Keyword(k) if k == w)
+	}
+	pub fn match_id(&self) -> bool {
+		matches!(self.token(), Token::Id(_))
+	}
+	pub fn match_eof(&self) -> bool {
+		self.token() == &Token::Eof
+	}
+
+	pub fn eat_delim(&mut self, d: char) -> Result<()> {
+		if !self.match_delim(d) {
+			return Err(self.bad_syntax());
+		}
+		self.advance();
+
+		Ok(())
+	}
+	pub fn eat_int_constant(&mut self) -> Result<i32> {
+		match *self.token() {
+			Token::IntConstant(i) => {
+				self.advance();
+				Ok(i)
+			}
+			_ => Err(self.bad_syntax()),
+		}
+	}
+	pub fn eat_string_constant(&mut self) -> Result<String> {
+		match self.token().clone() {
+			Token::StringConstant(s) => {
+				self.advance();
+				Ok(s)
+			}
+			_ => Err(self.bad_syntax()),
+		}
+	}
+	pub fn eat_keyword(&mut self, w: &str) -> Result<()> {
+		if !self.match_keyword(w) {
+			return Err(self.bad_syntax());
+		}
+		self.advance();
+
+		Ok(())
+	}
+	pub fn eat_id(&mut self) -> Result<String> {
+		match self.token().clone() {
+			Token::Id(s) => {
+				self.advance();
+				Ok(s)
+			}
+			_ => Err(self.bad_syntax()),
+		}
+	}
+
+	// an error at the current token
+	pub fn bad_syntax(&self) -> anyhow::Error {
+		From::from(LexerError::BadSyntax(self.tokens[self.current].1))
+	}
+
+	fn token(&self) -> &Token {
+		&self.tokens[self.current].0
+	}
+
+	fn advance(&mut self) {
+		if self.current + 1 < self.tokens.len() {
+			self.current += 1;
+		}
+	}
+
+	fn tokenize(s: &str) -> Result<Vec<(Token, usize)>> {
+		let chars: Vec<char> = s.chars().collect();
+		let mut tokens = vec![];
+		let mut pos = 0;
+		while pos < chars.len() {
+			let c = chars[pos];
+			let start = pos;
+			if c.is_whitespace() {
+				pos += 1;
+				continue;
+			}
+
+			let token = if c.is_ascii_digit() || (c == '-' && chars.get(pos + 1).is_some_and(|c| c.is_ascii_digit())) {
+				pos += 1;
+				while pos < chars.len() && chars[pos].is_ascii_digit() {
+					pos += 1;
+				}
+				let digits: String = chars[start..pos].iter().collect();
+				match digits.parse() {
+					Ok(i) => Token::IntConstant(i),
+					Err(_) => return Err(From::from(LexerError::BadSyntax(start))),
+				}
+			} else if c == '\'' {
+				pos += 1;
+				while pos < chars.len() && chars[pos] != '\'' {
+					pos += 1;
+				}
+				if pos == chars.len() {
+					// unterminated string
+					return Err(From::from(LexerError::BadSyntax(start)));
+				}
+				pos += 1;
+				Token::StringConstant(chars[start + 1..pos - 1].iter().collect())
+			} else if c.is_alphabetic() || c == '_' {
+				while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+					pos += 1;
+				}
+				let word: String = chars[start..pos].iter().collect::<String>().to_lowercase();
+				if KEYWORDS.contains(&word.as_str()) {
+					Token::Keyword(word)
+				} else {
+					Token::Id(word)
+				}
+			} else if DELIMITERS.contains(c) {
+				pos += 1;
+				Token::Delim(c)
+			} else {
+				return Err(From::from(LexerError::BadSyntax(start)));
+			};
+			tokens.push((token, start));
+		}
+		tokens.push((Token::Eof, chars.len()));
+
+		Ok(tokens)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_tokenize_query() -> Result<()> {
+		let mut lex = Lexer::new("select a from t where b = 3")?;
+		assert!(lex.match_keyword("select"));
+		lex.eat_keyword("select")?;
+		assert_eq!(lex.eat_id()?, "a");
+		lex.eat_keyword("from")?;
+		assert!(lex.match_id());
+		assert_eq!(lex.eat_id()?, "t");
+		lex.eat_keyword("where")?;
+		assert_eq!(lex.eat_id()?, "b");
+		assert!(lex.match_delim('='));
+		lex.eat_delim('=')?;
+		assert!(lex.match_int_constant());
+		assert_eq!(lex.eat_int_constant()?, 3);
+		assert!(lex.match_eof());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_constants_and_case() -> Result<()> {
+		let mut lex = Lexer::new("INSERT into T(Name) values ('Joe Doe', -12)")?;
+		lex.eat_keyword("insert")?;
+		lex.eat_keyword("into")?;
+		assert_eq!(lex.eat_id()?, "t");
+		lex.eat_delim('(')?;
+		assert_eq!(lex.eat_id()?, "name");
+		lex.eat_delim(')')?;
+		lex.eat_keyword("values")?;
+		lex.eat_delim('(')?;
+		assert!(lex.match_string_constant());
+		// string constants keep their case and spaces
+		assert_eq!(lex.eat_string_constant()?, "Joe Doe");
+		lex.eat_delim(',')?;
+		assert_eq!(lex.eat_int_constant()?, -12);
+		lex.eat_delim(')')?;
+		assert!(lex.match_eof());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_bad_syntax() -> Result<()> {
+		let mut lex = Lexer::new("select a from")?;
+		lex.eat_keyword("select")?;
+		// a keyword is not an identifier
+		assert!(lex.eat_keyword("from").is_err());
+		assert_eq!(lex.eat_id()?, "a");
+		lex.eat_keyword("from")?;
+		let err = lex.eat_id().unwrap_err();
+		assert_eq!(err.to_string(), "bad syntax at position 13");
+
+		assert_eq!(Lexer::new("select # from t").err().unwrap().to_string(), "bad syntax at position 7");
+		assert!(Lexer::new("select 'abc").is_err());
+		assert!(Lexer::new("select 99999999999").is_err());
+
+		Ok(())
+	}
+}
@@ -0,0 +1,312 @@
+use anyhow::Result;
+
+use crate::{
+	query::{
+		constant::Constant,
+		predicate::{Expression, Predicate, Term},
+	},
+	record::schema::Schema,
+};
+
+use super::{
+	data::{
+		CreateIndexData, CreateTableData, CreateViewData, DeleteData, InsertData, ModifyData,
+		QueryData, UpdateCmd,
+	},
+	lexer::Lexer,
+};
+
+// A recursive-descent parser for the SQL subset:
+//
+// <Query>       := SELECT <SelectList> FROM <TableList> [ WHERE <Predicate> ]
+// <UpdateCmd>   := <Insert> | <Delete> | <Modify> | <Create>
+// <Insert>      := INSERT INTO IdTok ( <FieldList> ) VALUES ( <ConstList> )
+// <Delete>      := DELETE FROM IdTok [ WHERE <Predicate> ]
+// <Modify>      := UPDATE IdTok SET <Field> = <Expression> [ WHERE <Predicate> ]
+// <Create>      := CREATE TABLE IdTok ( <FieldDefs> )
+//                | CREATE VIEW IdTok AS <Query>
+//                | CREATE INDEX IdTok ON IdTok ( <Field> )
+// <Predicate>   := <Term> [ AND <Predicate> ]
+// <Term>        := <Expression> = <Expression>
+// <Expression>  := <Field> | <Constant>
+// <FieldDef>    := IdTok INT | IdTok VARCHAR ( IntTok )
+pub struct Parser {
+	lex: Lexer,
+}
+
+impl Parser {
+	pub fn new(s: &str) -> Result<Self> {
+		Ok(Self { lex: Lexer::new(s)? })
+	}
+
+	// a whole select statement
+	pub fn query(&mut self) -> Result<QueryData> {
+		let data = self.query_data()?;
+		self.eat_eof()?;
+
+		Ok(data)
+	}
+
+	// a whole statement other than a select
+	pub fn update_cmd(&mut self) -> Result<UpdateCmd> {
+		let cmd = if self.lex.match_keyword("insert") {
+			UpdateCmd::Insert(self.insert()?)
+		} else if self.lex.match_keyword("delete") {
+			UpdateCmd::Delete(self.delete()?)
+		} else if self.lex.match_keyword("update") {
+			UpdateCmd::Modify(self.modify()?)
+		} else {
+			self.create()?
+		};
+		self.eat_eof()?;
+
+		Ok(cmd)
+	}
+
+	pub fn field(&mut self) -> Result<String> {
+		self.lex.eat_id()
+	}
+
+	pub fn constant(&mut self) -> Result<Constant> {
+		if self.lex.match_string_constant() {
+			Ok(Constant::Str(self.lex.eat_string_constant()?))
+		} else {
+			Ok(Constant::Int(self.lex.eat_int_constant()?))
+		}
+	}
+
+	pub fn expression(&mut self) -> Result<Expression> {
+		if self.lex.match_id() {
+			Ok(Expression::Field(self.field()?))
+		} else {
+			Ok(Expression::Val(self.constant()?))
+		}
+	}
+
+	pub fn term(&mut self) -> Result<Term> {
+		let lhs = self.expression()?;
+		self.lex.eat_delim('=')?;
+		let rhs = self.expression()?;
+
+		Ok(Term::new(lhs, rhs))
+	}
+
+	pub fn predicate(&mut self) -> Result<Predicate> {
+		let mut pred = Predicate::with_term(self.term()?);
+		while self.lex.match_keyword("and") {
+			self.lex.eat_keyword("and")?;
+			pred.conjoin_with(Predicate::with_term(self.term()?));
+		}
+
+		Ok(pred)
+	}
+
+	fn query_data(&mut self) -> Result<QueryData> {
+		self.lex.eat_keyword("select")?;
+		let fields = self.list(Self::field)?;
+		self.lex.eat_keyword("from")?;
+		let tables = self.list(|p| p.lex.eat_id())?;
+		let pred = self.optional_where()?;
+
+		Ok(QueryData::new(fields, tables, pred))
+	}
+
+	fn insert(&mut self) -> Result<InsertData> {
+		self.lex.eat_keyword("insert")?;
+		self.lex.eat_keyword("into")?;
+		let tblname = self.lex.eat_id()?;
+		self.lex.eat_delim('(')?;
+		let flds = self.list(Self::field)?;
+		self.lex.eat_delim(')')?;
+		self.lex.eat_keyword("values")?;
+		self.lex.eat_delim('(')?;
+		let vals = self.list(Self::constant)?;
+		self.lex.eat_delim(')')?;
+
+		Ok(InsertData::new(tblname, flds, vals))
+	}
+
+	fn delete(&mut self) -> Result<DeleteData> {
+		self.lex.eat_keyword("delete")?;
+		self.lex.eat_keyword("from")?;
+		let tblname = self.lex.eat_id()?;
+		let pred = self.optional_where()?;
+
+		Ok(DeleteData::new(tblname, pred))
+	}
+
+	fn modify(&mut self) -> Result<ModifyData> {
+		self.lex.eat_keyword("update")?;
+		let tblname = self.lex.eat_id()?;
+		self.lex.eat_keyword("set")?;
+		let fldname = self.field()?;
+		self.lex.eat_delim('=')?;
+		let newval = self.expression()?;
+		let pred = self.optional_where()?;
+
+		Ok(ModifyData::new(tblname, fldname, newval, pred))
+	}
+
+	fn create(&mut self) -> Result<UpdateCmd> {
+		self.lex.eat_keyword("create")?;
+		if self.lex.match_keyword("table") {
+			self.lex.eat_keyword("table")?;
+			let tblname = self.lex.eat_id()?;
+			self.lex.eat_delim('(')?;
+			let mut sch = Schema::new();
+			self.field_def(&mut sch)?;
+			while self.lex.match_delim(',') {
+				self.lex.eat_delim(',')?;
+				self.field_def(&mut sch)?;
+			}
+			self.lex.eat_delim(')')?;
+			Ok(UpdateCmd::CreateTable(CreateTableData::new(tblname, sch)))
+		} else if self.lex.match_keyword("view") {
+			self.lex.eat_keyword("view")?;
+			let viewname = self.lex.eat_id()?;
+			self.lex.eat_keyword("as")?;
+			let qrydata = self.query_data()?;
+			Ok(UpdateCmd::CreateView(CreateViewData::new(viewname, qrydata)))
+		} else {
+			self.lex.eat_keyword("index")?;
+			let idxname = self.lex.eat_id()?;
+			self.lex.eat_keyword("on")?;
+			let tblname = self.lex.eat_id()?;
+			self.lex.eat_delim('(')?;
+			let fldname = self.field()?;
+			self.lex.eat_delim(')')?;
+			Ok(UpdateCmd::CreateIndex(CreateIndexData::new(idxname, tblname, fldname)))
+		}
+	}
+
+	fn field_def(&mut self, sch: &mut Schema) -> Result<()> {
+		let fldname = self.field()?;
+		if self.lex.match_keyword("int") {
+			self.lex.eat_keyword("int")?;
+			sch.add_i32_field(&fldname);
+		} else {
+			self.lex.eat_keyword("varchar")?;
+			self.lex.eat_delim('(')?;
+			let strlen = self.lex.eat_int_constant()?;
+			if strlen < 0 {
+				return Err(self.lex.bad_syntax());
+			}
+			self.lex.eat_delim(')')?;
+			sch.add_string_field(&fldname, strlen as usize);
+		}
+
+		Ok(())
+	}
+
+	fn optional_where(&mut self) -> Result<Predicate> {
+		if self.lex.match_keyword("where") {
+			self.lex.eat_keyword("where")?;
+			self.predicate()
+		} else {
+			Ok(Predicate::new())
+		}
+	}
+
+	// one or more items separated by commas
+	fn list<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+		let mut items = vec![item(self)?];
+		while self.lex.match_delim(',') {
+			self.lex.eat_delim(',')?;
+			items.push(item(self)?);
+		}
+
+		Ok(items)
+	}
+
+	fn eat_eof(&self) -> Result<()> {
+		if !self.lex.match_eof() {
+			return Err(self.lex.bad_syntax());
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::record::schema::FieldType;
+
+	#[test]
+	fn test_parse_select() -> Result<()> {
+		let data = Parser::new("select sname, gradyear from student, dept where majorid = did and sname = 'joe'")?
+			.query()?;
+		assert_eq!(data.fields(), ["sname", "gradyear"]);
+		assert_eq!(data.tables(), ["student", "dept"]);
+		assert_eq!(data.pred().terms().len(), 2);
+		assert_eq!(data.pred().terms()[0].lhs(), &Expression::Field("majorid".to_string()));
+		assert_eq!(data.pred().terms()[1].rhs(), &Expression::Val(Constant::from("joe")));
+		assert_eq!(
+			data.to_string(),
+			"select sname, gradyear from student, dept where majorid=did and sname='joe'"
+		);
+
+		let data = Parser::new("select a from t")?.query()?;
+		assert!(data.pred().terms().is_empty());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_parse_create_table() -> Result<()> {
+		let cmd = Parser::new("create table student (sid int, sname varchar(10), gradyear int)")?.update_cmd()?;
+		let data = match cmd {
+			UpdateCmd::CreateTable(data) => data,
+			_ => panic!("not a create table: {:?}", cmd),
+		};
+		assert_eq!(data.table_name(), "student");
+		let sch = data.new_schema();
+		assert_eq!(sch.fields(), ["sid", "sname", "gradyear"]);
+		assert_eq!(sch.field_type("sname")?, FieldType::VARCHAR);
+		assert_eq!(sch.field_length("sname")?, 10);
+		assert_eq!(sch.field_type("gradyear")?, FieldType::INTEGER);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_parse_other_commands() -> Result<()> {
+		match Parser::new("insert into t (a, b) values (1, 'x')")?.update_cmd()? {
+			UpdateCmd::Insert(data) => {
+				assert_eq!(data.table_name(), "t");
+				assert_eq!(data.fields(), ["a", "b"]);
+				assert_eq!(data.vals(), [Constant::Int(1), Constant::from("x")]);
+			}
+			cmd => panic!("not an insert: {:?}", cmd),
+		}
+		match Parser::new("update t set a = 2 where b = 'x'")?.update_cmd()? {
+			UpdateCmd::Modify(data) => {
+				assert_eq!(data.target_field(), "a");
+				assert_eq!(data.new_value(), &Expression::Val(Constant::Int(2)));
+			}
+			cmd => panic!("not an update: {:?}", cmd),
+		}
+		match Parser::new("create view v as select a from t where b = 1")?.update_cmd()? {
+			UpdateCmd::CreateView(data) => assert_eq!(data.view_def(), "select a from t where b=1"),
+			cmd => panic!("not a create view: {:?}", cmd),
+		}
+		assert!(matches!(Parser::new("delete from t")?.update_cmd()?, UpdateCmd::Delete(_)));
+		assert!(matches!(Parser::new("create index i on t (a)")?.update_cmd()?, UpdateCmd::CreateIndex(_)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_syntax_errors() -> Result<()> {
+		assert!(Parser::new("select from t")?.query().is_err());
+		assert!(Parser::new("select a from t where")?.query().is_err());
+		assert!(Parser::new("select a from t b")?.query().is_err());
+		assert!(Parser::new("create table t (a float)")?.update_cmd().is_err());
+		assert!(Parser::new("drop table t")?.update_cmd().is_err());
+		let err = Parser::new("insert into t (a) values (b)")?.update_cmd().unwrap_err();
+		assert_eq!(err.to_string(), "bad syntax at position 26");
+
+		Ok(())
+	}
+}
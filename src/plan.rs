@@ -0,0 +1,22 @@
+pub mod basic_query_planner;
+pub mod basic_update_planner;
+pub mod planner;
+pub mod product_plan;
+pub mod project_plan;
+pub mod select_plan;
+pub mod table_plan;
+
+use anyhow::Result;
+
+use crate::{query::scan::Scan, record::schema::Schema};
+
+// A node of a query tree: it opens the scan computing its records,
+// and estimates their cost from the statistics of the tables.
+pub trait Plan {
+	fn open(&self) -> Result<Box<dyn Scan>>;
+	// the number of block accesses the scan makes
+	fn blocks_accessed(&self) -> u64;
+	fn records_output(&self) -> u64;
+	fn distinct_values(&self, fldname: &str) -> u64;
+	fn schema(&self) -> &Schema;
+}
@@ -0,0 +1,154 @@
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	metadata::metadata_mgr::MetadataMgr,
+	parse::{data::QueryData, parser::Parser},
+	tx::transaction::Transaction,
+};
+
+use super::{
+	product_plan::ProductPlan,
+	project_plan::ProjectPlan,
+	select_plan::SelectPlan,
+	table_plan::TablePlan,
+	Plan,
+};
+
+// Plans a query the simplest way: the product of its tables in the order given,
+// then the selection by its predicate, then the projection on its fields.
+pub struct BasicQueryPlanner {
+	mdm: Arc<MetadataMgr>,
+}
+
+impl BasicQueryPlanner {
+	pub fn new(mdm: Arc<MetadataMgr>) -> Self {
+		Self { mdm }
+	}
+
+	pub fn create_plan(&self, data: &QueryData, tx: Arc<Mutex<Transaction>>) -> Result<Box<dyn Plan>> {
+		// a plan for each table or view
+		let mut plans: Vec<Box<dyn Plan>> = vec![];
+		for tblname in data.tables() {
+			match self.mdm.get_view_def(tblname, tx.clone())? {
+				Some(viewdef) => {
+					let viewdata = Parser::new(&viewdef)?.query()?;
+					plans.push(self.create_plan(&viewdata, tx.clone())?);
+				}
+				None => plans.push(Box::new(TablePlan::new(tx.clone(), tblname, &self.mdm)?)),
+			}
+		}
+
+		// the parser gives at least one table
+		let mut plans = plans.into_iter();
+		let mut p = plans.next().unwrap();
+		for next in plans {
+			p = Box::new(ProductPlan::new(p, next)?);
+		}
+		let p = Box::new(SelectPlan::new(p, data.pred().clone()));
+
+		Ok(Box::new(ProjectPlan::new(p, data.fields())?))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::fs;
+
+	use crate::{
+		buffer::manager::BufferMgr,
+		file::manager::FileMgr,
+		log::manager::LogMgr,
+		query::{
+			constant::Constant,
+			scan::{Scan, UpdateScan},
+		},
+		record::{schema::Schema, table_scan::TableScan},
+	};
+
+	fn insert_rows(
+		mdm: &MetadataMgr,
+		tx: Arc<Mutex<Transaction>>,
+		tblname: &str,
+		sch: &Schema,
+		rows: Vec<Vec<Constant>>,
+	) -> Result<()> {
+		mdm.create_table(tblname, sch, tx.clone())?;
+		let layout = Arc::new(mdm.get_layout(tblname, tx.clone())?);
+		let mut ts = TableScan::new(tx, tblname, layout)?;
+		for row in rows {
+			ts.insert()?;
+			for (fldname, val) in sch.fields().iter().zip(row) {
+				ts.set_val(fldname, &val)?;
+			}
+		}
+		ts.close()
+	}
+
+	#[test]
+	fn test_join_select_project() -> Result<()> {
+		let dir = "plantest/basicqueryplanner";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+		let mdm = Arc::new(MetadataMgr::new(true, tx.clone())?);
+
+		let mut sch = Schema::new();
+		sch.add_string_field("sname", 10);
+		sch.add_i32_field("majorid");
+		sch.add_i32_field("gradyear");
+		let students = [("joe", 10, 2021), ("amy", 20, 2020), ("max", 10, 2022), ("sue", 20, 2022)];
+		let rows = students
+			.iter()
+			.map(|(n, m, g)| vec![Constant::from(*n), Constant::Int(*m), Constant::Int(*g)])
+			.collect();
+		insert_rows(&mdm, tx.clone(), "student", &sch, rows)?;
+
+		let mut sch = Schema::new();
+		sch.add_i32_field("did");
+		sch.add_string_field("dname", 10);
+		let rows = vec![
+			vec![Constant::Int(10), Constant::from("compsci")],
+			vec![Constant::Int(20), Constant::from("math")],
+		];
+		insert_rows(&mdm, tx.clone(), "dept", &sch, rows)?;
+
+		let planner = BasicQueryPlanner::new(mdm.clone());
+		let data = Parser::new("select sname, dname from student, dept where majorid = did and gradyear = 2022")?
+			.query()?;
+		let p = planner.create_plan(&data, tx.clone())?;
+		assert_eq!(p.schema().fields(), ["sname", "dname"]);
+		// the statistics are those of the tables, as they were when first asked for
+		assert_eq!(p.blocks_accessed(), 1 + 4);
+
+		let mut s = p.open()?;
+		let mut rows = vec![];
+		while s.next()? {
+			rows.push((s.get_string("sname")?, s.get_string("dname")?));
+			assert!(!s.has_field("gradyear"));
+		}
+		s.close()?;
+		rows.sort();
+		assert_eq!(rows, [("max".to_string(), "compsci".to_string()), ("sue".to_string(), "math".to_string())]);
+
+		// a view is planned from its definition
+		mdm.create_view("math", "select sname from student, dept where majorid = did and dname = 'math'", tx.clone())?;
+		let data = Parser::new("select sname from math")?.query()?;
+		let mut s = planner.create_plan(&data, tx.clone())?.open()?;
+		let mut names = vec![];
+		while s.next()? {
+			names.push(s.get_string("sname")?);
+		}
+		s.close()?;
+		names.sort();
+		assert_eq!(names, ["amy", "sue"]);
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
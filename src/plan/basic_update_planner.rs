@@ -0,0 +1,218 @@
+use anyhow::Result;
+use core::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	metadata::metadata_mgr::MetadataMgr,
+	parse::data::{CreateTableData, CreateViewData, DeleteData, InsertData, ModifyData},
+	query::{
+		constant::Constant,
+		scan::{Scan, UpdateScan},
+		select_scan::SelectScan,
+	},
+	record::{
+		schema::{FieldType, Schema},
+		table_scan::TableScan,
+	},
+	tx::transaction::Transaction,
+};
+
+#[derive(Debug)]
+enum BasicUpdatePlannerError {
+	// the numbers of fields and of values
+	ValueCountMismatch(usize, usize),
+	FieldNotFound(String),
+	TypeMismatch(String, Constant),
+	// the field, its length and the length of the value
+	StringTooLong(String, usize, usize),
+}
+
+impl std::error::Error for BasicUpdatePlannerError {}
+impl fmt::Display for BasicUpdatePlannerError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			BasicUpdatePlannerError::ValueCountMismatch(fields, vals) => {
+				write!(f, "insert has {} fields but {} values", fields, vals)
+			}
+			BasicUpdatePlannerError::FieldNotFound(fldname) => write!(f, "field not found: {}", fldname),
+			BasicUpdatePlannerError::TypeMismatch(fldname, val) => {
+				write!(f, "value {} does not match the type of field {}", val, fldname)
+			}
+			BasicUpdatePlannerError::StringTooLong(fldname, length, len) => {
+				write!(f, "string of length {} does not fit field {} of length {}", len, fldname, length)
+			}
+		}
+	}
+}
+
+// Executes the update commands directly on the table scans,
+// each returning the number of records affected.
+pub struct BasicUpdatePlanner {
+	mdm: Arc<MetadataMgr>,
+}
+
+impl BasicUpdatePlanner {
+	pub fn new(mdm: Arc<MetadataMgr>) -> Self {
+		Self { mdm }
+	}
+
+	fn open(&self, tblname: &str, tx: Arc<Mutex<Transaction>>) -> Result<TableScan> {
+		let layout = Arc::new(self.mdm.get_layout(tblname, tx.clone())?);
+		TableScan::new(tx, tblname, layout)
+	}
+
+	pub fn execute_insert(&self, data: &InsertData, tx: Arc<Mutex<Transaction>>) -> Result<usize> {
+		if data.fields().len() != data.vals().len() {
+			return Err(From::from(BasicUpdatePlannerError::ValueCountMismatch(
+				data.fields().len(),
+				data.vals().len(),
+			)));
+		}
+
+		// every value is checked before the slot is claimed,
+		// so a rejected insert leaves no record behind
+		let layout = Arc::new(self.mdm.get_layout(data.table_name(), tx.clone())?);
+		for (fldname, val) in data.fields().iter().zip(data.vals()) {
+			check_value(layout.schema(), fldname, val)?;
+		}
+
+		let mut us = TableScan::new(tx, data.table_name(), layout)?;
+		us.insert()?;
+		for (fldname, val) in data.fields().iter().zip(data.vals()) {
+			us.set_val(fldname, val)?;
+		}
+		us.close()?;
+
+		Ok(1)
+	}
+
+	pub fn execute_delete(&self, data: &DeleteData, tx: Arc<Mutex<Transaction>>) -> Result<usize> {
+		let ts = self.open(data.table_name(), tx)?;
+		let mut us = SelectScan::new(ts, data.pred().clone());
+		let mut count = 0;
+		while us.next()? {
+			us.delete()?;
+			count += 1;
+		}
+		us.close()?;
+
+		Ok(count)
+	}
+
+	pub fn execute_modify(&self, data: &ModifyData, tx: Arc<Mutex<Transaction>>) -> Result<usize> {
+		let ts = self.open(data.table_name(), tx)?;
+		let mut us = SelectScan::new(ts, data.pred().clone());
+		let mut count = 0;
+		while us.next()? {
+			let val = data.new_value().evaluate(&us)?;
+			us.set_val(data.target_field(), &val)?;
+			count += 1;
+		}
+		us.close()?;
+
+		Ok(count)
+	}
+
+	pub fn execute_create_table(&self, data: &CreateTableData, tx: Arc<Mutex<Transaction>>) -> Result<usize> {
+		self.mdm.create_table(data.table_name(), data.new_schema(), tx)?;
+
+		Ok(0)
+	}
+
+	pub fn execute_create_view(&self, data: &CreateViewData, tx: Arc<Mutex<Transaction>>) -> Result<usize> {
+		self.mdm.create_view(data.view_name(), &data.view_def(), tx)?;
+
+		Ok(0)
+	}
+}
+
+fn check_value(sch: &Schema, fldname: &str, val: &Constant) -> Result<()> {
+	if !sch.has_field(fldname) {
+		return Err(From::from(BasicUpdatePlannerError::FieldNotFound(fldname.to_string())));
+	}
+	match (sch.field_type(fldname)?, val) {
+		(FieldType::INTEGER, Constant::Int(_)) => Ok(()),
+		(FieldType::VARCHAR, Constant::Str(s)) => {
+			let length = sch.field_length(fldname)?;
+			if s.len() > length {
+				return Err(From::from(BasicUpdatePlannerError::StringTooLong(
+					fldname.to_string(),
+					length,
+					s.len(),
+				)));
+			}
+			Ok(())
+		}
+		_ => Err(From::from(BasicUpdatePlannerError::TypeMismatch(
+			fldname.to_string(),
+			val.clone(),
+		))),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::fs;
+
+	use crate::{
+		buffer::manager::BufferMgr,
+		file::manager::FileMgr,
+		log::manager::LogMgr,
+		parse::{data::UpdateCmd, parser::Parser},
+	};
+
+	fn execute(planner: &BasicUpdatePlanner, cmd: &str, tx: Arc<Mutex<Transaction>>) -> Result<usize> {
+		match Parser::new(cmd)?.update_cmd()? {
+			UpdateCmd::Insert(data) => planner.execute_insert(&data, tx),
+			UpdateCmd::Delete(data) => planner.execute_delete(&data, tx),
+			UpdateCmd::Modify(data) => planner.execute_modify(&data, tx),
+			UpdateCmd::CreateTable(data) => planner.execute_create_table(&data, tx),
+			UpdateCmd::CreateView(data) => planner.execute_create_view(&data, tx),
+			UpdateCmd::CreateIndex(_) => unreachable!(),
+		}
+	}
+
+	#[test]
+	fn test_insert_modify_delete() -> Result<()> {
+		let dir = "plantest/basicupdateplanner";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+		let mdm = Arc::new(MetadataMgr::new(true, tx.clone())?);
+		let planner = BasicUpdatePlanner::new(mdm.clone());
+
+		assert_eq!(execute(&planner, "create table t (a int, b varchar(9))", tx.clone())?, 0);
+		for i in 0..30 {
+			let cmd = format!("insert into t (a, b) values ({}, 'rec{}')", i % 3, i);
+			assert_eq!(execute(&planner, &cmd, tx.clone())?, 1);
+		}
+		// nothing is inserted when the fields and values don't match up
+		assert!(execute(&planner, "insert into t (a, b) values (1)", tx.clone()).is_err());
+		assert!(execute(&planner, "insert into t (a) values (1, 'extra')", tx.clone()).is_err());
+		// nor when a value doesn't fit its field
+		assert!(execute(&planner, "insert into t (a, b) values ('x', 'rec')", tx.clone()).is_err());
+		assert!(execute(&planner, "insert into t (a, b) values (1, 'toolongvalue')", tx.clone()).is_err());
+		assert!(execute(&planner, "insert into t (a, c) values (1, 'rec')", tx.clone()).is_err());
+		assert_eq!(execute(&planner, "update t set b = 'changed' where a = 1", tx.clone())?, 10);
+		assert_eq!(execute(&planner, "delete from t where a = 2", tx.clone())?, 10);
+
+		let layout = Arc::new(mdm.get_layout("t", tx.clone())?);
+		let mut ts = TableScan::new(tx.clone(), "t", layout)?;
+		let mut counts = [0; 3];
+		while ts.next()? {
+			let a = ts.get_i32("a")?;
+			counts[a as usize] += 1;
+			assert_eq!(ts.get_string("b")? == "changed", a == 1);
+		}
+		ts.close()?;
+		assert_eq!(counts, [10, 10, 0]);
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
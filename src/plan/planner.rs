@@ -0,0 +1,57 @@
+use anyhow::Result;
+use core::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	metadata::metadata_mgr::MetadataMgr,
+	parse::{data::UpdateCmd, parser::Parser},
+	tx::transaction::Transaction,
+};
+
+use super::{basic_query_planner::BasicQueryPlanner, basic_update_planner::BasicUpdatePlanner, Plan};
+
+#[derive(Debug)]
+enum PlannerError {
+	IndexNotSupported,
+}
+
+impl std::error::Error for PlannerError {}
+impl fmt::Display for PlannerError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			PlannerError::IndexNotSupported => write!(f, "create index is not supported"),
+		}
+	}
+}
+
+// Parses SQL and hands it to the query planner or the update planner.
+pub struct Planner {
+	qplanner: BasicQueryPlanner,
+	uplanner: BasicUpdatePlanner,
+}
+
+impl Planner {
+	pub fn new(mdm: Arc<MetadataMgr>) -> Self {
+		Self {
+			qplanner: BasicQueryPlanner::new(mdm.clone()),
+			uplanner: BasicUpdatePlanner::new(mdm),
+		}
+	}
+
+	pub fn create_query_plan(&self, qry: &str, tx: Arc<Mutex<Transaction>>) -> Result<Box<dyn Plan>> {
+		let data = Parser::new(qry)?.query()?;
+		self.qplanner.create_plan(&data, tx)
+	}
+
+	// returns the number of records affected
+	pub fn execute_update(&self, cmd: &str, tx: Arc<Mutex<Transaction>>) -> Result<usize> {
+		match Parser::new(cmd)?.update_cmd()? {
+			UpdateCmd::Insert(data) => self.uplanner.execute_insert(&data, tx),
+			UpdateCmd::Delete(data) => self.uplanner.execute_delete(&data, tx),
+			UpdateCmd::Modify(data) => self.uplanner.execute_modify(&data, tx),
+			UpdateCmd::CreateTable(data) => self.uplanner.execute_create_table(&data, tx),
+			UpdateCmd::CreateView(data) => self.uplanner.execute_create_view(&data, tx),
+			UpdateCmd::CreateIndex(_) => Err(From::from(PlannerError::IndexNotSupported)),
+		}
+	}
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+
+use crate::{
+	query::{product_scan::ProductScan, scan::Scan},
+	record::schema::Schema,
+};
+
+use super::Plan;
+
+// Every combination of the records of two plans.
+pub struct ProductPlan {
+	p1: Box<dyn Plan>,
+	p2: Box<dyn Plan>,
+	schema: Schema,
+}
+
+impl ProductPlan {
+	pub fn new(p1: Box<dyn Plan>, p2: Box<dyn Plan>) -> Result<Self> {
+		let mut schema = Schema::new();
+		schema.add_all(p1.schema())?;
+		schema.add_all(p2.schema())?;
+
+		Ok(Self { p1, p2, schema })
+	}
+}
+
+impl Plan for ProductPlan {
+	fn open(&self) -> Result<Box<dyn Scan>> {
+		let s1 = self.p1.open()?;
+		let s2 = self.p2.open()?;
+		Ok(Box::new(ProductScan::new(s1, s2)?))
+	}
+
+	// p2 is scanned once for each record of p1
+	fn blocks_accessed(&self) -> u64 {
+		self.p1.blocks_accessed() + self.p1.records_output() * self.p2.blocks_accessed()
+	}
+
+	fn records_output(&self) -> u64 {
+		self.p1.records_output() * self.p2.records_output()
+	}
+
+	fn distinct_values(&self, fldname: &str) -> u64 {
+		if self.p1.schema().has_field(fldname) {
+			self.p1.distinct_values(fldname)
+		} else {
+			self.p2.distinct_values(fldname)
+		}
+	}
+
+	fn schema(&self) -> &Schema {
+		&self.schema
+	}
+}
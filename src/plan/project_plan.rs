@@ -0,0 +1,48 @@
+use anyhow::Result;
+
+use crate::{
+	query::{project_scan::ProjectScan, scan::Scan},
+	record::schema::Schema,
+};
+
+use super::Plan;
+
+// The records of a plan, with only some of their fields.
+pub struct ProjectPlan {
+	p: Box<dyn Plan>,
+	schema: Schema,
+}
+
+impl ProjectPlan {
+	pub fn new(p: Box<dyn Plan>, fieldlist: &[String]) -> Result<Self> {
+		let mut schema = Schema::new();
+		for fldname in fieldlist {
+			schema.add(fldname, p.schema())?;
+		}
+
+		Ok(Self { p, schema })
+	}
+}
+
+impl Plan for ProjectPlan {
+	fn open(&self) -> Result<Box<dyn Scan>> {
+		let s = self.p.open()?;
+		Ok(Box::new(ProjectScan::new(s, self.schema.fields().to_vec())))
+	}
+
+	fn blocks_accessed(&self) -> u64 {
+		self.p.blocks_accessed()
+	}
+
+	fn records_output(&self) -> u64 {
+		self.p.records_output()
+	}
+
+	fn distinct_values(&self, fldname: &str) -> u64 {
+		self.p.distinct_values(fldname)
+	}
+
+	fn schema(&self) -> &Schema {
+		&self.schema
+	}
+}
@@ -0,0 +1,48 @@
+use anyhow::Result;
+
+use crate::{
+	query::{predicate::Predicate, scan::Scan, select_scan::SelectScan},
+	record::schema::Schema,
+};
+
+use super::Plan;
+
+// The records of a plan that satisfy a predicate.
+pub struct SelectPlan {
+	p: Box<dyn Plan>,
+	pred: Predicate,
+}
+
+impl SelectPlan {
+	pub fn new(p: Box<dyn Plan>, pred: Predicate) -> Self {
+		Self { p, pred }
+	}
+}
+
+impl Plan for SelectPlan {
+	fn open(&self) -> Result<Box<dyn Scan>> {
+		Ok(Box::new(SelectScan::new(self.p.open()?, self.pred.clone())))
+	}
+
+	fn blocks_accessed(&self) -> u64 {
+		self.p.blocks_accessed()
+	}
+
+	fn records_output(&self) -> u64 {
+		self.p.records_output() / self.pred.reduction_factor(self.p.as_ref()).max(1)
+	}
+
+	fn distinct_values(&self, fldname: &str) -> u64 {
+		if self.pred.equates_with_constant(fldname).is_some() {
+			return 1;
+		}
+		match self.pred.equates_with_field(fldname) {
+			Some(fldname2) => self.p.distinct_values(fldname).min(self.p.distinct_values(fldname2)),
+			None => self.p.distinct_values(fldname),
+		}
+	}
+
+	fn schema(&self) -> &Schema {
+		self.p.schema()
+	}
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	metadata::{metadata_mgr::MetadataMgr, stat_mgr::StatInfo},
+	query::scan::Scan,
+	record::{layout::Layout, schema::Schema, table_scan::TableScan},
+	tx::transaction::Transaction,
+};
+
+use super::Plan;
+
+// The records of a stored table.
+pub struct TablePlan {
+	tx: Arc<Mutex<Transaction>>,
+	tblname: String,
+	layout: Arc<Layout>,
+	si: StatInfo,
+}
+
+impl TablePlan {
+	pub fn new(tx: Arc<Mutex<Transaction>>, tblname: &str, mdm: &MetadataMgr) -> Result<Self> {
+		let layout = Arc::new(mdm.get_layout(tblname, tx.clone())?);
+		let si = mdm.get_stat_info(tblname, layout.clone(), tx.clone())?;
+
+		Ok(Self {
+			tx,
+			tblname: tblname.to_string(),
+			layout,
+			si,
+		})
+	}
+}
+
+impl Plan for TablePlan {
+	fn open(&self) -> Result<Box<dyn Scan>> {
+		Ok(Box::new(TableScan::new(self.tx.clone(), &self.tblname, self.layout.clone())?))
+	}
+
+	fn blocks_accessed(&self) -> u64 {
+		self.si.blocks_accessed()
+	}
+
+	fn records_output(&self) -> u64 {
+		self.si.records_output()
+	}
+
+	fn distinct_values(&self, fldname: &str) -> u64 {
+		self.si.distinct_values(fldname)
+	}
+
+	fn schema(&self) -> &Schema {
+		self.layout.schema()
+	}
+}
@@ -0,0 +1,6 @@
+pub mod constant;
+pub mod predicate;
+pub mod product_scan;
+pub mod project_scan;
+pub mod scan;
+pub mod select_scan;
@@ -0,0 +1,93 @@
+use anyhow::Result;
+use core::fmt;
+
+#[derive(Debug)]
+enum ConstantError {
+	NotI32(String),
+	NotString(i32),
+}
+
+impl std::error::Error for ConstantError {}
+impl fmt::Display for ConstantError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ConstantError::NotI32(s) => {
+				write!(f, "constant '{}' is not an integer", s)
+			}
+			ConstantError::NotString(i) => {
+				write!(f, "constant {} is not a string", i)
+			}
+		}
+	}
+}
+
+// A value stored in a field.
+// Constants of the same type compare by value; any Int is less than any Str.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Constant {
+	Int(i32),
+	Str(String),
+}
+
+impl Constant {
+	pub fn as_i32(&self) -> Result<i32> {
+		match self {
+			Constant::Int(i) => Ok(*i),
+			Constant::Str(s) => Err(From::from(ConstantError::NotI32(s.clone()))),
+		}
+	}
+
+	pub fn as_string(&self) -> Result<&str> {
+		match self {
+			Constant::Str(s) => Ok(s),
+			Constant::Int(i) => Err(From::from(ConstantError::NotString(*i))),
+		}
+	}
+}
+
+impl From<i32> for Constant {
+	fn from(i: i32) -> Self {
+		Constant::Int(i)
+	}
+}
+
+impl From<&str> for Constant {
+	fn from(s: &str) -> Self {
+		Constant::Str(s.to_string())
+	}
+}
+
+impl fmt::Display for Constant {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Constant::Int(i) => write!(f, "{}", i),
+			Constant::Str(s) => write!(f, "{}", s),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_conversion() -> Result<()> {
+		assert_eq!(Constant::Int(3).as_i32()?, 3);
+		assert_eq!(Constant::from("abc").as_string()?, "abc");
+		assert!(Constant::Int(3).as_string().is_err());
+		assert!(Constant::from("abc").as_i32().is_err());
+		assert_eq!(Constant::from(-7).to_string(), "-7");
+		assert_eq!(Constant::from("abc").to_string(), "abc");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_comparison() {
+		assert!(Constant::Int(2) < Constant::Int(10));
+		assert!(Constant::from("b") > Constant::from("abc"));
+		assert_eq!(Constant::from("x"), Constant::Str("x".to_string()));
+		// mixed types
+		assert!(Constant::Int(i32::MAX) < Constant::from(""));
+	}
+}
@@ -0,0 +1,240 @@
+use anyhow::Result;
+use core::fmt;
+
+use crate::plan::Plan;
+
+use super::{constant::Constant, scan::Scan};
+
+// A constant, or the value of a field in the current record of a scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+	Val(Constant),
+	Field(String),
+}
+
+impl Expression {
+	pub fn evaluate(&self, s: &dyn Scan) -> Result<Constant> {
+		match self {
+			Expression::Val(val) => Ok(val.clone()),
+			Expression::Field(fldname) => s.get_val(fldname),
+		}
+	}
+
+	pub fn as_constant(&self) -> Option<&Constant> {
+		match self {
+			Expression::Val(val) => Some(val),
+			Expression::Field(_) => None,
+		}
+	}
+
+	pub fn as_field_name(&self) -> Option<&str> {
+		match self {
+			Expression::Field(fldname) => Some(fldname),
+			Expression::Val(_) => None,
+		}
+	}
+}
+
+// in SQL syntax, with string constants quoted
+impl fmt::Display for Expression {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Expression::Val(Constant::Str(s)) => write!(f, "'{}'", s),
+			Expression::Val(val) => write!(f, "{}", val),
+			Expression::Field(fldname) => write!(f, "{}", fldname),
+		}
+	}
+}
+
+// An equality between two expressions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+	lhs: Expression,
+	rhs: Expression,
+}
+
+impl Term {
+	pub fn new(lhs: Expression, rhs: Expression) -> Self {
+		Self { lhs, rhs }
+	}
+
+	pub fn is_satisfied(&self, s: &dyn Scan) -> Result<bool> {
+		Ok(self.lhs.evaluate(s)? == self.rhs.evaluate(s)?)
+	}
+
+	// How much the term shrinks the output of p: the number of records
+	// from which one satisfies it, as estimated from the distinct values.
+	pub fn reduction_factor(&self, p: &dyn Plan) -> u64 {
+		match (&self.lhs, &self.rhs) {
+			(Expression::Field(l), Expression::Field(r)) => p.distinct_values(l).max(p.distinct_values(r)),
+			(Expression::Field(l), Expression::Val(_)) => p.distinct_values(l),
+			(Expression::Val(_), Expression::Field(r)) => p.distinct_values(r),
+			(Expression::Val(l), Expression::Val(r)) => {
+				if l == r {
+					1
+				} else {
+					u64::MAX
+				}
+			}
+		}
+	}
+
+	// the constant the field is equated with by this term, if any
+	pub fn equates_with_constant(&self, fldname: &str) -> Option<&Constant> {
+		match (&self.lhs, &self.rhs) {
+			(Expression::Field(f), Expression::Val(val)) | (Expression::Val(val), Expression::Field(f))
+				if f == fldname => Some(val),
+			_ => None,
+		}
+	}
+
+	// the other field the field is equated with by this term, if any
+	pub fn equates_with_field(&self, fldname: &str) -> Option<&str> {
+		match (&self.lhs, &self.rhs) {
+			(Expression::Field(l), Expression::Field(r)) if l == fldname => Some(r),
+			(Expression::Field(l), Expression::Field(r)) if r == fldname => Some(l),
+			_ => None,
+		}
+	}
+
+	pub fn lhs(&self) -> &Expression {
+		&self.lhs
+	}
+	pub fn rhs(&self) -> &Expression {
+		&self.rhs
+	}
+}
+
+impl fmt::Display for Term {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}={}", self.lhs, self.rhs)
+	}
+}
+
+// A conjunction of terms; the empty predicate is always satisfied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Predicate {
+	terms: Vec<Term>,
+}
+
+impl Predicate {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_term(t: Term) -> Self {
+		Self { terms: vec![t] }
+	}
+
+	// adds the terms of pred to this one
+	pub fn conjoin_with(&mut self, pred: Predicate) {
+		self.terms.extend(pred.terms);
+	}
+
+	pub fn terms(&self) -> &[Term] {
+		&self.terms
+	}
+
+	pub fn is_satisfied(&self, s: &dyn Scan) -> Result<bool> {
+		for t in &self.terms {
+			if !t.is_satisfied(s)? {
+				return Ok(false);
+			}
+		}
+
+		Ok(true)
+	}
+
+	pub fn reduction_factor(&self, p: &dyn Plan) -> u64 {
+		self.terms
+			.iter()
+			.fold(1u64, |factor, t| factor.saturating_mul(t.reduction_factor(p)))
+	}
+
+	pub fn equates_with_constant(&self, fldname: &str) -> Option<&Constant> {
+		self.terms.iter().find_map(|t| t.equates_with_constant(fldname))
+	}
+
+	pub fn equates_with_field(&self, fldname: &str) -> Option<&str> {
+		self.terms.iter().find_map(|t| t.equates_with_field(fldname))
+	}
+}
+
+impl fmt::Display for Predicate {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let terms: Vec<String> = self.terms.iter().map(|t| t.to_string()).collect();
+		write!(f, "{}", terms.join(" and "))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::collections::HashMap;
+
+	// the current record of a scan, without any storage behind it
+	struct Row(HashMap<&'static str, Constant>);
+
+	impl Scan for Row {
+		fn before_first(&mut self) -> Result<()> {
+			Ok(())
+		}
+		fn next(&mut self) -> Result<bool> {
+			Ok(false)
+		}
+		fn get_i32(&self, fldname: &str) -> Result<i32> {
+			self.get_val(fldname)?.as_i32()
+		}
+		fn get_string(&self, fldname: &str) -> Result<String> {
+			Ok(self.get_val(fldname)?.as_string()?.to_string())
+		}
+		fn get_val(&self, fldname: &str) -> Result<Constant> {
+			match self.0.get(fldname) {
+				Some(val) => Ok(val.clone()),
+				None => Err(anyhow::anyhow!("no field {}", fldname)),
+			}
+		}
+		fn has_field(&self, fldname: &str) -> bool {
+			self.0.contains_key(fldname)
+		}
+		fn close(&mut self) -> Result<()> {
+			Ok(())
+		}
+	}
+
+	fn field(fldname: &str) -> Expression {
+		Expression::Field(fldname.to_string())
+	}
+
+	#[test]
+	fn test_is_satisfied() -> Result<()> {
+		let row = Row(HashMap::from([
+			("A", Constant::Int(3)),
+			("B", Constant::from("x")),
+			("C", Constant::Int(3)),
+		]));
+
+		let mut pred = Predicate::with_term(Term::new(field("A"), Expression::Val(Constant::Int(3))));
+		assert!(pred.is_satisfied(&row)?);
+		pred.conjoin_with(Predicate::with_term(Term::new(field("A"), field("C"))));
+		assert!(pred.is_satisfied(&row)?);
+		pred.conjoin_with(Predicate::with_term(Term::new(Expression::Val(Constant::from("y")), field("B"))));
+		assert!(!pred.is_satisfied(&row)?);
+		assert_eq!(pred.terms().len(), 3);
+
+		assert!(Predicate::new().is_satisfied(&row)?);
+		// a field the record does not have
+		assert!(Predicate::with_term(Term::new(field("D"), field("A"))).is_satisfied(&row).is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_display() {
+		let mut pred = Predicate::with_term(Term::new(field("A"), Expression::Val(Constant::Int(3))));
+		pred.conjoin_with(Predicate::with_term(Term::new(field("B"), Expression::Val(Constant::from("x")))));
+		assert_eq!(pred.to_string(), "A=3 and B='x'");
+		assert_eq!(Predicate::new().to_string(), "");
+	}
+}
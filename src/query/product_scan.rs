@@ -0,0 +1,143 @@
+use anyhow::Result;
+
+use super::{constant::Constant, scan::Scan};
+
+// Every combination of a record of s1 with a record of s2.
+// s2 is scanned once for each record of s1.
+pub struct ProductScan<S1, S2> {
+	s1: S1,
+	s2: S2,
+	// false once s1 has no record left, or has none at all
+	s1_valid: bool,
+}
+
+impl<S1: Scan, S2: Scan> ProductScan<S1, S2> {
+	pub fn new(s1: S1, s2: S2) -> Result<Self> {
+		let mut s = Self {
+			s1,
+			s2,
+			s1_valid: false,
+		};
+		s.before_first()?;
+
+		Ok(s)
+	}
+}
+
+impl<S1: Scan, S2: Scan> Scan for ProductScan<S1, S2> {
+	fn before_first(&mut self) -> Result<()> {
+		self.s1.before_first()?;
+		self.s1_valid = self.s1.next()?;
+		self.s2.before_first()
+	}
+
+	fn next(&mut self) -> Result<bool> {
+		if !self.s1_valid {
+			return Ok(false);
+		}
+		if self.s2.next()? {
+			return Ok(true);
+		}
+		self.s2.before_first()?;
+		self.s1_valid = self.s1.next()?;
+		Ok(self.s1_valid && self.s2.next()?)
+	}
+
+	fn get_i32(&self, fldname: &str) -> Result<i32> {
+		if self.s1.has_field(fldname) {
+			self.s1.get_i32(fldname)
+		} else {
+			self.s2.get_i32(fldname)
+		}
+	}
+
+	fn get_string(&self, fldname: &str) -> Result<String> {
+		if self.s1.has_field(fldname) {
+			self.s1.get_string(fldname)
+		} else {
+			self.s2.get_string(fldname)
+		}
+	}
+
+	fn get_val(&self, fldname: &str) -> Result<Constant> {
+		if self.s1.has_field(fldname) {
+			self.s1.get_val(fldname)
+		} else {
+			self.s2.get_val(fldname)
+		}
+	}
+
+	fn has_field(&self, fldname: &str) -> bool {
+		self.s1.has_field(fldname) || self.s2.has_field(fldname)
+	}
+
+	fn close(&mut self) -> Result<()> {
+		let closed1 = self.s1.close();
+		let closed2 = self.s2.close();
+		closed1.and(closed2)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::{
+		fs,
+		sync::{Arc, Mutex},
+	};
+
+	use crate::{
+		buffer::manager::BufferMgr,
+		file::manager::FileMgr,
+		log::manager::LogMgr,
+		query::scan::UpdateScan,
+		record::{layout::Layout, schema::Schema, table_scan::TableScan},
+		tx::transaction::Transaction,
+	};
+
+	fn create_table(tx: Arc<Mutex<Transaction>>, tblname: &str, fldname: &str, n: i32) -> Result<TableScan> {
+		let mut sch = Schema::new();
+		sch.add_i32_field(fldname);
+		let mut ts = TableScan::new(tx, tblname, Arc::new(Layout::new(sch)?))?;
+		for i in 0..n {
+			ts.insert()?;
+			ts.set_i32(fldname, i)?;
+		}
+
+		Ok(ts)
+	}
+
+	#[test]
+	fn test_product_of_two_scans() -> Result<()> {
+		let dir = "querytest/productscan";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+
+		let ts1 = create_table(tx.clone(), "T1", "A", 3)?;
+		let ts2 = create_table(tx.clone(), "T2", "B", 2)?;
+		let mut s = ProductScan::new(ts1, ts2)?;
+		assert!(s.has_field("A") && s.has_field("B"));
+
+		let mut rows = vec![];
+		while s.next()? {
+			rows.push((s.get_i32("A")?, s.get_i32("B")?));
+		}
+		assert_eq!(rows, [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1)]);
+		s.close()?;
+
+		// nothing is produced when one side is empty
+		let ts1 = create_table(tx.clone(), "T3", "C", 0)?;
+		let ts2 = create_table(tx.clone(), "T4", "D", 2)?;
+		let mut s = ProductScan::new(ts1, ts2)?;
+		assert!(!s.next()?);
+		s.close()?;
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
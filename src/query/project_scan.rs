@@ -0,0 +1,136 @@
+use anyhow::Result;
+use core::fmt;
+
+use super::{constant::Constant, scan::Scan};
+
+#[derive(Debug)]
+enum ProjectScanError {
+	FieldNotFound(String),
+}
+
+impl std::error::Error for ProjectScanError {}
+impl fmt::Display for ProjectScanError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ProjectScanError::FieldNotFound(fldname) => {
+				write!(f, "field {} not projected", fldname)
+			}
+		}
+	}
+}
+
+// The records of a scan, with only some of their fields.
+pub struct ProjectScan<S> {
+	s: S,
+	fieldlist: Vec<String>,
+}
+
+impl<S: Scan> ProjectScan<S> {
+	pub fn new(s: S, fieldlist: Vec<String>) -> Self {
+		Self { s, fieldlist }
+	}
+
+	fn check_field(&self, fldname: &str) -> Result<()> {
+		if !self.has_field(fldname) {
+			return Err(From::from(ProjectScanError::FieldNotFound(fldname.to_string())));
+		}
+
+		Ok(())
+	}
+}
+
+impl<S: Scan> Scan for ProjectScan<S> {
+	fn before_first(&mut self) -> Result<()> {
+		self.s.before_first()
+	}
+
+	fn next(&mut self) -> Result<bool> {
+		self.s.next()
+	}
+
+	fn get_i32(&self, fldname: &str) -> Result<i32> {
+		self.check_field(fldname)?;
+		self.s.get_i32(fldname)
+	}
+
+	fn get_string(&self, fldname: &str) -> Result<String> {
+		self.check_field(fldname)?;
+		self.s.get_string(fldname)
+	}
+
+	fn get_val(&self, fldname: &str) -> Result<Constant> {
+		self.check_field(fldname)?;
+		self.s.get_val(fldname)
+	}
+
+	fn has_field(&self, fldname: &str) -> bool {
+		self.fieldlist.iter().any(|f| f == fldname)
+	}
+
+	fn close(&mut self) -> Result<()> {
+		self.s.close()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::{
+		fs,
+		sync::{Arc, Mutex},
+	};
+
+	use crate::{
+		buffer::manager::BufferMgr,
+		file::manager::FileMgr,
+		log::manager::LogMgr,
+		query::scan::UpdateScan,
+		record::{layout::Layout, schema::Schema, table_scan::TableScan},
+		tx::transaction::Transaction,
+	};
+
+	#[test]
+	fn test_project_two_of_three_fields() -> Result<()> {
+		let dir = "querytest/projectscan";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+
+		let mut sch = Schema::new();
+		sch.add_i32_field("A");
+		sch.add_string_field("B", 9);
+		sch.add_i32_field("C");
+		let layout = Arc::new(Layout::new(sch)?);
+
+		let mut ts = TableScan::new(tx.clone(), "T", layout)?;
+		for i in 0..5 {
+			ts.insert()?;
+			ts.set_i32("A", i)?;
+			ts.set_string("B", &format!("rec{}", i))?;
+			ts.set_i32("C", -i)?;
+		}
+
+		let mut s = ProjectScan::new(ts, vec!["A".to_string(), "B".to_string()]);
+		assert!(s.has_field("A"));
+		assert!(s.has_field("B"));
+		assert!(!s.has_field("C"));
+
+		s.before_first()?;
+		let mut count = 0;
+		while s.next()? {
+			assert_eq!(s.get_string("B")?, format!("rec{}", s.get_i32("A")?));
+			assert!(s.get_i32("C").is_err());
+			assert!(s.get_val("C").is_err());
+			count += 1;
+		}
+		assert_eq!(count, 5);
+		s.close()?;
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+
+use crate::record::rid::RID;
+
+use super::constant::Constant;
+
+// A cursor over the records output by a query, read field by field.
+// Query operators are scans over other scans.
+pub trait Scan {
+	fn before_first(&mut self) -> Result<()>;
+	// moves to the next record; false when there is none left
+	fn next(&mut self) -> Result<bool>;
+	fn get_i32(&self, fldname: &str) -> Result<i32>;
+	fn get_string(&self, fldname: &str) -> Result<String>;
+	fn get_val(&self, fldname: &str) -> Result<Constant>;
+	fn has_field(&self, fldname: &str) -> bool;
+	fn close(&mut self) -> Result<()>;
+}
+
+// A scan whose records can be changed, i.e. one backed by a stored table.
+pub trait UpdateScan: Scan {
+	fn set_i32(&mut self, fldname: &str, val: i32) -> Result<()>;
+	fn set_string(&mut self, fldname: &str, val: &str) -> Result<()>;
+	fn set_val(&mut self, fldname: &str, val: &Constant) -> Result<()>;
+	// moves to a new record
+	fn insert(&mut self) -> Result<()>;
+	fn delete(&mut self) -> Result<()>;
+	fn get_rid(&self) -> Result<RID>;
+	fn move_to_rid(&mut self, rid: RID) -> Result<()>;
+}
+
+// so that operators can be built over a boxed scan, e.g. the one a plan opens
+impl<S: Scan + ?Sized> Scan for Box<S> {
+	fn before_first(&mut self) -> Result<()> {
+		(**self).before_first()
+	}
+	fn next(&mut self) -> Result<bool> {
+		(**self).next()
+	}
+	fn get_i32(&self, fldname: &str) -> Result<i32> {
+		(**self).get_i32(fldname)
+	}
+	fn get_string(&self, fldname: &str) -> Result<String> {
+		(**self).get_string(fldname)
+	}
+	fn get_val(&self, fldname: &str) -> Result<Constant> {
+		(**self).get_val(fldname)
+	}
+	fn has_field(&self, fldname: &str) -> bool {
+		(**self).has_field(fldname)
+	}
+	fn close(&mut self) -> Result<()> {
+		(**self).close()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::{
+		fs,
+		sync::{Arc, Mutex},
+	};
+
+	use crate::{
+		buffer::manager::BufferMgr,
+		file::manager::FileMgr,
+		log::manager::LogMgr,
+		record::{layout::Layout, schema::Schema, table_scan::TableScan},
+		tx::transaction::Transaction,
+	};
+
+	fn fill(s: &mut dyn UpdateScan, n: i32) -> Result<()> {
+		for i in 0..n {
+			s.insert()?;
+			s.set_val("A", &Constant::Int(i))?;
+			s.set_val("B", &Constant::from(format!("rec{}", i).as_str()))?;
+		}
+
+		Ok(())
+	}
+
+	fn read_all(s: &mut dyn Scan) -> Result<Vec<(Constant, Constant)>> {
+		let mut recs = vec![];
+		s.before_first()?;
+		while s.next()? {
+			recs.push((s.get_val("A")?, s.get_val("B")?));
+		}
+
+		Ok(recs)
+	}
+
+	#[test]
+	fn test_table_scan_as_scan() -> Result<()> {
+		let dir = "querytest/scan";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+
+		let mut sch = Schema::new();
+		sch.add_i32_field("A");
+		sch.add_string_field("B", 9);
+		let layout = Arc::new(Layout::new(sch)?);
+
+		let mut ts = TableScan::new(tx.clone(), "T", layout)?;
+		fill(&mut ts, 30)?;
+		let recs = read_all(&mut ts)?;
+		assert_eq!(recs.len(), 30);
+		for (i, (a, b)) in recs.into_iter().enumerate() {
+			assert_eq!(a, Constant::Int(i as i32));
+			assert_eq!(b.as_string()?, format!("rec{}", i));
+		}
+		assert!(ts.has_field("B"));
+		assert!(!ts.has_field("C"));
+		// a value of the wrong type is rejected
+		assert!(ts.set_val("A", &Constant::from("x")).is_err());
+
+		let scan: &mut dyn Scan = &mut ts;
+		scan.before_first()?;
+		assert!(scan.next()?);
+		assert_eq!(scan.get_i32("A")?, 0);
+		assert_eq!(scan.get_string("B")?, "rec0");
+		scan.close()?;
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,152 @@
+use anyhow::Result;
+
+use crate::record::rid::RID;
+
+use super::{
+	constant::Constant,
+	predicate::Predicate,
+	scan::{Scan, UpdateScan},
+};
+
+// The records of a scan that satisfy a predicate.
+// Over an updatable scan, the selected records can be changed too.
+pub struct SelectScan<S> {
+	s: S,
+	pred: Predicate,
+}
+
+impl<S: Scan> SelectScan<S> {
+	pub fn new(s: S, pred: Predicate) -> Self {
+		Self { s, pred }
+	}
+}
+
+impl<S: Scan> Scan for SelectScan<S> {
+	fn before_first(&mut self) -> Result<()> {
+		self.s.before_first()
+	}
+
+	fn next(&mut self) -> Result<bool> {
+		while self.s.next()? {
+			if self.pred.is_satisfied(&self.s)? {
+				return Ok(true);
+			}
+		}
+
+		Ok(false)
+	}
+
+	fn get_i32(&self, fldname: &str) -> Result<i32> {
+		self.s.get_i32(fldname)
+	}
+
+	fn get_string(&self, fldname: &str) -> Result<String> {
+		self.s.get_string(fldname)
+	}
+
+	fn get_val(&self, fldname: &str) -> Result<Constant> {
+		self.s.get_val(fldname)
+	}
+
+	fn has_field(&self, fldname: &str) -> bool {
+		self.s.has_field(fldname)
+	}
+
+	fn close(&mut self) -> Result<()> {
+		self.s.close()
+	}
+}
+
+impl<S: UpdateScan> UpdateScan for SelectScan<S> {
+	fn set_i32(&mut self, fldname: &str, val: i32) -> Result<()> {
+		self.s.set_i32(fldname, val)
+	}
+
+	fn set_string(&mut self, fldname: &str, val: &str) -> Result<()> {
+		self.s.set_string(fldname, val)
+	}
+
+	fn set_val(&mut self, fldname: &str, val: &Constant) -> Result<()> {
+		self.s.set_val(fldname, val)
+	}
+
+	fn insert(&mut self) -> Result<()> {
+		self.s.insert()
+	}
+
+	fn delete(&mut self) -> Result<()> {
+		self.s.delete()
+	}
+
+	fn get_rid(&self) -> Result<RID> {
+		self.s.get_rid()
+	}
+
+	fn move_to_rid(&mut self, rid: RID) -> Result<()> {
+		self.s.move_to_rid(rid)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::{
+		fs,
+		sync::{Arc, Mutex},
+	};
+
+	use crate::{
+		buffer::manager::BufferMgr,
+		file::manager::FileMgr,
+		log::manager::LogMgr,
+		query::predicate::{Expression, Term},
+		record::{layout::Layout, schema::Schema, table_scan::TableScan},
+		tx::transaction::Transaction,
+	};
+
+	#[test]
+	fn test_select_by_field_equality() -> Result<()> {
+		let dir = "querytest/selectscan";
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+
+		let mut sch = Schema::new();
+		sch.add_i32_field("A");
+		sch.add_string_field("B", 9);
+		let layout = Arc::new(Layout::new(sch)?);
+
+		let mut ts = TableScan::new(tx.clone(), "T", layout)?;
+		for i in 0..40 {
+			ts.insert()?;
+			ts.set_i32("A", i % 4)?;
+			ts.set_string("B", &format!("rec{}", i))?;
+		}
+
+		let term = Term::new(Expression::Field("A".to_string()), Expression::Val(Constant::Int(2)));
+		let mut s = SelectScan::new(ts, Predicate::with_term(term));
+		s.before_first()?;
+		let mut selected = vec![];
+		while s.next()? {
+			assert_eq!(s.get_i32("A")?, 2);
+			selected.push(s.get_string("B")?);
+		}
+		assert_eq!(selected, (0..40).filter(|i| i % 4 == 2).map(|i| format!("rec{}", i)).collect::<Vec<_>>());
+
+		// deleting through the select scan removes only the selected records
+		s.before_first()?;
+		while s.next()? {
+			s.delete()?;
+		}
+		s.before_first()?;
+		assert!(!s.next()?);
+		s.close()?;
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,105 @@
+use anyhow::Result;
+use core::fmt;
+use std::{
+	collections::HashMap,
+	mem,
+};
+
+use crate::file::page::Page;
+
+use super::schema::{FieldType, Schema};
+
+#[derive(Debug)]
+enum LayoutError {
+	FieldNotFound(String),
+}
+
+impl std::error::Error for LayoutError {}
+impl fmt::Display for LayoutError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			LayoutError::FieldNotFound(fldname) => {
+				write!(f, "field {} not in layout", fldname)
+			}
+		}
+	}
+}
+
+// Where each field of a record lives within its slot.
+// A slot starts with an i32 flag telling whether it is in use.
+#[derive(Debug, Clone)]
+pub struct Layout {
+	schema: Schema,
+	offsets: HashMap<String, usize>,
+	slotsize: usize,
+}
+
+impl Layout {
+	pub fn new(schema: Schema) -> Result<Self> {
+		let mut offsets = HashMap::new();
+		let mut pos = mem::size_of::<i32>();
+		for fldname in schema.fields() {
+			offsets.insert(fldname.clone(), pos);
+			pos += Self::length_in_bytes(&schema, fldname)?;
+		}
+
+		Ok(Self {
+			schema,
+			offsets,
+			slotsize: pos,
+		})
+	}
+
+	// for a layout already computed, e.g. read back from the catalog
+	pub fn with_offsets(schema: Schema, offsets: HashMap<String, usize>, slotsize: usize) -> Self {
+		Self {
+			schema,
+			offsets,
+			slotsize,
+		}
+	}
+
+	pub fn schema(&self) -> &Schema {
+		&self.schema
+	}
+	pub fn offset(&self, fldname: &str) -> Result<usize> {
+		match self.offsets.get(fldname) {
+			Some(offset) => Ok(*offset),
+			None => Err(From::from(LayoutError::FieldNotFound(fldname.to_string()))),
+		}
+	}
+	pub fn slot_size(&self) -> usize {
+		self.slotsize
+	}
+
+	fn length_in_bytes(schema: &Schema, fldname: &str) -> Result<usize> {
+		match schema.field_type(fldname)? {
+			FieldType::INTEGER => Ok(mem::size_of::<i32>()),
+			FieldType::VARCHAR => Ok(Page::max_length(schema.field_length(fldname)?)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_offsets() -> Result<()> {
+		let mut sch = Schema::new();
+		sch.add_i32_field("A");
+		sch.add_string_field("B", 9);
+		sch.add_i32_field("C");
+		let layout = Layout::new(sch)?;
+
+		// flag: 0..4, A: 4..8, B: 8..21 (length + 9 bytes), C: 21..25
+		assert_eq!(layout.offset("A")?, 4);
+		assert_eq!(layout.offset("B")?, 8);
+		assert_eq!(layout.offset("C")?, 21);
+		assert_eq!(layout.slot_size(), 25);
+		assert!(layout.offset("D").is_err());
+		assert_eq!(layout.schema().fields(), ["A", "B", "C"]);
+
+		Ok(())
+	}
+}
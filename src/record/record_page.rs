@@ -0,0 +1,260 @@
+use anyhow::Result;
+use core::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::{file::block_id::BlockId, tx::transaction::Transaction};
+
+use super::{layout::Layout, schema::FieldType};
+
+#[derive(Debug)]
+enum RecordPageError {
+	// the field, the length of the value and the length of the field
+	StringTooLong(String, usize, usize),
+}
+
+impl std::error::Error for RecordPageError {}
+impl fmt::Display for RecordPageError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			RecordPageError::StringTooLong(fldname, len, max) => {
+				write!(f, "string of {} bytes does not fit in field {} of {} bytes", len, fldname, max)
+			}
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SlotFlag {
+	Empty = 0,
+	Used = 1,
+}
+
+// Stores records of one layout in a block, as an array of fixed-size slots.
+// A slot is addressed by its number; -1 stands for "before the first slot".
+pub struct RecordPage {
+	tx: Arc<Mutex<Transaction>>,
+	blk: BlockId,
+	layout: Arc<Layout>,
+}
+
+impl RecordPage {
+	pub fn new(tx: Arc<Mutex<Transaction>>, blk: BlockId, layout: Arc<Layout>) -> Result<Self> {
+		tx.lock().unwrap().pin(&blk)?;
+
+		Ok(Self { tx, blk, layout })
+	}
+
+	pub fn get_i32(&self, slot: i32, fldname: &str) -> Result<i32> {
+		let fldpos = self.field_offset(slot, fldname)?;
+		self.tx.lock().unwrap().get_i32(&self.blk, fldpos)
+	}
+	pub fn get_string(&self, slot: i32, fldname: &str) -> Result<String> {
+		let fldpos = self.field_offset(slot, fldname)?;
+		self.tx.lock().unwrap().get_string(&self.blk, fldpos)
+	}
+	pub fn set_i32(&self, slot: i32, fldname: &str, val: i32) -> Result<()> {
+		let fldpos = self.field_offset(slot, fldname)?;
+		self.tx.lock().unwrap().set_i32(&self.blk, fldpos, val, true)
+	}
+	// A longer string would overwrite the next field or slot.
+	pub fn set_string(&self, slot: i32, fldname: &str, val: &str) -> Result<()> {
+		let max = self.layout.schema().field_length(fldname)?;
+		if val.len() > max {
+			return Err(From::from(RecordPageError::StringTooLong(fldname.to_string(), val.len(), max)));
+		}
+		let fldpos = self.field_offset(slot, fldname)?;
+		self.tx.lock().unwrap().set_string(&self.blk, fldpos, val, true)
+	}
+
+	pub fn delete(&self, slot: i32) -> Result<()> {
+		self.set_flag(slot, SlotFlag::Empty)
+	}
+
+	// Makes every slot of the block empty and zeroes its fields.
+	// Bytes left at the end of the block, too few for a whole slot, are never used.
+	// Not logged: the block is new, so there is nothing to undo.
+	pub fn format(&self) -> Result<()> {
+		let sch = self.layout.schema();
+		let mut slot = 0;
+		while self.is_valid_slot(slot) {
+			let mut tx = self.tx.lock().unwrap();
+			let pos = self.offset(slot);
+			tx.set_i32(&self.blk, pos, SlotFlag::Empty as i32, false)?;
+			for fldname in sch.fields() {
+				let fldpos = pos + self.layout.offset(fldname)? as i32;
+				match sch.field_type(fldname)? {
+					FieldType::INTEGER => tx.set_i32(&self.blk, fldpos, 0, false)?,
+					FieldType::VARCHAR => tx.set_string(&self.blk, fldpos, "", false)?,
+				}
+			}
+			slot += 1;
+		}
+
+		Ok(())
+	}
+
+	// the next used slot after the given one
+	pub fn next_after(&self, slot: i32) -> Result<Option<i32>> {
+		self.search_after(slot, SlotFlag::Used)
+	}
+
+	// finds an empty slot after the given one and marks it used
+	pub fn insert_after(&self, slot: i32) -> Result<Option<i32>> {
+		let newslot = self.search_after(slot, SlotFlag::Empty)?;
+		if let Some(newslot) = newslot {
+			self.set_flag(newslot, SlotFlag::Used)?;
+		}
+
+		Ok(newslot)
+	}
+
+	pub fn block(&self) -> &BlockId {
+		&self.blk
+	}
+
+	fn set_flag(&self, slot: i32, flag: SlotFlag) -> Result<()> {
+		self.tx.lock().unwrap().set_i32(&self.blk, self.offset(slot), flag as i32, true)
+	}
+
+	fn search_after(&self, slot: i32, flag: SlotFlag) -> Result<Option<i32>> {
+		let mut slot = slot + 1;
+		while self.is_valid_slot(slot) {
+			if self.tx.lock().unwrap().get_i32(&self.blk, self.offset(slot))? == flag as i32 {
+				return Ok(Some(slot));
+			}
+			slot += 1;
+		}
+
+		Ok(None)
+	}
+
+	fn is_valid_slot(&self, slot: i32) -> bool {
+		self.offset(slot + 1) as u64 <= self.tx.lock().unwrap().block_size()
+	}
+
+	fn offset(&self, slot: i32) -> i32 {
+		slot * self.layout.slot_size() as i32
+	}
+
+	fn field_offset(&self, slot: i32, fldname: &str) -> Result<i32> {
+		Ok(self.offset(slot) + self.layout.offset(fldname)? as i32)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::fs;
+
+	use crate::{
+		buffer::manager::BufferMgr,
+		file::manager::FileMgr,
+		log::manager::LogMgr,
+		record::schema::Schema,
+	};
+
+	fn setup(dir: &str) -> Result<(Arc<Mutex<Transaction>>, Arc<Layout>)> {
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+		let tx = Arc::new(Mutex::new(Transaction::new(fm, lm, bm)?));
+
+		let mut sch = Schema::new();
+		sch.add_i32_field("A");
+		sch.add_string_field("B", 9);
+		let layout = Arc::new(Layout::new(sch)?);
+
+		Ok((tx, layout))
+	}
+
+	#[test]
+	fn test_insert_and_read_back() -> Result<()> {
+		let (tx, layout) = setup("recordtest/recordpage")?;
+
+		let blk = tx.lock().unwrap().append("recordpagefile")?;
+		let rp = RecordPage::new(tx.clone(), blk, layout.clone())?;
+		rp.format()?;
+		assert_eq!(rp.next_after(-1)?, None);
+
+		// 400 bytes / 21 bytes per slot
+		let mut slot = -1;
+		let mut count = 0;
+		while let Some(s) = rp.insert_after(slot)? {
+			rp.set_i32(s, "A", s * 10)?;
+			rp.set_string(s, "B", &format!("rec{}", s))?;
+			slot = s;
+			count += 1;
+		}
+		assert_eq!(count, 19);
+
+		rp.delete(3)?;
+		let mut slots = vec![];
+		let mut slot = -1;
+		while let Some(s) = rp.next_after(slot)? {
+			assert_eq!(rp.get_i32(s, "A")?, s * 10);
+			assert_eq!(rp.get_string(s, "B")?, format!("rec{}", s));
+			slots.push(s);
+			slot = s;
+		}
+		assert_eq!(slots.len(), 18);
+		assert!(!slots.contains(&3));
+		// the deleted slot is the first to be reused
+		assert_eq!(rp.insert_after(-1)?, Some(3));
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_set_string_rejects_too_long() -> Result<()> {
+		let (tx, layout) = setup("recordtest/recordpagetoolong")?;
+
+		let blk = tx.lock().unwrap().append("recordpagetoolongfile")?;
+		let rp = RecordPage::new(tx.clone(), blk, layout)?;
+		rp.format()?;
+		rp.insert_after(-1)?;
+		rp.insert_after(0)?;
+		rp.set_i32(1, "A", 7)?;
+
+		rp.set_string(0, "B", "ninechars")?;
+		assert!(rp.set_string(0, "B", "tencharsxx").is_err());
+		// neither the field nor the next slot is touched
+		assert_eq!(rp.get_string(0, "B")?, "ninechars");
+		assert_eq!(rp.get_i32(1, "A")?, 7);
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_format_empties_every_slot() -> Result<()> {
+		let (tx, layout) = setup("recordtest/recordpageformat")?;
+
+		let blk = tx.lock().unwrap().append("recordpageformatfile")?;
+		tx.lock().unwrap().pin(&blk)?;
+		// leftovers in the block, including its last byte past the final slot
+		for offset in (0..400).step_by(4) {
+			tx.lock().unwrap().set_i32(&blk, offset, 1, false)?;
+		}
+
+		let rp = RecordPage::new(tx.clone(), blk.clone(), layout)?;
+		rp.format()?;
+		assert_eq!(rp.next_after(-1)?, None);
+		for slot in 0..19 {
+			assert_eq!(rp.get_i32(slot, "A")?, 0);
+			assert_eq!(rp.get_string(slot, "B")?, "");
+		}
+		// 400 bytes / 21 bytes per slot: the last 1 byte holds no slot
+		assert_eq!(rp.insert_after(17)?, Some(18));
+		assert_eq!(rp.insert_after(18)?, None);
+		assert_eq!(tx.lock().unwrap().get_i32(&blk, 396)?, 1);
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
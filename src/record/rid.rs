@@ -0,0 +1,50 @@
+use core::fmt;
+
+use crate::file::block_id::BlockId;
+
+// Identifies a record by the block of its table file and its slot in the block.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct RID {
+	blknum: u64,
+	slot: i32,
+}
+
+impl RID {
+	pub fn new(blknum: u64, slot: i32) -> Self {
+		Self { blknum, slot }
+	}
+
+	pub fn block_number(&self) -> u64 {
+		self.blknum
+	}
+
+	pub fn slot(&self) -> i32 {
+		self.slot
+	}
+
+	// the block holding the record, in the given table file
+	pub fn block_id(&self, filename: &str) -> BlockId {
+		BlockId::new(filename, self.blknum)
+	}
+}
+
+impl fmt::Display for RID {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "[{}, {}]", self.blknum, self.slot)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_rid() {
+		let rid = RID::new(2, 5);
+		assert_eq!(rid, RID::new(2, 5));
+		assert_ne!(rid, RID::new(2, 6));
+		assert_ne!(rid, RID::new(3, 5));
+		assert_eq!(rid.to_string(), "[2, 5]");
+		assert_eq!(rid.block_id("tbl.tbl"), BlockId::new("tbl.tbl", 2));
+	}
+}
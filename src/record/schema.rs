@@ -0,0 +1,152 @@
+use anyhow::Result;
+use core::fmt;
+use std::collections::HashMap;
+
+#[derive(Debug)]
+enum SchemaError {
+	FieldNotFound(String),
+	UnknownFieldType(i32),
+}
+
+impl std::error::Error for SchemaError {}
+impl fmt::Display for SchemaError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			SchemaError::FieldNotFound(fldname) => {
+				write!(f, "field {} not found", fldname)
+			}
+			SchemaError::UnknownFieldType(code) => {
+				write!(f, "unknown field type {}", code)
+			}
+		}
+	}
+}
+
+// same values as java.sql.Types, as in the original SimpleDB
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum FieldType {
+	INTEGER = 4,
+	VARCHAR = 12,
+}
+
+impl TryFrom<i32> for FieldType {
+	type Error = anyhow::Error;
+
+	fn try_from(code: i32) -> Result<Self> {
+		match code {
+			4 => Ok(FieldType::INTEGER),
+			12 => Ok(FieldType::VARCHAR),
+			_ => Err(From::from(SchemaError::UnknownFieldType(code))),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FieldInfo {
+	fldtype: FieldType,
+	// the max number of characters of a string, ignored for an integer
+	length: usize,
+}
+
+// The names and types of a table's fields, in the order they were added.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+	fields: Vec<String>,
+	info: HashMap<String, FieldInfo>,
+}
+
+impl Schema {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn add_field(&mut self, fldname: &str, fldtype: FieldType, length: usize) {
+		if !self.info.contains_key(fldname) {
+			self.fields.push(fldname.to_string());
+		}
+		self.info.insert(fldname.to_string(), FieldInfo { fldtype, length });
+	}
+	pub fn add_i32_field(&mut self, fldname: &str) {
+		self.add_field(fldname, FieldType::INTEGER, 0);
+	}
+	pub fn add_string_field(&mut self, fldname: &str, length: usize) {
+		self.add_field(fldname, FieldType::VARCHAR, length);
+	}
+	// copies a field of another schema
+	pub fn add(&mut self, fldname: &str, sch: &Schema) -> Result<()> {
+		let fldtype = sch.field_type(fldname)?;
+		let length = sch.field_length(fldname)?;
+		self.add_field(fldname, fldtype, length);
+
+		Ok(())
+	}
+	pub fn add_all(&mut self, sch: &Schema) -> Result<()> {
+		for fldname in sch.fields() {
+			self.add(fldname, sch)?;
+		}
+
+		Ok(())
+	}
+
+	pub fn fields(&self) -> &[String] {
+		&self.fields
+	}
+	pub fn has_field(&self, fldname: &str) -> bool {
+		self.info.contains_key(fldname)
+	}
+	pub fn field_type(&self, fldname: &str) -> Result<FieldType> {
+		Ok(self.field_info(fldname)?.fldtype)
+	}
+	pub fn field_length(&self, fldname: &str) -> Result<usize> {
+		Ok(self.field_info(fldname)?.length)
+	}
+
+	fn field_info(&self, fldname: &str) -> Result<&FieldInfo> {
+		match self.info.get(fldname) {
+			Some(info) => Ok(info),
+			None => Err(From::from(SchemaError::FieldNotFound(fldname.to_string()))),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_schema_fields() -> Result<()> {
+		let mut sch = Schema::new();
+		sch.add_i32_field("A");
+		sch.add_string_field("B", 9);
+		sch.add_field("C", FieldType::INTEGER, 0);
+
+		assert_eq!(sch.fields(), ["A", "B", "C"]);
+		assert!(sch.has_field("B"));
+		assert!(!sch.has_field("D"));
+		assert_eq!(sch.field_type("A")?, FieldType::INTEGER);
+		assert_eq!(sch.field_type("B")?, FieldType::VARCHAR);
+		assert_eq!(sch.field_length("B")?, 9);
+		assert!(sch.field_type("D").is_err());
+		assert_eq!(FieldType::try_from(FieldType::VARCHAR as i32)?, FieldType::VARCHAR);
+		assert!(FieldType::try_from(5).is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_add_from_another_schema() -> Result<()> {
+		let mut sch1 = Schema::new();
+		sch1.add_i32_field("A");
+		sch1.add_string_field("B", 9);
+
+		let mut sch2 = Schema::new();
+		sch2.add("B", &sch1)?;
+		sch2.add_all(&sch1)?;
+		// a field already in the schema keeps its position
+		assert_eq!(sch2.fields(), ["B", "A"]);
+		assert_eq!(sch2.field_length("B")?, 9);
+		assert!(sch2.add("D", &sch1).is_err());
+
+		Ok(())
+	}
+}
@@ -0,0 +1,325 @@
+use anyhow::Result;
+use core::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	file::block_id::BlockId,
+	query::{
+		constant::Constant,
+		scan::{Scan, UpdateScan},
+	},
+	tx::transaction::Transaction,
+};
+
+use super::{layout::Layout, record_page::RecordPage, rid::RID, schema::FieldType};
+
+#[derive(Debug)]
+enum TableScanError {
+	Closed,
+}
+
+impl std::error::Error for TableScanError {}
+impl fmt::Display for TableScanError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			TableScanError::Closed => {
+				write!(f, "table scan already closed")
+			}
+		}
+	}
+}
+
+// Walks the records of a table, block by block and slot by slot.
+// The table is stored in the file "<tblname>.tbl".
+pub struct TableScan {
+	tx: Arc<Mutex<Transaction>>,
+	layout: Arc<Layout>,
+	// None once the scan is closed
+	rp: Option<RecordPage>,
+	filename: String,
+	currentslot: i32,
+}
+
+impl TableScan {
+	pub fn new(tx: Arc<Mutex<Transaction>>, tblname: &str, layout: Arc<Layout>) -> Result<Self> {
+		let mut scan = Self {
+			tx,
+			layout,
+			rp: None,
+			filename: format!("{}.tbl", tblname),
+			currentslot: -1,
+		};
+		if scan.tx.lock().unwrap().size(&scan.filename)? == 0 {
+			scan.move_to_new_block()?;
+		} else {
+			scan.move_to_block(0)?;
+		}
+
+		Ok(scan)
+	}
+
+	fn rp(&self) -> Result<&RecordPage> {
+		match &self.rp {
+			Some(rp) => Ok(rp),
+			None => Err(From::from(TableScanError::Closed)),
+		}
+	}
+
+	fn move_to_block(&mut self, blknum: u64) -> Result<()> {
+		self.close()?;
+		let blk = BlockId::new(&self.filename, blknum);
+		self.rp = Some(RecordPage::new(self.tx.clone(), blk, self.layout.clone())?);
+		self.currentslot = -1;
+
+		Ok(())
+	}
+
+	fn move_to_new_block(&mut self) -> Result<()> {
+		self.close()?;
+		let blk = self.tx.lock().unwrap().append(&self.filename)?;
+		let rp = RecordPage::new(self.tx.clone(), blk, self.layout.clone())?;
+		rp.format()?;
+		self.rp = Some(rp);
+		self.currentslot = -1;
+
+		Ok(())
+	}
+
+	fn at_last_block(&self) -> Result<bool> {
+		let size = self.tx.lock().unwrap().size(&self.filename)?;
+		Ok(self.rp()?.block().number() == size - 1)
+	}
+}
+
+impl Scan for TableScan {
+	fn before_first(&mut self) -> Result<()> {
+		self.move_to_block(0)
+	}
+
+	fn next(&mut self) -> Result<bool> {
+		loop {
+			if let Some(slot) = self.rp()?.next_after(self.currentslot)? {
+				self.currentslot = slot;
+				return Ok(true);
+			}
+			if self.at_last_block()? {
+				return Ok(false);
+			}
+			let blknum = self.rp()?.block().number();
+			self.move_to_block(blknum + 1)?;
+		}
+	}
+
+	fn get_i32(&self, fldname: &str) -> Result<i32> {
+		self.rp()?.get_i32(self.currentslot, fldname)
+	}
+
+	fn get_string(&self, fldname: &str) -> Result<String> {
+		self.rp()?.get_string(self.currentslot, fldname)
+	}
+
+	fn get_val(&self, fldname: &str) -> Result<Constant> {
+		match self.layout.schema().field_type(fldname)? {
+			FieldType::INTEGER => Ok(Constant::Int(self.get_i32(fldname)?)),
+			FieldType::VARCHAR => Ok(Constant::Str(self.get_string(fldname)?)),
+		}
+	}
+
+	fn has_field(&self, fldname: &str) -> bool {
+		self.layout.schema().has_field(fldname)
+	}
+
+	fn close(&mut self) -> Result<()> {
+		if let Some(rp) = self.rp.take() {
+			self.tx.lock().unwrap().unpin(rp.block())?;
+		}
+
+		Ok(())
+	}
+}
+
+// The setters write through the tx, so they are logged and undone on rollback.
+impl UpdateScan for TableScan {
+	fn set_i32(&mut self, fldname: &str, val: i32) -> Result<()> {
+		self.rp()?.set_i32(self.currentslot, fldname, val)
+	}
+
+	fn set_string(&mut self, fldname: &str, val: &str) -> Result<()> {
+		self.rp()?.set_string(self.currentslot, fldname, val)
+	}
+
+	// fails if the value does not have the type of the field
+	fn set_val(&mut self, fldname: &str, val: &Constant) -> Result<()> {
+		match self.layout.schema().field_type(fldname)? {
+			FieldType::INTEGER => self.set_i32(fldname, val.as_i32()?),
+			FieldType::VARCHAR => self.set_string(fldname, val.as_string()?),
+		}
+	}
+
+	// moves to a free slot after the current one, appending a block if none is left
+	fn insert(&mut self) -> Result<()> {
+		loop {
+			if let Some(slot) = self.rp()?.insert_after(self.currentslot)? {
+				self.currentslot = slot;
+				return Ok(());
+			}
+			if self.at_last_block()? {
+				self.move_to_new_block()?;
+			} else {
+				let blknum = self.rp()?.block().number();
+				self.move_to_block(blknum + 1)?;
+			}
+		}
+	}
+
+	fn delete(&mut self) -> Result<()> {
+		self.rp()?.delete(self.currentslot)
+	}
+
+	fn get_rid(&self) -> Result<RID> {
+		Ok(RID::new(self.rp()?.block().number(), self.currentslot))
+	}
+
+	fn move_to_rid(&mut self, rid: RID) -> Result<()> {
+		self.close()?;
+		let blk = rid.block_id(&self.filename);
+		self.rp = Some(RecordPage::new(self.tx.clone(), blk, self.layout.clone())?);
+		self.currentslot = rid.slot();
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::{collections::HashSet, fs};
+
+	use crate::{
+		buffer::manager::BufferMgr,
+		file::manager::FileMgr,
+		log::manager::LogMgr,
+		record::schema::Schema,
+	};
+
+	type Managers = (Arc<Mutex<FileMgr>>, Arc<Mutex<LogMgr>>, Arc<Mutex<BufferMgr>>);
+
+	fn new_tx((fm, lm, bm): &Managers) -> Result<Arc<Mutex<Transaction>>> {
+		let tx = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		Ok(Arc::new(Mutex::new(tx)))
+	}
+
+	fn setup(dir: &str) -> Result<(Managers, Arc<Layout>)> {
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)?));
+
+		let mut sch = Schema::new();
+		sch.add_i32_field("A");
+		sch.add_string_field("B", 9);
+		let layout = Arc::new(Layout::new(sch)?);
+
+		Ok(((fm, lm, bm), layout))
+	}
+
+	#[test]
+	fn test_scan_spans_blocks() -> Result<()> {
+		let (mgrs, layout) = setup("recordtest/tablescan")?;
+		let tx = new_tx(&mgrs)?;
+
+		let mut ts = TableScan::new(tx.clone(), "T", layout.clone())?;
+		let mut inserted = HashSet::new();
+		for _ in 0..50 {
+			ts.insert()?;
+			inserted.insert(ts.get_rid()?);
+		}
+		assert_eq!(inserted.len(), 50);
+		// 19 slots fit in a block of 400 bytes
+		assert_eq!(tx.lock().unwrap().size("T.tbl")?, 3);
+
+		ts.before_first()?;
+		let mut scanned = HashSet::new();
+		while ts.next()? {
+			scanned.insert(ts.get_rid()?);
+		}
+		assert_eq!(scanned, inserted);
+		ts.close()?;
+		assert!(ts.next().is_err());
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_rescan_skips_deleted() -> Result<()> {
+		let (mgrs, layout) = setup("recordtest/tablescandelete")?;
+		let tx = new_tx(&mgrs)?;
+
+		let mut ts = TableScan::new(tx.clone(), "T", layout.clone())?;
+		for i in 0..50 {
+			ts.insert()?;
+			ts.set_i32("A", i)?;
+			ts.set_string("B", &format!("rec{}", i))?;
+		}
+
+		ts.before_first()?;
+		while ts.next()? {
+			if ts.get_i32("A")? % 2 == 0 {
+				ts.delete()?;
+			}
+		}
+
+		ts.before_first()?;
+		let mut remaining = vec![];
+		while ts.next()? {
+			let a = ts.get_i32("A")?;
+			assert_eq!(ts.get_string("B")?, format!("rec{}", a));
+			remaining.push(a);
+		}
+		assert_eq!(remaining, (0..50).filter(|i| i % 2 == 1).collect::<Vec<_>>());
+
+		// a deleted slot is reused by the next insert
+		ts.before_first()?;
+		ts.insert()?;
+		assert_eq!(ts.get_rid()?, RID::new(0, 0));
+		ts.close()?;
+
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_rollback_undoes_inserted_values() -> Result<()> {
+		let (mgrs, layout) = setup("recordtest/tablescanrollback")?;
+
+		let tx = new_tx(&mgrs)?;
+		let mut ts = TableScan::new(tx.clone(), "T", layout.clone())?;
+		ts.insert()?;
+		ts.set_i32("A", 1)?;
+		ts.close()?;
+		tx.lock().unwrap().commit()?;
+
+		let tx = new_tx(&mgrs)?;
+		let mut ts = TableScan::new(tx.clone(), "T", layout.clone())?;
+		ts.next()?;
+		ts.set_i32("A", 2)?;
+		ts.insert()?;
+		ts.set_i32("A", 3)?;
+		ts.close()?;
+		tx.lock().unwrap().rollback()?;
+
+		let tx = new_tx(&mgrs)?;
+		let mut ts = TableScan::new(tx.clone(), "T", layout)?;
+		assert!(ts.next()?);
+		assert_eq!(ts.get_i32("A")?, 1);
+		assert!(!ts.next()?);
+		ts.close()?;
+		tx.lock().unwrap().commit()?;
+
+		Ok(())
+	}
+}
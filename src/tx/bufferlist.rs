@@ -13,7 +13,8 @@ use crate::{
 #[derive(Debug, Clone)]
 pub struct BufferList {
 	buffers: HashMap<BlockId, Arc<Mutex<Buffer>>>,
-	pins: Vec<BlockId>,
+	// how many times this tx has pinned each block
+	pins: HashMap<BlockId, usize>,
 	bm: Arc<Mutex<BufferMgr>>,
 }
 
@@ -21,7 +22,7 @@ impl BufferList {
 	pub fn new(bm: Arc<Mutex<BufferMgr>>) -> Self {
 		Self {
 			buffers: HashMap::new(),
-			pins: vec![],
+			pins: HashMap::new(),
 			bm,
 		}
 	}
@@ -31,24 +32,30 @@ impl BufferList {
 	pub fn pin(&mut self, blk: &BlockId) -> Result<()> {
 		let buff = self.bm.lock().unwrap().pin(blk)?;
 		self.buffers.insert(blk.clone(), buff);
-		self.pins.push(blk.clone());
+		*self.pins.entry(blk.clone()).or_insert(0) += 1;
 
 		Ok(())
 	}
 	pub fn unpin(&mut self, blk: &BlockId) -> Result<()> {
 		if let Some(buff) = self.buffers.get(blk) {
 			let _ = self.bm.lock().unwrap().unpin(Arc::clone(buff));
-			self.pins.retain(|x| x == blk);
-			if self.pins.contains(blk) {
-				self.buffers.remove(blk);
+			// drop one pin of blk; the buffer stays listed while other pins remain
+			if let Some(count) = self.pins.get_mut(blk) {
+				*count -= 1;
+				if *count == 0 {
+					self.pins.remove(blk);
+					self.buffers.remove(blk);
+				}
 			}
 		}
 		Ok(())
 	}
 	pub fn unpin_all(&mut self) -> Result<()> {
-		for blk in self.pins.iter() {
+		for (blk, count) in self.pins.iter() {
 			if let Some(buff) = self.buffers.get(blk) {
-				self.bm.lock().unwrap().unpin(buff.clone())?;
+				for _ in 0..*count {
+					self.bm.lock().unwrap().unpin(buff.clone())?;
+				}
 			}
 		}
 		self.buffers.clear();
@@ -57,3 +64,78 @@ impl BufferList {
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::{file::manager::FileMgr, log::manager::LogMgr};
+
+	fn setup(dir: &str) -> Arc<Mutex<BufferMgr>> {
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400).unwrap()));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log").unwrap()));
+		Arc::new(Mutex::new(BufferMgr::new(fm, lm, 3).unwrap()))
+	}
+
+	#[test]
+	fn test_pin_and_get_buffer() -> Result<()> {
+		let bm = setup("bufferlisttest/pin");
+		let mut bl = BufferList::new(bm.clone());
+		let blk = BlockId::new("testfile", 1);
+		assert!(bl.get_buffer(&blk).is_none());
+
+		bl.pin(&blk)?;
+		let buff = bl.get_buffer(&blk).unwrap();
+		assert_eq!(buff.lock().unwrap().block(), Some(&blk));
+		assert_eq!(bm.lock().unwrap().available()?, 2);
+
+		bl.unpin_all()?;
+		assert!(bl.get_buffer(&blk).is_none());
+		assert_eq!(bm.lock().unwrap().available()?, 3);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_unpin_keeps_other_pins() -> Result<()> {
+		let bm = setup("bufferlisttest/unpin");
+		let mut bl = BufferList::new(bm.clone());
+		let blk1 = BlockId::new("testfile", 1);
+		let blk2 = BlockId::new("testfile", 2);
+
+		bl.pin(&blk1)?;
+		bl.pin(&blk1)?;
+		bl.pin(&blk2)?;
+
+		bl.unpin(&blk1)?;
+		assert!(bl.get_buffer(&blk1).is_some());
+		assert!(bl.get_buffer(&blk2).is_some());
+		assert!(bl.get_buffer(&blk1).unwrap().lock().unwrap().is_pinned());
+
+		bl.unpin(&blk1)?;
+		assert!(bl.get_buffer(&blk1).is_none());
+		assert!(bl.get_buffer(&blk2).is_some());
+		assert_eq!(bm.lock().unwrap().available()?, 2);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_unpin_all_releases_every_pin() -> Result<()> {
+		let bm = setup("bufferlisttest/unpinall");
+		let mut bl = BufferList::new(bm.clone());
+		let blk = BlockId::new("testfile", 1);
+
+		bl.pin(&blk)?;
+		bl.pin(&blk)?;
+		bl.unpin(&blk)?;
+		assert!(bl.get_buffer(&blk).unwrap().lock().unwrap().is_pinned());
+		bl.pin(&blk)?;
+
+		bl.unpin_all()?;
+		assert!(bl.get_buffer(&blk).is_none());
+		assert_eq!(bm.lock().unwrap().available()?, 3);
+
+		Ok(())
+	}
+}
@@ -1,134 +1,649 @@
 use anyhow::Result;
 use core::fmt;
 use std::{
-	collections::HashMap,
-	sync::{Arc, Mutex, MutexGuard},
-	thread,
+	collections::{HashMap, HashSet, VecDeque},
+	sync::{Arc, Condvar, Mutex},
 	time::{Duration, SystemTime},
 };
 
 use crate::file::block_id::BlockId;
 
-const MAX_TIME: u64 = 10_000;
+// how long a lock request waits by default before giving up
+const MAX_TIME: Duration = Duration::from_millis(10_000);
 
 #[derive(Debug)]
-enum LockTableError {
-	LockAbort,
+pub enum LockTableError {
+	LockAbort { key: LockTableKey, kind: LockKind },
 	LockFailed(String),
+	Deadlock(i32),
 }
 
 impl std::error::Error for LockTableError {}
 impl fmt::Display for LockTableError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		match self {
-			LockTableError::LockAbort => {
-				write!(f, "lock abort")
+			LockTableError::LockAbort { key, kind } => {
+				write!(f, "lock abort: {} lock on {}", kind, key)
 			}
 			LockTableError::LockFailed(s) => {
 				write!(f, "lock failed: {}", s)
 			}
+			LockTableError::Deadlock(txnum) => {
+				write!(f, "deadlock: transaction {} aborted", txnum)
+			}
 		}
 	}
 }
 
-#[derive(Clone, Eq, PartialEq, Hash)]
+// Ordered blocks first, so that locks can be taken in one canonical order.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum LockTableKey {
 	BID(BlockId),
 	DUMMY(u64),
 }
 
-macro_rules! lock {
-	($self:ident, $processing:block, $msg:literal) => {
-		if ($self.l.lock().is_ok())
-			$processing
-		else {
-			Err(From::from(LockTableError::LockFailed(String::from($msg))))
+impl fmt::Display for LockTableKey {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			LockTableKey::BID(blk) => write!(f, "{}", blk),
+			LockTableKey::DUMMY(n) => write!(f, "[dummy {}]", n),
 		}
 	}
 }
-macro_rules! sleep {
-	($self:ident, $processing:block) => {
-		let timestamp = SystemTime::now();
-		while !waiting_too_long(timestamp) {
-			let mut locks = $self.locks.lock().unwrap();
-			$processing
-			drop(locks);
-			thread::sleep(Duration::new(1, 0));
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum LockKind {
+	Shared,
+	Exclusive,
+}
+
+impl fmt::Display for LockKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			LockKind::Shared => write!(f, "S"),
+			LockKind::Exclusive => write!(f, "X"),
 		}
-		return Err(From::from(LockTableError::LockAbort));
 	}
 }
 
+// The txs holding a lock on one key. A tx holding the X-lock may also be
+// listed as a shared holder, from the S-lock it held before upgrading.
+// Waiting requests are granted in arrival order, so a stream of S-locks can't
+// starve an X-lock. A tx that already holds the key skips the queue, otherwise
+// upgrading its S-lock would wait for the txs waiting on its own lock.
+#[derive(Default)]
+struct Lock {
+	shared: HashSet<i32>,
+	exclusive: Option<i32>,
+	queue: VecDeque<(i32, LockKind)>,
+}
+
+impl Lock {
+	fn is_free(&self) -> bool {
+		self.shared.is_empty() && self.exclusive.is_none() && self.queue.is_empty()
+	}
+	fn has_other_x_lock(&self, txnum: i32) -> bool {
+		self.exclusive.is_some_and(|t| t != txnum)
+	}
+	fn has_other_holders(&self, txnum: i32) -> bool {
+		self.has_other_x_lock(txnum) || self.shared.iter().any(|&t| t != txnum)
+	}
+	fn holders(&self) -> HashSet<i32> {
+		self.shared.iter().copied().chain(self.exclusive).collect()
+	}
+	fn can_grant(&self, txnum: i32, kind: LockKind) -> bool {
+		let compatible = match kind {
+			LockKind::Exclusive => !self.has_other_holders(txnum),
+			LockKind::Shared => !self.has_other_x_lock(txnum),
+		};
+		if !compatible {
+			return false;
+		}
+		if self.holders().contains(&txnum) {
+			return true;
+		}
+		self.ahead_of(txnum, kind).is_empty()
+	}
+	fn grant(&mut self, txnum: i32, kind: LockKind) {
+		self.queue.retain(|&(t, _)| t != txnum);
+		match kind {
+			LockKind::Exclusive => self.exclusive = Some(txnum),
+			LockKind::Shared => {
+				self.shared.insert(txnum);
+			}
+		}
+	}
+	// the queued requests that have to be granted before this one
+	fn ahead_of(&self, txnum: i32, kind: LockKind) -> Vec<i32> {
+		self.queue
+			.iter()
+			.take_while(|&&(t, _)| t != txnum)
+			.filter(|&&(_, k)| kind == LockKind::Exclusive || k == LockKind::Exclusive)
+			.map(|&(t, _)| t)
+			.collect()
+	}
+}
+
+#[derive(Default)]
+struct LockState {
+	locks: HashMap<LockTableKey, Lock>,
+	// wait-for graph: the txs each waiting tx waits for
+	waits_for: HashMap<i32, HashSet<i32>>,
+	// waiting txs chosen to be aborted to break a deadlock
+	victims: HashSet<i32>,
+	// a condvar per contended key, with the number of txs waiting on it
+	conds: HashMap<LockTableKey, (Arc<Condvar>, usize)>,
+	// the key each waiting tx waits on
+	waiting_on: HashMap<i32, LockTableKey>,
+	stats: LockTableStats,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockTableStats {
+	pub requests: u64,
+	// requests that could not be granted right away
+	pub waits: u64,
+	// requests that timed out or were chosen to break a deadlock
+	pub aborts: u64,
+	// how many times a waiting request has been woken up
+	pub wakeups: u64,
+	// keys currently locked or waited on
+	pub size: usize,
+}
+
+// Locks are requested on behalf of a tx, so that the table knows who holds and
+// who waits for each key. A request that would close a cycle in the wait-for
+// graph aborts the youngest tx (the largest txnum) of the cycle with a Deadlock.
 pub struct LockTable {
-	locks: Arc<Mutex<HashMap<LockTableKey, i32>>>,
+	state: Mutex<LockState>,
+	// how long s_lock/x_lock wait before giving up
+	timeout: Duration,
 }
 
 impl LockTable {
 	pub fn new() -> Self {
+		Self::with_timeout(MAX_TIME)
+	}
+
+	pub fn with_timeout(timeout: Duration) -> Self {
 		Self {
-			locks: Arc::new(Mutex::new(HashMap::new())),
+			state: Mutex::new(LockState::default()),
+			timeout,
 		}
 	}
 
-	pub fn s_lock(&mut self, key: &LockTableKey) -> Result<()> {
-		let timestamp = SystemTime::now();
+	pub fn timeout(&self) -> Duration {
+		self.timeout
+	}
 
-		while !waiting_too_long(timestamp) {
-			let mut locks = self.locks.lock().unwrap();
-			if !has_x_lock(&locks, &key) {
-				*locks.entry(key.clone()).or_insert(0) += 1;
-				return Ok(());
-			}
-			drop(locks); // release
-			thread::sleep(Duration::new(1, 0));
+	pub fn stats(&self) -> LockTableStats {
+		let state = self.state.lock().unwrap();
+		LockTableStats {
+			size: state.locks.len(),
+			..state.stats
 		}
+	}
 
-		Err(From::from(LockTableError::LockAbort))
+	pub fn s_lock(&self, txnum: i32, key: &LockTableKey) -> Result<()> {
+		self.s_lock_with_timeout(txnum, key, self.timeout)
 	}
-	pub fn x_lock(&mut self, key: &LockTableKey) -> Result<()> {
-		let timestamp = SystemTime::now();
+	pub fn s_lock_with_timeout(&self, txnum: i32, key: &LockTableKey, timeout: Duration) -> Result<()> {
+		self.lock(txnum, key, timeout, LockKind::Shared)
+	}
+	pub fn x_lock(&self, txnum: i32, key: &LockTableKey) -> Result<()> {
+		self.x_lock_with_timeout(txnum, key, self.timeout)
+	}
+	pub fn x_lock_with_timeout(&self, txnum: i32, key: &LockTableKey, timeout: Duration) -> Result<()> {
+		self.lock(txnum, key, timeout, LockKind::Exclusive)
+	}
+	pub fn unlock(&self, txnum: i32, key: &LockTableKey) -> Result<()> {
+		let mut state = self.state.lock().unwrap();
 
-		while !waiting_too_long(timestamp) {
-			let mut locks = self.locks.lock().unwrap();
-			if !has_other_s_locks(&locks, &key) {
-				*locks.entry(key.clone()).or_insert(-1) = -1;
-				return Ok(());
+		if let Some(lock) = state.locks.get_mut(key) {
+			lock.shared.remove(&txnum);
+			if lock.exclusive == Some(txnum) {
+				lock.exclusive = None;
 			}
-			drop(locks); // release
-			thread::sleep(Duration::new(1, 0));
+			if lock.is_free() {
+				state.locks.remove(key);
+			}
+		}
+		// only the waiters on this key can make progress
+		if let Some((cond, _)) = state.conds.get(key) {
+			cond.notify_all();
 		}
 
-		Err(From::from(LockTableError::LockAbort))
+		Ok(())
 	}
-	pub fn unlock(&mut self, key: &LockTableKey) -> Result<()> {
-		let mut locks = self.locks.lock().unwrap();
 
-		let val = get_lock_val(&locks, &key);
-		if val > 1 {
-			locks.entry(key.clone()).or_insert(val - 1);
-		} else {
-			locks.remove(&key);
+	// Grants the lock only if that is possible right now; never waits.
+	pub fn try_s_lock(&self, txnum: i32, key: &LockTableKey) -> Result<bool> {
+		self.try_lock(txnum, key, LockKind::Shared)
+	}
+	pub fn try_x_lock(&self, txnum: i32, key: &LockTableKey) -> Result<bool> {
+		self.try_lock(txnum, key, LockKind::Exclusive)
+	}
+
+	fn try_lock(&self, txnum: i32, key: &LockTableKey, kind: LockKind) -> Result<bool> {
+		let mut state = self.state.lock().unwrap();
+		state.stats.requests += 1;
+
+		let lock = state.locks.entry(key.clone()).or_default();
+		if lock.can_grant(txnum, kind) {
+			lock.grant(txnum, kind);
+			return Ok(true);
+		}
+		if lock.is_free() {
+			state.locks.remove(key);
 		}
 
-		return Ok(());
+		Ok(false)
 	}
-}
 
-fn has_x_lock(locks: &MutexGuard<HashMap<LockTableKey, i32>>, key: &LockTableKey) -> bool {
-	get_lock_val(locks, key) < 0 
+	fn lock(&self, txnum: i32, key: &LockTableKey, timeout: Duration, kind: LockKind) -> Result<()> {
+		let timestamp = SystemTime::now();
+		let mut state = self.state.lock().unwrap();
+		state.stats.requests += 1;
+
+		loop {
+			if state.victims.remove(&txnum) {
+				stop_waiting(&mut state, txnum, key);
+				return Err(From::from(LockTableError::Deadlock(txnum)));
+			}
+
+			// a tx's own lock never blocks it, so an S-lock can be upgraded
+			// once the other holders are gone
+			let lock = state.locks.entry(key.clone()).or_default();
+			if lock.can_grant(txnum, kind) {
+				lock.grant(txnum, kind);
+				state.waits_for.remove(&txnum);
+				return Ok(());
+			}
+			if waiting_too_long(timestamp, timeout) {
+				stop_waiting(&mut state, txnum, key);
+				return Err(From::from(LockTableError::LockAbort {
+					key: key.clone(),
+					kind,
+				}));
+			}
+
+			let lock = state.locks.get_mut(key).unwrap();
+			let first_wait = !lock.queue.iter().any(|&(t, _)| t == txnum);
+			if first_wait {
+				lock.queue.push_back((txnum, kind));
+			}
+			let mut blockers = lock.holders();
+			blockers.extend(lock.ahead_of(txnum, kind));
+			blockers.remove(&txnum);
+			if first_wait {
+				state.stats.waits += 1;
+			}
+			state.waits_for.insert(txnum, blockers);
+			if let Some(cycle) = find_cycle(&state.waits_for, txnum) {
+				let victim = *cycle.iter().max().unwrap();
+				if victim == txnum {
+					stop_waiting(&mut state, txnum, key);
+					return Err(From::from(LockTableError::Deadlock(txnum)));
+				}
+				state.victims.insert(victim);
+				if let Some((cond, _)) = state.waiting_on.get(&victim).and_then(|k| state.conds.get(k)) {
+					cond.notify_all();
+				}
+			}
+
+			let entry = state
+				.conds
+				.entry(key.clone())
+				.or_insert_with(|| (Arc::new(Condvar::new()), 0));
+			entry.1 += 1;
+			let cond = Arc::clone(&entry.0);
+			state.waiting_on.insert(txnum, key.clone());
+
+			let waited = SystemTime::now().duration_since(timestamp).unwrap();
+			let (guard, result) = cond.wait_timeout(state, timeout.saturating_sub(waited)).unwrap();
+			state = guard;
+			if !result.timed_out() {
+				state.stats.wakeups += 1;
+			}
+
+			state.waiting_on.remove(&txnum);
+			let entry = state.conds.get_mut(key).unwrap();
+			entry.1 -= 1;
+			if entry.1 == 0 {
+				state.conds.remove(key);
+			}
+		}
+	}
 }
-fn has_other_s_locks(locks: &MutexGuard<HashMap<LockTableKey, i32>>, key: &LockTableKey) -> bool {
-	get_lock_val(locks, key) > 1
+
+// Forgets a request that is aborted.
+fn stop_waiting(state: &mut LockState, txnum: i32, key: &LockTableKey) {
+	state.stats.aborts += 1;
+	state.waits_for.remove(&txnum);
+	if let Some(lock) = state.locks.get_mut(key) {
+		lock.queue.retain(|&(t, _)| t != txnum);
+		if lock.is_free() {
+			state.locks.remove(key);
+		}
+	}
+	// the requests queued behind this one may be grantable now
+	if let Some((cond, _)) = state.conds.get(key) {
+		cond.notify_all();
+	}
 }
-fn get_lock_val(locks: &MutexGuard<HashMap<LockTableKey, i32>>, key: &LockTableKey) -> i32 {
-	match locks.get(&key) {
-		Some(&ival) => ival,
-		None => 0,
+
+// The txs on a path of the wait-for graph from txnum back to itself, if any.
+fn find_cycle(waits_for: &HashMap<i32, HashSet<i32>>, txnum: i32) -> Option<Vec<i32>> {
+	let mut visited = HashSet::new();
+	let mut stack = vec![vec![txnum]];
+	while let Some(path) = stack.pop() {
+		let last = *path.last().unwrap();
+		for &next in waits_for.get(&last).into_iter().flatten() {
+			if next == txnum {
+				return Some(path);
+			}
+			if visited.insert(next) {
+				let mut path = path.clone();
+				path.push(next);
+				stack.push(path);
+			}
+		}
 	}
+
+	None
 }
 
-fn waiting_too_long(starttime: SystemTime) -> bool {
+fn waiting_too_long(starttime: SystemTime, timeout: Duration) -> bool {
 	let now = SystemTime::now();
 	let diff = now.duration_since(starttime).unwrap();
-	diff.as_millis() as u64 > MAX_TIME
+	diff >= timeout
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::{sync::Arc, thread, time::Instant};
+
+	fn key(n: u64) -> LockTableKey {
+		LockTableKey::BID(BlockId::new("locktablefile", n))
+	}
+
+	// an X-lock is requested while holding the S-lock, as ConcurrencyMgr does
+	fn x_lock(lt: &LockTable, txnum: i32, key: &LockTableKey) -> Result<()> {
+		lt.s_lock(txnum, key)?;
+		lt.x_lock(txnum, key)
+	}
+
+	#[test]
+	fn test_shared_and_exclusive_locks() -> Result<()> {
+		let lt = LockTable::new();
+		let timeout = Duration::from_millis(100);
+
+		// S-locks are shared
+		lt.s_lock(1, &key(8))?;
+		lt.s_lock_with_timeout(2, &key(8), timeout)?;
+		lt.unlock(1, &key(8))?;
+		lt.unlock(2, &key(8))?;
+
+		// an X-lock excludes every other lock, even without a prior S-lock
+		lt.x_lock(1, &key(8))?;
+		assert!(lt.s_lock_with_timeout(2, &key(8), timeout).is_err());
+		assert!(lt.x_lock_with_timeout(2, &key(8), timeout).is_err());
+		lt.unlock(1, &key(8))?;
+		lt.x_lock_with_timeout(2, &key(8), timeout)?;
+		lt.unlock(2, &key(8))?;
+		assert!(lt.state.lock().unwrap().locks.is_empty());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_reentrant_s_lock_counts_once() -> Result<()> {
+		let lt = LockTable::new();
+		lt.s_lock(1, &key(10))?;
+		lt.s_lock(1, &key(10))?;
+		assert_eq!(lt.state.lock().unwrap().locks[&key(10)].holders(), HashSet::from([1]));
+
+		// one unlock gives the key back
+		lt.unlock(1, &key(10))?;
+		lt.x_lock_with_timeout(2, &key(10), Duration::from_millis(100))?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_unlock_decrements_shared_count() -> Result<()> {
+		let lt = LockTable::new();
+		let timeout = Duration::from_millis(100);
+		lt.s_lock(1, &key(1))?;
+		lt.s_lock(2, &key(1))?;
+		lt.unlock(1, &key(1))?;
+
+		// tx 2 still holds its S-lock
+		lt.s_lock(3, &key(1))?;
+		let err = lt.x_lock_with_timeout(3, &key(1), timeout).unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(LockTableError::LockAbort { .. })));
+
+		lt.unlock(2, &key(1))?;
+		lt.x_lock_with_timeout(3, &key(1), timeout)?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_upgrade_s_lock_to_x_lock() -> Result<()> {
+		let lt = LockTable::new();
+		let timeout = Duration::from_millis(100);
+
+		// the requester's own S-lock doesn't block the upgrade
+		lt.s_lock(1, &key(4))?;
+		lt.x_lock_with_timeout(1, &key(4), timeout)?;
+		let err = lt.s_lock_with_timeout(2, &key(4), timeout).unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(LockTableError::LockAbort { .. })));
+		lt.unlock(1, &key(4))?;
+
+		// but another tx's S-lock does
+		lt.s_lock(1, &key(4))?;
+		lt.s_lock(2, &key(4))?;
+		let err = lt.x_lock_with_timeout(1, &key(4), timeout).unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(LockTableError::LockAbort { .. })));
+		lt.unlock(2, &key(4))?;
+		lt.x_lock_with_timeout(1, &key(4), timeout)?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_timeout_aborts_quickly() -> Result<()> {
+		let lt = LockTable::with_timeout(Duration::from_millis(100));
+		assert_eq!(lt.timeout(), Duration::from_millis(100));
+		x_lock(&lt, 1, &key(5))?;
+
+		let start = Instant::now();
+		let err = lt.s_lock(2, &key(5)).unwrap_err();
+		assert!(matches!(
+			err.downcast_ref(),
+			Some(LockTableError::LockAbort { key: k, kind: LockKind::Shared }) if *k == key(5)
+		));
+		assert_eq!(err.to_string(), "lock abort: S lock on [file locktablefile, block 5]");
+		assert!(start.elapsed() >= Duration::from_millis(100));
+		assert!(start.elapsed() < Duration::from_secs(1));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_waiting_x_lock_goes_before_newer_s_lock() -> Result<()> {
+		let lt = Arc::new(LockTable::new());
+		let granted = Arc::new(Mutex::new(vec![]));
+		lt.s_lock(1, &key(9))?;
+
+		let (lt2, granted2) = (lt.clone(), granted.clone());
+		let writer = thread::spawn(move || {
+			lt2.x_lock(2, &key(9)).unwrap();
+			granted2.lock().unwrap().push(2);
+			thread::sleep(Duration::from_millis(100));
+			lt2.unlock(2, &key(9)).unwrap();
+		});
+		thread::sleep(Duration::from_millis(100));
+
+		// compatible with tx 1's S-lock, but queued behind the X-lock
+		let (lt3, granted3) = (lt.clone(), granted.clone());
+		let reader = thread::spawn(move || {
+			lt3.s_lock(3, &key(9)).unwrap();
+			granted3.lock().unwrap().push(3);
+		});
+		thread::sleep(Duration::from_millis(100));
+		assert!(granted.lock().unwrap().is_empty());
+
+		lt.unlock(1, &key(9))?;
+		writer.join().unwrap();
+		reader.join().unwrap();
+		assert_eq!(*granted.lock().unwrap(), vec![2, 3]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_stats_count_waits_and_aborts() -> Result<()> {
+		let lt = Arc::new(LockTable::with_timeout(Duration::from_millis(100)));
+		x_lock(&lt, 1, &key(11))?;
+		lt.s_lock(1, &key(12))?;
+
+		// waits until it times out
+		assert!(lt.s_lock(2, &key(11)).is_err());
+
+		// waits until tx 1 unlocks
+		let lt2 = lt.clone();
+		let handle = thread::spawn(move || lt2.x_lock_with_timeout(2, &key(12), Duration::from_secs(5)));
+		thread::sleep(Duration::from_millis(100));
+		lt.unlock(1, &key(12))?;
+		handle.join().unwrap()?;
+
+		assert_eq!(
+			lt.stats(),
+			LockTableStats {
+				requests: 5,
+				waits: 2,
+				aborts: 1,
+				wakeups: 1,
+				size: 2,
+			}
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_try_lock_does_not_wait() -> Result<()> {
+		let lt = LockTable::new();
+		x_lock(&lt, 1, &key(13))?;
+
+		let start = Instant::now();
+		assert!(!lt.try_s_lock(2, &key(13))?);
+		assert!(!lt.try_x_lock(2, &key(13))?);
+		assert!(start.elapsed() < Duration::from_millis(100));
+		assert_eq!(lt.stats().waits, 0);
+
+		lt.unlock(1, &key(13))?;
+		assert!(lt.try_s_lock(2, &key(13))?);
+		assert!(lt.try_x_lock(2, &key(13))?);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_unlock_wakes_waiter() -> Result<()> {
+		let lt = Arc::new(LockTable::new());
+		lt.s_lock(1, &key(3))?;
+
+		let lt2 = lt.clone();
+		let handle = thread::spawn(move || {
+			x_lock(&lt2, 2, &key(3)).map(|_| Instant::now())
+		});
+		thread::sleep(Duration::from_millis(200));
+
+		let unlocked = Instant::now();
+		lt.unlock(1, &key(3))?;
+		let granted = handle.join().unwrap()?;
+		assert!(granted.duration_since(unlocked) < Duration::from_millis(500));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_unlock_wakes_only_waiters_on_key() -> Result<()> {
+		let lt = Arc::new(LockTable::new());
+		x_lock(&lt, 1, &key(6))?;
+		x_lock(&lt, 2, &key(7))?;
+
+		let lt3 = lt.clone();
+		let handle = thread::spawn(move || lt3.s_lock(3, &key(7)));
+		thread::sleep(Duration::from_millis(200));
+
+		let wakeups = lt.stats().wakeups;
+		lt.unlock(1, &key(6))?;
+		thread::sleep(Duration::from_millis(200));
+		assert_eq!(lt.stats().wakeups, wakeups);
+
+		lt.unlock(2, &key(7))?;
+		handle.join().unwrap()?;
+		assert_eq!(lt.stats().wakeups, wakeups + 1);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_deadlock_aborts_youngest_requester() -> Result<()> {
+		let lt = Arc::new(LockTable::new());
+		x_lock(&lt, 1, &key(1))?;
+		x_lock(&lt, 2, &key(2))?;
+
+		let lt1 = lt.clone();
+		let handle = thread::spawn(move || x_lock(&lt1, 1, &key(2)));
+		thread::sleep(Duration::from_millis(100));
+
+		// tx 2 closes the cycle and is the youngest, so it fails right away
+		let start = Instant::now();
+		let err = x_lock(&lt, 2, &key(1)).unwrap_err();
+		assert!(matches!(
+			err.downcast_ref(),
+			Some(LockTableError::Deadlock(2))
+		));
+		assert!(start.elapsed() < Duration::from_secs(1));
+
+		lt.unlock(2, &key(2))?;
+		handle.join().unwrap()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_deadlock_aborts_youngest_waiter() -> Result<()> {
+		let lt = Arc::new(LockTable::new());
+		x_lock(&lt, 1, &key(1))?;
+		x_lock(&lt, 2, &key(2))?;
+
+		let lt2 = lt.clone();
+		let handle = thread::spawn(move || {
+			let result = x_lock(&lt2, 2, &key(1));
+			// a victim gives its locks back, as its tx rolls back
+			lt2.unlock(2, &key(2)).unwrap();
+			result
+		});
+		thread::sleep(Duration::from_millis(100));
+
+		// tx 1 closes the cycle, but the waiting tx 2 is the one aborted
+		let start = Instant::now();
+		x_lock(&lt, 1, &key(2))?;
+		assert!(start.elapsed() < Duration::from_secs(5));
+		let err = handle.join().unwrap().unwrap_err();
+		assert!(matches!(
+			err.downcast_ref(),
+			Some(LockTableError::Deadlock(2))
+		));
+
+		Ok(())
+	}
 }
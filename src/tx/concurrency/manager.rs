@@ -1,41 +1,50 @@
 use anyhow::Result;
 use std::{
 	collections::HashMap,
-	sync::{Arc, Mutex, Once}
+	sync::{Arc, OnceLock},
+	time::Duration,
 };
 
 use super::locktable::{
+	LockKind,
 	LockTable,
 	LockTableKey,
 };
 
 pub struct ConcurrencyMgr {
 	// static member (shared by all ConcurrentMgr)
-	locktbl: Arc<Mutex<LockTable>>,
+	locktbl: Arc<LockTable>,
+	txnum: i32,
 	locks: HashMap<LockTableKey, String>,
+	// None waits as long as the lock table's timeout
+	lock_timeout: Option<Duration>,
 }
 
 impl ConcurrencyMgr {
-	pub fn new() -> Self {
-		static mut SINGLETON: Option<Arc<Mutex<LockTable>>> = None;
-		static ONCE: Once = Once::new();
-
-		unsafe {
-			ONCE.call_once(|| {
-				let singleton = Arc::new(Mutex::new(LockTable::new()));
-				SINGLETON = Some(singleton);
-			});
-
-			Self {
-				locktbl: SINGLETON.clone().unwrap(),
-				locks: HashMap::new(),
-			}
+	pub fn new(txnum: i32) -> Self {
+		static SINGLETON: OnceLock<Arc<LockTable>> = OnceLock::new();
+
+		Self {
+			locktbl: Arc::clone(SINGLETON.get_or_init(|| Arc::new(LockTable::new()))),
+			txnum,
+			locks: HashMap::new(),
+			lock_timeout: None,
 		}
 	}
 
+	// the number of keys this tx holds a lock on
+	pub fn num_locks(&self) -> usize {
+		self.locks.len()
+	}
+
+	pub fn set_lock_timeout(&mut self, timeout: Duration) {
+		self.lock_timeout = Some(timeout);
+	}
+
 	pub fn s_lock(&mut self, key: &LockTableKey) -> Result<()> {
-		if self.locks.get(&key).is_none() {
-			self.locktbl.lock().unwrap().s_lock(key)?;
+		if !self.locks.contains_key(key) {
+			let timeout = self.lock_timeout.unwrap_or(self.locktbl.timeout());
+			self.locktbl.s_lock_with_timeout(self.txnum, key, timeout)?;
 			self.locks.insert(key.clone(), "S".to_string());
 		}
 
@@ -44,15 +53,72 @@ impl ConcurrencyMgr {
 	pub fn x_lock(&mut self, key: &LockTableKey) -> Result<()> {
 		if !self.has_x_lock(key) {
 			self.s_lock(key)?;
-			self.locktbl.lock().unwrap().x_lock(key)?;
+			let timeout = self.lock_timeout.unwrap_or(self.locktbl.timeout());
+			self.locktbl.x_lock_with_timeout(self.txnum, key, timeout)?;
+			self.locks.insert(key.clone(), "X".to_string());
+		}
+
+		Ok(())
+	}
+	// Takes the locks in the order of their keys rather than the given order.
+	// Txs that take all the locks of an operation this way can't deadlock each other.
+	pub fn lock_all(&mut self, keys: &[(LockTableKey, LockKind)]) -> Result<()> {
+		let mut keys = keys.to_vec();
+		keys.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+		for (key, kind) in keys.iter() {
+			match kind {
+				LockKind::Shared => self.s_lock(key)?,
+				LockKind::Exclusive => self.x_lock(key)?,
+			}
+		}
+
+		Ok(())
+	}
+
+	// Like s_lock/x_lock, but returns false instead of waiting
+	// when the lock can't be granted right now.
+	pub fn try_s_lock(&mut self, key: &LockTableKey) -> Result<bool> {
+		if !self.locks.contains_key(key) {
+			if !self.locktbl.try_s_lock(self.txnum, key)? {
+				return Ok(false);
+			}
+			self.locks.insert(key.clone(), "S".to_string());
+		}
+
+		Ok(true)
+	}
+	pub fn try_x_lock(&mut self, key: &LockTableKey) -> Result<bool> {
+		if !self.has_x_lock(key) {
+			let had_lock = self.locks.contains_key(key);
+			if !self.try_s_lock(key)? {
+				return Ok(false);
+			}
+			if !self.locktbl.try_x_lock(self.txnum, key)? {
+				// a refused try leaves the tx with only the locks it had before
+				if !had_lock {
+					self.locktbl.unlock(self.txnum, key)?;
+					self.locks.remove(key);
+				}
+				return Ok(false);
+			}
 			self.locks.insert(key.clone(), "X".to_string());
 		}
 
+		Ok(true)
+	}
+	// Gives back a shared lock before the tx finishes. Exclusive locks are kept,
+	// since the written values must stay hidden until commit.
+	pub fn release_s_lock(&mut self, key: &LockTableKey) -> Result<()> {
+		if self.locks.get(key).is_some_and(|locktype| locktype.eq("S")) {
+			self.locktbl.unlock(self.txnum, key)?;
+			self.locks.remove(key);
+		}
+
 		Ok(())
 	}
 	pub fn release(&mut self) -> Result<()> {
 		for key in self.locks.keys() {
-			self.locktbl.lock().unwrap().unlock(key)?;
+			self.locktbl.unlock(self.txnum, key)?;
 		}
 		self.locks.clear();
 
@@ -68,11 +134,87 @@ impl ConcurrencyMgr {
 mod tests {
 	use super::*;
 
+	use std::{thread, time::Duration};
+
+	use crate::file::block_id::BlockId;
+
 	#[test]
 	fn test_locktable_is_singleton() {
-		// マルチスレッドでシングルトンであるかどうかが確認できていない
-		let cm1 = ConcurrencyMgr::new();
-		let cm2 = ConcurrencyMgr::new();
+		let cm1 = ConcurrencyMgr::new(1);
+		let cm2 = ConcurrencyMgr::new(2);
 		assert!(Arc::ptr_eq(&cm1.locktbl, &cm2.locktbl));
 	}
+
+	#[test]
+	fn test_try_lock() -> Result<()> {
+		let key = LockTableKey::BID(BlockId::new("trylockfile", 1));
+		// the lock table is shared with the other tests' txs, whose numbers are positive
+		let mut cm1 = ConcurrencyMgr::new(-1);
+		let mut cm2 = ConcurrencyMgr::new(-2);
+
+		cm1.x_lock(&key)?;
+		assert!(!cm2.try_s_lock(&key)?);
+		assert!(cm1.try_x_lock(&key)?);
+		cm1.release()?;
+
+		assert!(cm2.try_x_lock(&key)?);
+		assert!(!cm1.try_s_lock(&key)?);
+		cm2.release()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_refused_try_x_lock_keeps_no_lock() -> Result<()> {
+		let key = LockTableKey::BID(BlockId::new("trylockfile", 2));
+		let mut cm1 = ConcurrencyMgr::new(-5);
+		let mut cm2 = ConcurrencyMgr::new(-6);
+
+		cm1.s_lock(&key)?;
+		assert!(!cm2.try_x_lock(&key)?);
+		assert_eq!(cm2.num_locks(), 0);
+		// cm2 holds no S-lock that would block cm1's upgrade
+		assert!(cm1.try_x_lock(&key)?);
+		cm1.release()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_lock_all_in_canonical_order() {
+		let blk1 = LockTableKey::BID(BlockId::new("lockallfile", 1));
+		let blk2 = LockTableKey::BID(BlockId::new("lockallfile", 2));
+		let orders = [
+			vec![(blk1.clone(), LockKind::Exclusive), (blk2.clone(), LockKind::Exclusive)],
+			vec![(blk2, LockKind::Exclusive), (blk1, LockKind::Shared)],
+		];
+
+		let handles: Vec<_> = orders
+			.into_iter()
+			.enumerate()
+			.map(|(i, keys)| {
+				thread::spawn(move || {
+					let mut cm = ConcurrencyMgr::new(-3 - i as i32);
+					cm.set_lock_timeout(Duration::from_secs(5));
+					cm.lock_all(&keys)?;
+					thread::sleep(Duration::from_millis(100));
+					cm.release()
+				})
+			})
+			.collect();
+		for h in handles {
+			h.join().unwrap().unwrap();
+		}
+	}
+
+	#[test]
+	fn test_locktable_is_singleton_across_threads() {
+		let cm = ConcurrencyMgr::new(1);
+		let handles: Vec<_> = (0..10)
+			.map(|i| thread::spawn(move || ConcurrencyMgr::new(i).locktbl))
+			.collect();
+		for h in handles {
+			assert!(Arc::ptr_eq(&cm.locktbl, &h.join().unwrap()));
+		}
+	}
 }
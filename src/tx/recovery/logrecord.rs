@@ -16,6 +16,7 @@ use crate::{
 #[derive(Debug)]
 enum LogRecordError {
 	UnknownRecord,
+	NotUndoable(TxType),
 }
 
 impl std::error::Error for LogRecordError {}
@@ -25,6 +26,9 @@ impl fmt::Display for LogRecordError {
 			&LogRecordError::UnknownRecord => {
 				write!(f, "unknown log record")
 			}
+			LogRecordError::NotUndoable(op) => {
+				write!(f, "a {:?} record can't be compensated", op)
+			}
 		}
 	}
 }
@@ -37,62 +41,223 @@ pub enum TxType {
 	ROLLBACK = 3,
 	SETI32 = 4,
 	SETSTRING = 5,
+	SETI64 = 6,
+	COMPENSATION = 7,
+	SETBOOL = 8,
+	SETBYTES = 9,
+}
+
+// One variant per TxType. A new record type doesn't compile until
+// every match below handles it.
+pub enum LogRecord {
+	Checkpoint(CheckpointRecord),
+	Start(StartRecord),
+	Commit(CommitRecord),
+	Rollback(RollbackRecord),
+	SetI32(SetI32Record),
+	SetString(SetStringRecord),
+	SetI64(SetI64Record),
+	Compensation(CompensationRecord),
+	SetBool(SetBoolRecord),
+	SetBytes(SetBytesRecord),
+}
+
+impl fmt::Display for LogRecord {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			LogRecord::Checkpoint(rec) => rec.fmt(f),
+			LogRecord::Start(rec) => rec.fmt(f),
+			LogRecord::Commit(rec) => rec.fmt(f),
+			LogRecord::Rollback(rec) => rec.fmt(f),
+			LogRecord::SetI32(rec) => rec.fmt(f),
+			LogRecord::SetString(rec) => rec.fmt(f),
+			LogRecord::SetI64(rec) => rec.fmt(f),
+			LogRecord::Compensation(rec) => rec.fmt(f),
+			LogRecord::SetBool(rec) => rec.fmt(f),
+			LogRecord::SetBytes(rec) => rec.fmt(f),
+		}
+	}
 }
 
-pub trait LogRecord {
-	fn op(&self) -> TxType;
-	fn tx_number(&self) -> i32;
-	fn undo(&self, tx: &mut Transaction) -> Result<()>;
+impl LogRecord {
+	pub fn op(&self) -> TxType {
+		match self {
+			LogRecord::Checkpoint(_) => TxType::CHECKPOINT,
+			LogRecord::Start(_) => TxType::START,
+			LogRecord::Commit(_) => TxType::COMMIT,
+			LogRecord::Rollback(_) => TxType::ROLLBACK,
+			LogRecord::SetI32(_) => TxType::SETI32,
+			LogRecord::SetString(_) => TxType::SETSTRING,
+			LogRecord::SetI64(_) => TxType::SETI64,
+			LogRecord::Compensation(_) => TxType::COMPENSATION,
+			LogRecord::SetBool(_) => TxType::SETBOOL,
+			LogRecord::SetBytes(_) => TxType::SETBYTES,
+		}
+	}
+
+	pub fn tx_number(&self) -> i32 {
+		match self {
+			LogRecord::Checkpoint(_) => -1, // dummy value
+			LogRecord::Start(rec) => rec.txnum,
+			LogRecord::Commit(rec) => rec.txnum,
+			LogRecord::Rollback(rec) => rec.txnum,
+			LogRecord::SetI32(rec) => rec.txnum,
+			LogRecord::SetString(rec) => rec.txnum,
+			LogRecord::SetI64(rec) => rec.txnum,
+			LogRecord::Compensation(rec) => rec.txnum,
+			LogRecord::SetBool(rec) => rec.txnum,
+			LogRecord::SetBytes(rec) => rec.txnum,
+		}
+	}
+
+	// writes back the old value of a data record
+	pub fn undo(&self, tx: &mut Transaction) -> Result<()> {
+		match self {
+			LogRecord::SetI32(rec) => rec.undo(tx),
+			LogRecord::SetString(rec) => rec.undo(tx),
+			LogRecord::SetI64(rec) => rec.undo(tx),
+			LogRecord::SetBool(rec) => rec.undo(tx),
+			LogRecord::SetBytes(rec) => rec.undo(tx),
+			// a CLR is never undone
+			LogRecord::Checkpoint(_)
+			| LogRecord::Start(_)
+			| LogRecord::Commit(_)
+			| LogRecord::Rollback(_)
+			| LogRecord::Compensation(_) => Ok(()),
+		}
+	}
+
+	// writes the new value of a data record again
+	pub fn redo(&self, tx: &mut Transaction) -> Result<()> {
+		match self {
+			LogRecord::SetI32(rec) => rec.redo(tx),
+			LogRecord::SetString(rec) => rec.redo(tx),
+			LogRecord::SetI64(rec) => rec.redo(tx),
+			LogRecord::SetBool(rec) => rec.redo(tx),
+			LogRecord::SetBytes(rec) => rec.redo(tx),
+			LogRecord::Compensation(rec) => rec.redo(tx),
+			LogRecord::Checkpoint(_)
+			| LogRecord::Start(_)
+			| LogRecord::Commit(_)
+			| LogRecord::Rollback(_) => Ok(()),
+		}
+	}
+
+	// the CLR written when this data record is undone
+	pub fn compensation_bytes(&self) -> Result<Vec<u8>> {
+		match self {
+			LogRecord::SetI32(rec) => {
+				SetI32Record::to_compensation_bytes(rec.txnum, &rec.blk, rec.offset, rec.val)
+			}
+			LogRecord::SetString(rec) => {
+				SetStringRecord::to_compensation_bytes(rec.txnum, &rec.blk, rec.offset, rec.val.clone())
+			}
+			LogRecord::SetI64(rec) => {
+				SetI64Record::to_compensation_bytes(rec.txnum, &rec.blk, rec.offset, rec.val)
+			}
+			LogRecord::SetBool(rec) => {
+				SetBoolRecord::to_compensation_bytes(rec.txnum, &rec.blk, rec.offset, rec.val)
+			}
+			LogRecord::SetBytes(rec) => {
+				SetBytesRecord::to_compensation_bytes(rec.txnum, &rec.blk, rec.offset, rec.val.clone())
+			}
+			LogRecord::Checkpoint(_)
+			| LogRecord::Start(_)
+			| LogRecord::Commit(_)
+			| LogRecord::Rollback(_)
+			| LogRecord::Compensation(_) => Err(From::from(LogRecordError::NotUndoable(self.op()))),
+		}
+	}
+
+	// the same bytes write_to_log appends for this record
+	pub fn serialize(&self) -> Vec<u8> {
+		match self {
+			LogRecord::Checkpoint(rec) => rec.serialize(),
+			LogRecord::Start(rec) => rec.serialize(),
+			LogRecord::Commit(rec) => rec.serialize(),
+			LogRecord::Rollback(rec) => rec.serialize(),
+			LogRecord::SetI32(rec) => rec.serialize(),
+			LogRecord::SetString(rec) => rec.serialize(),
+			LogRecord::SetI64(rec) => rec.serialize(),
+			LogRecord::Compensation(rec) => rec.serialize(),
+			LogRecord::SetBool(rec) => rec.serialize(),
+			LogRecord::SetBytes(rec) => rec.serialize(),
+		}
+	}
 }
 
-pub fn create_log_record(bytes: Vec<u8>) -> Result<Box<dyn LogRecord,>> {
+pub fn create_log_record(bytes: Vec<u8>) -> Result<LogRecord> {
 	let p = Page::new_from_bytes(bytes);
 	let tx_type: i32 = p.get_i32(0)?;
 
 	match FromPrimitive::from_i32(tx_type) {
-		Some(TxType::CHECKPOINT) => Ok(Box::new(CheckpointRecord::new(p)?)),
-			Some(TxType::START) => Ok(Box::new(StartRecord::new(p)?)),
-			Some(TxType::COMMIT) => Ok(Box::new(CommitRecord::new(p)?)),
-			Some(TxType::ROLLBACK) => Ok(Box::new(RollbackRecord::new(p)?)),
-			Some(TxType::SETI32) => Ok(Box::new(SetI32Record::new(p)?)),
-			Some(TxType::SETSTRING) => Ok(Box::new(SetStringRecord::new(p)?)),
-			None => Err(From::from(LogRecordError::UnknownRecord)),
+		Some(TxType::CHECKPOINT) => Ok(LogRecord::Checkpoint(CheckpointRecord::new(p)?)),
+		Some(TxType::START) => Ok(LogRecord::Start(StartRecord::new(p)?)),
+		Some(TxType::COMMIT) => Ok(LogRecord::Commit(CommitRecord::new(p)?)),
+		Some(TxType::ROLLBACK) => Ok(LogRecord::Rollback(RollbackRecord::new(p)?)),
+		Some(TxType::SETI32) => Ok(LogRecord::SetI32(SetI32Record::new(p)?)),
+		Some(TxType::SETSTRING) => Ok(LogRecord::SetString(SetStringRecord::new(p)?)),
+		Some(TxType::SETI64) => Ok(LogRecord::SetI64(SetI64Record::new(p)?)),
+		Some(TxType::COMPENSATION) => Ok(LogRecord::Compensation(CompensationRecord::new(p)?)),
+		Some(TxType::SETBOOL) => Ok(LogRecord::SetBool(SetBoolRecord::new(p)?)),
+		Some(TxType::SETBYTES) => Ok(LogRecord::SetBytes(SetBytesRecord::new(p)?)),
+		None => Err(From::from(LogRecordError::UnknownRecord)),
 	}
 }
 
-pub struct CheckpointRecord {}
+// The txs listed were still active when the checkpoint was written;
+// an empty list makes it a quiescent checkpoint.
+pub struct CheckpointRecord {
+	active_txs: Vec<i32>,
+}
 
 impl fmt::Display for CheckpointRecord {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "<CHECKPOINT>")
+		write!(f, "<CHECKPOINT")?;
+		for txnum in self.active_txs.iter() {
+			write!(f, " {}", txnum)?;
+		}
+		write!(f, ">")
 	}
 }
 
-impl LogRecord for CheckpointRecord {
-	fn op(&self) -> TxType {
-		TxType::CHECKPOINT
-	}
-	fn tx_number(&self) -> i32 {
-		-1 // dummy value
+impl CheckpointRecord {
+	pub fn new(p: Page) -> Result<Self> {
+		let cpos = mem::size_of::<i32>();
+		let count = p.get_i32(cpos)?;
+		let active_txs = (0..count as usize)
+			.map(|i| p.get_i32(cpos + (i + 1) * mem::size_of::<i32>()))
+			.collect::<Result<Vec<i32>>>()?;
+
+		Ok(Self { active_txs })
 	}
-	fn undo(&self, tx: &mut Transaction) -> Result<()> {
-		// nop
-		Ok(())
+
+	pub fn active_txs(&self) -> &[i32] {
+		&self.active_txs
 	}
-}
 
-impl CheckpointRecord {
-	pub fn new(p: Page) -> Result<Self> {
-		Ok(Self {})
+	pub fn write_to_log(lm: Arc<Mutex<LogMgr>>, active_txs: &[i32]) -> Result<u64> {
+		let mut bytes = Self::to_bytes(active_txs)?;
+		lm.lock().unwrap().append(&mut bytes)
 	}
 
-	pub fn write_to_log(lm: Arc<Mutex<LogMgr>>) -> Result<u64> {
-		let reclen = mem::size_of::<i32>();
+	fn to_bytes(active_txs: &[i32]) -> Result<Vec<u8>> {
+		let cpos = mem::size_of::<i32>();
+		let reclen = cpos + (active_txs.len() + 1) * mem::size_of::<i32>();
 
 		let mut p = Page::new_from_size(reclen);
 		p.set(0, TxType::CHECKPOINT as i32)?;
+		p.set(cpos, active_txs.len() as i32)?;
+		for (i, txnum) in active_txs.iter().enumerate() {
+			p.set(cpos + (i + 1) * mem::size_of::<i32>(), *txnum)?;
+		}
+
+		Ok(p.contents().to_vec())
+	}
 
-		lm.lock().unwrap().append(p.contents())
+	pub fn serialize(&self) -> Vec<u8> {
+		// the page is sized for the record, so setting it can't fail
+		Self::to_bytes(&self.active_txs).unwrap()
 	}
 }
 
@@ -106,19 +271,6 @@ impl fmt::Display for StartRecord {
 	}
 }
 
-impl LogRecord for StartRecord {
-	fn op(&self) -> TxType {
-		TxType::START
-	}
-	fn tx_number(&self) -> i32 {
-		self.txnum
-	}
-	fn undo(&self, tx: &mut Transaction) -> Result<()> {
-		// nop
-		Ok(())
-	}
-}
-
 impl StartRecord {
 	pub fn new(p: Page) -> Result<Self> {
 		let tpos = mem::size_of::<i32>();
@@ -128,6 +280,11 @@ impl StartRecord {
 	}
 
 	pub fn write_to_log(lm: Arc<Mutex<LogMgr>>, txnum: i32) -> Result<u64> {
+		let mut bytes = Self::to_bytes(txnum)?;
+		lm.lock().unwrap().append(&mut bytes)
+	}
+
+	fn to_bytes(txnum: i32) -> Result<Vec<u8>> {
 		let tpos = mem::size_of::<i32>();
 		let reclen = tpos + mem::size_of::<i32>();
 
@@ -135,7 +292,12 @@ impl StartRecord {
 		p.set(0, TxType::START as i32)?;
 		p.set(tpos, txnum)?;
 
-		lm.lock().unwrap().append(p.contents())
+		Ok(p.contents().to_vec())
+	}
+
+	pub fn serialize(&self) -> Vec<u8> {
+		// the page is sized for the record, so setting it can't fail
+		Self::to_bytes(self.txnum).unwrap()
 	}
 }
 
@@ -145,20 +307,7 @@ pub struct CommitRecord {
 
 impl fmt::Display for CommitRecord {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "<COMIIT {}>", self.txnum)
-	}
-}
-
-impl LogRecord for CommitRecord {
-	fn op(&self) -> TxType {
-		TxType::COMMIT
-	}
-	fn tx_number(&self) -> i32 {
-		self.txnum
-	}
-	fn undo(&self, tx: &mut Transaction) -> Result<()> {
-		// nop
-		Ok(())
+		write!(f, "<COMMIT {}>", self.txnum)
 	}
 }
 
@@ -171,6 +320,11 @@ impl CommitRecord {
 	}
 
 	pub fn write_to_log(lm: Arc<Mutex<LogMgr>>, txnum: i32) -> Result<u64> {
+		let mut bytes = Self::to_bytes(txnum)?;
+		lm.lock().unwrap().append(&mut bytes)
+	}
+
+	fn to_bytes(txnum: i32) -> Result<Vec<u8>> {
 		let tpos = mem::size_of::<i32>();
 		let reclen = tpos + mem::size_of::<i32>();
 
@@ -178,7 +332,12 @@ impl CommitRecord {
 		p.set(0, TxType::COMMIT as i32)?;
 		p.set(tpos, txnum)?;
 
-		lm.lock().unwrap().append(p.contents())
+		Ok(p.contents().to_vec())
+	}
+
+	pub fn serialize(&self) -> Vec<u8> {
+		// the page is sized for the record, so setting it can't fail
+		Self::to_bytes(self.txnum).unwrap()
 	}
 }
 
@@ -192,19 +351,6 @@ impl fmt::Display for RollbackRecord {
 	}
 }
 
-impl LogRecord for RollbackRecord {
-	fn op(&self) -> TxType {
-		TxType::ROLLBACK
-	}
-	fn tx_number(&self) -> i32 {
-		self.txnum
-	}
-	fn undo(&self, tx: &mut Transaction) -> Result<()> {
-		// nop
-		Ok(())
-	}
-}
-
 impl RollbackRecord {
 	pub fn new(p: Page) -> Result<Self> {
 		let tpos = mem::size_of::<i32>();
@@ -214,6 +360,11 @@ impl RollbackRecord {
 	}
 
 	pub fn write_to_log(lm: Arc<Mutex<LogMgr>>, txnum: i32) -> Result<u64> {
+		let mut bytes = Self::to_bytes(txnum)?;
+		lm.lock().unwrap().append(&mut bytes)
+	}
+
+	fn to_bytes(txnum: i32) -> Result<Vec<u8>> {
 		let tpos = mem::size_of::<i32>();
 		let reclen = tpos + mem::size_of::<i32>();
 
@@ -221,61 +372,181 @@ impl RollbackRecord {
 		p.set(0, TxType::ROLLBACK as i32)?;
 		p.set(tpos, txnum)?;
 
-		lm.lock().unwrap().append(p.contents())
+		Ok(p.contents().to_vec())
+	}
+
+	pub fn serialize(&self) -> Vec<u8> {
+		// the page is sized for the record, so setting it can't fail
+		Self::to_bytes(self.txnum).unwrap()
 	}
 }
 
+// Written when a data record is undone. It holds only what undoing needs,
+// the block, offset and old value, so it is never longer than the data record.
+// Redoing a CLR undoes the data record again.
+pub struct CompensationRecord {
+	txnum: i32,
+	// the undone record, with its old value in place of the new one
+	rec: Box<LogRecord>,
+}
+
+impl fmt::Display for CompensationRecord {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "<COMPENSATION {} {}>", self.txnum, self.rec)
+	}
+}
+
+impl CompensationRecord {
+	// the type of the undone record is a single byte before the old value
+	pub fn new(mut p: Page) -> Result<Self> {
+		let (txnum, blk, offset, oppos) = parse_data_header(&p)?;
+		let vpos = oppos + mem::size_of::<u8>();
+		let rec = match FromPrimitive::from_u8(p.contents()[oppos]) {
+			Some(TxType::SETI32) => LogRecord::SetI32(SetI32Record::new_compensated(p, txnum, offset, vpos, blk)?),
+			Some(TxType::SETSTRING) => LogRecord::SetString(SetStringRecord::new_compensated(p, txnum, offset, vpos, blk)?),
+			Some(TxType::SETI64) => LogRecord::SetI64(SetI64Record::new_compensated(p, txnum, offset, vpos, blk)?),
+			Some(TxType::SETBOOL) => LogRecord::SetBool(SetBoolRecord::new_compensated(p, txnum, offset, vpos, blk)?),
+			Some(TxType::SETBYTES) => LogRecord::SetBytes(SetBytesRecord::new_compensated(p, txnum, offset, vpos, blk)?),
+			_ => return Err(From::from(LogRecordError::UnknownRecord)),
+		};
+
+		Ok(Self { txnum, rec: Box::new(rec) })
+	}
+
+	pub fn write_to_log(lm: Arc<Mutex<LogMgr>>, rec: &LogRecord) -> Result<u64> {
+		let mut bytes = rec.compensation_bytes()?;
+		lm.lock().unwrap().append(&mut bytes)
+	}
+
+	pub fn redo(&self, tx: &mut Transaction) -> Result<()> {
+		self.rec.undo(tx)
+	}
+
+	pub fn serialize(&self) -> Vec<u8> {
+		self.rec.compensation_bytes().unwrap()
+	}
+}
+
+// The header shared by data records and CLRs: tx number, block and offset.
+// Returns them with the position following the header.
+fn parse_data_header(p: &Page) -> Result<(i32, BlockId, i32, usize)> {
+	let tpos = mem::size_of::<i32>();
+	let txnum = p.get_i32(tpos)?;
+	let fpos = tpos + mem::size_of::<i32>();
+	let filename = p.get_string(fpos)?;
+	let bpos = fpos + Page::max_length(filename.len());
+	let blknum = p.get_i32(bpos)?;
+	let blk = BlockId::new(&filename, blknum as u64);
+	let opos = bpos + mem::size_of::<i32>();
+	let offset = p.get_i32(opos)?;
+
+	Ok((txnum, blk, offset, opos + mem::size_of::<i32>()))
+}
+
+fn data_header_len(blk: &BlockId) -> usize {
+	4 * mem::size_of::<i32>() + Page::max_length(blk.file_name().len())
+}
+
+fn set_data_header(p: &mut Page, op: TxType, txnum: i32, blk: &BlockId, offset: i32) -> Result<()> {
+	let tpos = mem::size_of::<i32>();
+	let fpos = tpos + mem::size_of::<i32>();
+	let bpos = fpos + Page::max_length(blk.file_name().len());
+	let opos = bpos + mem::size_of::<i32>();
+	p.set(0, op as i32)?;
+	p.set(tpos, txnum)?;
+	p.set(fpos, blk.file_name())?;
+	p.set(bpos, blk.number() as i32)?;
+	p.set(opos, offset)?;
+
+	Ok(())
+}
 
 pub trait AbstractDataRecord<T> {
 	fn new(p: Page) -> Result<Self> where Self: Sized {
-		let tpos = mem::size_of::<i32>();
-		let txnum = p.get_i32(tpos)?;
-		let fpos = tpos + mem::size_of::<i32>();
-		let filename = p.get_string(fpos)?;
-		let bpos = fpos + Page::max_length(filename.len());
-		let blknum = p.get_i32(bpos)?;
-		let blk = BlockId::new(&filename, blknum as u64);
-		let opos = bpos + mem::size_of::<i32>();
-		let offset = p.get_i32(opos)?;
-		let vpos = opos + mem::size_of::<i32>();
+		let (txnum, blk, offset, vpos) = parse_data_header(&p)?;
 
 		Self::new_from_vpos(p, txnum, offset, vpos, blk)
 	}
 
+	// the old value is at vpos, followed by the new value
 	fn new_from_vpos(
 		p: Page,
 		txnum: i32,
 		offset: i32,
 		vpos: usize,
-		blk: BlockId) -> Result<Self> where Self: Sized;
+		blk: BlockId) -> Result<Self> where Self: Sized {
+		let val = Self::get_value(&p, vpos)?;
+		let new_val = Self::get_value(&p, vpos + Self::get_data_size(&val))?;
+
+		Ok(Self::from_values(txnum, offset, blk, val, new_val))
+	}
+
+	// a CLR has only the old value at vpos
+	fn new_compensated(
+		p: Page,
+		txnum: i32,
+		offset: i32,
+		vpos: usize,
+		blk: BlockId) -> Result<Self> where Self: Sized, T: Clone {
+		let val = Self::get_value(&p, vpos)?;
+
+		Ok(Self::from_values(txnum, offset, blk, val.clone(), val))
+	}
 
 	fn write_to_log(
 		lm: Arc<Mutex<LogMgr>>,
 		txnum: i32,
 		blk: &BlockId,
 		offset: i32,
-		val: T
+		val: T,
+		new_val: T,
 	) -> Result<u64> {
-		let tpos = mem::size_of::<i32>();
-		let fpos = tpos + mem::size_of::<i32>();
-		let bpos = fpos + Page::max_length(blk.file_name().len());
-		let opos = bpos + mem::size_of::<i32>();
-		let vpos = opos + mem::size_of::<i32>();
+		let mut bytes = Self::to_bytes(txnum, blk, offset, val, new_val)?;
+		lm.lock().unwrap().append(&mut bytes)
+	}
+
+	fn to_bytes(
+		txnum: i32,
+		blk: &BlockId,
+		offset: i32,
+		val: T,
+		new_val: T,
+	) -> Result<Vec<u8>> {
+		let vpos = data_header_len(blk);
+		let npos = vpos + Self::get_data_size(&val);
+		let reclen = npos + Self::get_data_size(&new_val);
+
+		let mut p = Page::new_from_size(reclen);
+		set_data_header(&mut p, Self::txtype(), txnum, blk, offset)?;
+		Self::set_value(&mut p, vpos, val)?;
+		Self::set_value(&mut p, npos, new_val)?;
+
+		Ok(p.contents().to_vec())
+	}
+
+	// The type byte takes the place of the new value, which is at least a byte long.
+	fn to_compensation_bytes(
+		txnum: i32,
+		blk: &BlockId,
+		offset: i32,
+		val: T,
+	) -> Result<Vec<u8>> {
+		let oppos = data_header_len(blk);
+		let vpos = oppos + mem::size_of::<u8>();
 		let reclen = vpos + Self::get_data_size(&val);
 
 		let mut p = Page::new_from_size(reclen);
-		Self::set_txtype_as_i32(&mut p)?;
-		p.set(tpos, txnum)?;
-		p.set(fpos, blk.file_name())?;
-		p.set(bpos, blk.number() as i32)?;
-		p.set(opos, offset)?;
+		set_data_header(&mut p, TxType::COMPENSATION, txnum, blk, offset)?;
+		p.contents()[oppos] = Self::txtype() as u8;
 		Self::set_value(&mut p, vpos, val)?;
-		
-		lm.lock().unwrap().append(p.contents())
+
+		Ok(p.contents().to_vec())
 	}
 
+	fn from_values(txnum: i32, offset: i32, blk: BlockId, val: T, new_val: T) -> Self;
+	fn txtype() -> TxType;
 	fn get_data_size(val: &T) -> usize;
-	fn set_txtype_as_i32(p: &mut Page) -> Result<()>;
+	fn get_value(p: &Page, vpos: usize) -> Result<T>;
 	fn set_value(p: &mut Page, vpos: usize, val: T) -> Result<()>;
 }
 
@@ -283,6 +554,7 @@ pub struct SetI32Record {
 	txnum: i32,
 	offset: i32,
 	val: i32,
+	new_val: i32,
 	blk: BlockId,
 }
 
@@ -290,52 +562,53 @@ impl fmt::Display for SetI32Record {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		write!(
 			f,
-			"<SETI32 {} {} {} {}>",
-			self.txnum, self.blk, self.offset, self.val,
+			"<SETI32 {} {} {} {} {}>",
+			self.txnum, self.blk, self.offset, self.val, self.new_val,
 		)
 	}
 }
 
-impl LogRecord for SetI32Record {
-	fn op(&self) -> TxType {
-		TxType::SETI32
-	}
-	fn tx_number(&self) -> i32 {
-		self.txnum
-	}
-	fn undo(&self, tx: &mut Transaction) -> Result<()> {
+impl SetI32Record {
+	pub fn undo(&self, tx: &mut Transaction) -> Result<()> {
 		tx.pin(&self.blk)?;
 		tx.set_i32(&self.blk, self.offset, self.val, false)?;
 		tx.unpin(&self.blk)?;
 
 		Ok(())
 	}
+	pub fn redo(&self, tx: &mut Transaction) -> Result<()> {
+		tx.pin(&self.blk)?;
+		tx.set_i32(&self.blk, self.offset, self.new_val, false)?;
+		tx.unpin(&self.blk)?;
+
+		Ok(())
+	}
+	pub fn serialize(&self) -> Vec<u8> {
+		Self::to_bytes(self.txnum, &self.blk, self.offset, self.val, self.new_val).unwrap()
+	}
 }
 
 impl AbstractDataRecord<i32> for SetI32Record {
-	fn new_from_vpos(
-		p: Page,
-		txnum: i32,
-		offset: i32,
-		vpos: usize,
-		blk: BlockId,
-	) -> Result<Self> where Self: Sized {
-		let val = p.get_i32(vpos)?;
-		Ok(Self {
+	fn from_values(txnum: i32, offset: i32, blk: BlockId, val: i32, new_val: i32) -> Self {
+		Self {
 			txnum,
 			offset,
 			val,
+			new_val,
 			blk,
-		})
+		}
+	}
+
+	fn txtype() -> TxType {
+		TxType::SETI32
 	}
 
 	fn get_data_size(val: &i32) -> usize {
 		mem::size_of::<i32>()
 	}
 
-	fn set_txtype_as_i32(p: &mut Page) -> Result<()> {
-		p.set(0, TxType::SETI32 as i32)?;
-		Ok(())
+	fn get_value(p: &Page, vpos: usize) -> Result<i32> {
+		p.get_i32(vpos)
 	}
 
 	fn set_value(p: &mut Page, vpos: usize, val: i32) -> Result<()> {
@@ -344,10 +617,213 @@ impl AbstractDataRecord<i32> for SetI32Record {
 	}
 }
 
+pub struct SetI64Record {
+	txnum: i32,
+	offset: i32,
+	val: i64,
+	new_val: i64,
+	blk: BlockId,
+}
+
+impl fmt::Display for SetI64Record {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"<SETI64 {} {} {} {} {}>",
+			self.txnum, self.blk, self.offset, self.val, self.new_val,
+		)
+	}
+}
+
+impl SetI64Record {
+	pub fn undo(&self, tx: &mut Transaction) -> Result<()> {
+		tx.pin(&self.blk)?;
+		tx.set_i64(&self.blk, self.offset, self.val, false)?;
+		tx.unpin(&self.blk)?;
+
+		Ok(())
+	}
+	pub fn redo(&self, tx: &mut Transaction) -> Result<()> {
+		tx.pin(&self.blk)?;
+		tx.set_i64(&self.blk, self.offset, self.new_val, false)?;
+		tx.unpin(&self.blk)?;
+
+		Ok(())
+	}
+	pub fn serialize(&self) -> Vec<u8> {
+		Self::to_bytes(self.txnum, &self.blk, self.offset, self.val, self.new_val).unwrap()
+	}
+}
+
+impl AbstractDataRecord<i64> for SetI64Record {
+	fn from_values(txnum: i32, offset: i32, blk: BlockId, val: i64, new_val: i64) -> Self {
+		Self {
+			txnum,
+			offset,
+			val,
+			new_val,
+			blk,
+		}
+	}
+
+	fn txtype() -> TxType {
+		TxType::SETI64
+	}
+
+	fn get_data_size(_val: &i64) -> usize {
+		mem::size_of::<i64>()
+	}
+
+	fn get_value(p: &Page, vpos: usize) -> Result<i64> {
+		p.get_i64(vpos)
+	}
+
+	fn set_value(p: &mut Page, vpos: usize, val: i64) -> Result<()> {
+		p.set(vpos, val)?;
+		Ok(())
+	}
+}
+
+pub struct SetBoolRecord {
+	txnum: i32,
+	offset: i32,
+	val: bool,
+	new_val: bool,
+	blk: BlockId,
+}
+
+impl fmt::Display for SetBoolRecord {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"<SETBOOL {} {} {} {} {}>",
+			self.txnum, self.blk, self.offset, self.val, self.new_val,
+		)
+	}
+}
+
+impl SetBoolRecord {
+	pub fn undo(&self, tx: &mut Transaction) -> Result<()> {
+		tx.pin(&self.blk)?;
+		tx.set_bool(&self.blk, self.offset, self.val, false)?;
+		tx.unpin(&self.blk)?;
+
+		Ok(())
+	}
+	pub fn redo(&self, tx: &mut Transaction) -> Result<()> {
+		tx.pin(&self.blk)?;
+		tx.set_bool(&self.blk, self.offset, self.new_val, false)?;
+		tx.unpin(&self.blk)?;
+
+		Ok(())
+	}
+	pub fn serialize(&self) -> Vec<u8> {
+		Self::to_bytes(self.txnum, &self.blk, self.offset, self.val, self.new_val).unwrap()
+	}
+}
+
+impl AbstractDataRecord<bool> for SetBoolRecord {
+	fn from_values(txnum: i32, offset: i32, blk: BlockId, val: bool, new_val: bool) -> Self {
+		Self {
+			txnum,
+			offset,
+			val,
+			new_val,
+			blk,
+		}
+	}
+
+	fn txtype() -> TxType {
+		TxType::SETBOOL
+	}
+
+	fn get_data_size(_val: &bool) -> usize {
+		mem::size_of::<u8>()
+	}
+
+	fn get_value(p: &Page, vpos: usize) -> Result<bool> {
+		p.get_bool(vpos)
+	}
+
+	fn set_value(p: &mut Page, vpos: usize, val: bool) -> Result<()> {
+		p.set(vpos, val)?;
+		Ok(())
+	}
+}
+
+pub struct SetBytesRecord {
+	txnum: i32,
+	offset: i32,
+	val: Vec<u8>,
+	new_val: Vec<u8>,
+	blk: BlockId,
+}
+
+impl fmt::Display for SetBytesRecord {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"<SETBYTES {} {} {} {:?} {:?}>",
+			self.txnum, self.blk, self.offset, self.val, self.new_val,
+		)
+	}
+}
+
+impl SetBytesRecord {
+	pub fn undo(&self, tx: &mut Transaction) -> Result<()> {
+		tx.pin(&self.blk)?;
+		tx.set_bytes(&self.blk, self.offset, &self.val, false)?;
+		tx.unpin(&self.blk)?;
+
+		Ok(())
+	}
+	pub fn redo(&self, tx: &mut Transaction) -> Result<()> {
+		tx.pin(&self.blk)?;
+		tx.set_bytes(&self.blk, self.offset, &self.new_val, false)?;
+		tx.unpin(&self.blk)?;
+
+		Ok(())
+	}
+	pub fn serialize(&self) -> Vec<u8> {
+		Self::to_bytes(self.txnum, &self.blk, self.offset, self.val.clone(), self.new_val.clone()).unwrap()
+	}
+}
+
+impl AbstractDataRecord<Vec<u8>> for SetBytesRecord {
+	fn from_values(txnum: i32, offset: i32, blk: BlockId, val: Vec<u8>, new_val: Vec<u8>) -> Self {
+		Self {
+			txnum,
+			offset,
+			val,
+			new_val,
+			blk,
+		}
+	}
+
+	fn txtype() -> TxType {
+		TxType::SETBYTES
+	}
+
+	// the blob is length-prefixed like a string
+	fn get_data_size(val: &Vec<u8>) -> usize {
+		Page::max_length(val.len())
+	}
+
+	fn get_value(p: &Page, vpos: usize) -> Result<Vec<u8>> {
+		p.get_bytes_vec(vpos)
+	}
+
+	fn set_value(p: &mut Page, vpos: usize, val: Vec<u8>) -> Result<()> {
+		p.set_bytes(vpos, &val)?;
+		Ok(())
+	}
+}
+
 pub struct SetStringRecord {
 	txnum: i32,
 	offset: i32,
 	val: String,
+	new_val: String,
 	blk: BlockId,
 }
 
@@ -355,53 +831,53 @@ impl fmt::Display for SetStringRecord {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		write!(
 			f,
-			"<SETSTRING {} {} {} {}>",
-			self.txnum, self.blk, self.offset, self.val
+			"<SETSTRING {} {} {} {} {}>",
+			self.txnum, self.blk, self.offset, self.val, self.new_val
 		)
 	}
 }
 
-impl LogRecord for SetStringRecord {
-	fn op(&self) -> TxType {
-		TxType::SETSTRING
-	}
-	fn tx_number(&self) -> i32 {
-		self.txnum
-	}
-	fn undo(&self, tx: &mut Transaction) -> Result<()> {
+impl SetStringRecord {
+	pub fn undo(&self, tx: &mut Transaction) -> Result<()> {
 		tx.pin(&self.blk)?;
 		tx.set_string(&self.blk, self.offset, self.val.as_str(), false)?;
 		tx.unpin(&self.blk)?;
 
 		Ok(())
 	}
+	pub fn redo(&self, tx: &mut Transaction) -> Result<()> {
+		tx.pin(&self.blk)?;
+		tx.set_string(&self.blk, self.offset, self.new_val.as_str(), false)?;
+		tx.unpin(&self.blk)?;
+
+		Ok(())
+	}
+	pub fn serialize(&self) -> Vec<u8> {
+		Self::to_bytes(self.txnum, &self.blk, self.offset, self.val.clone(), self.new_val.clone()).unwrap()
+	}
 }
 
 impl AbstractDataRecord<String> for SetStringRecord {
-	fn new_from_vpos(
-		p: Page,
-		txnum: i32,
-		offset: i32,
-		vpos: usize,
-		blk: BlockId,
-	) -> Result<Self> {
-		let val = p.get_string(vpos)?;
-
-		Ok(Self {
+	fn from_values(txnum: i32, offset: i32, blk: BlockId, val: String, new_val: String) -> Self {
+		Self {
 			txnum,
 			offset,
 			val,
+			new_val,
 			blk,
-		})
+		}
+	}
+
+	fn txtype() -> TxType {
+		TxType::SETSTRING
 	}
 
 	fn get_data_size(val: &String) -> usize {
 		Page::max_length(val.len())
 	}
 
-	fn set_txtype_as_i32(p: &mut Page) -> Result<()> {
-		p.set(0, TxType::SETSTRING as i32);
-		Ok(())
+	fn get_value(p: &Page, vpos: usize) -> Result<String> {
+		p.get_string(vpos)
 	}
 
 	fn set_value(p: &mut Page, vpos: usize, val: String) -> Result<()> {
@@ -414,9 +890,24 @@ impl AbstractDataRecord<String> for SetStringRecord {
 mod tests {
 	use super::*;
 
+	use std::fs;
+
+	use crate::buffer::manager::BufferMgr;
 	use crate::file::{block_id::BlockId, manager::FileMgr};
 	use crate::log::manager::LogMgr;
 
+	fn setup_tx(dir: &str) -> (Arc<Mutex<LogMgr>>, Transaction) {
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400).unwrap()));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log").unwrap()));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 3).unwrap()));
+		(lm.clone(), Transaction::new(fm, lm, bm).unwrap())
+	}
+
+	fn last_log_record(lm: &Arc<Mutex<LogMgr>>) -> Result<Vec<u8>> {
+		Ok(lm.lock().unwrap().iterator()?.next().unwrap())
+	}
+
 	trait TestRecordCreator {
 		fn create(&self) -> (Vec<u8>, TxType, i32);
 	}
@@ -424,7 +915,7 @@ mod tests {
 	struct TestCheckoutRecordCreator;
 	impl TestRecordCreator for TestCheckoutRecordCreator {
 		fn create(&self) -> (Vec<u8>, TxType, i32) {
-			(vec![0x00, 0x00, 0x00, 0x00], TxType::CHECKPOINT, -1)
+			(vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], TxType::CHECKPOINT, -1)
 		}
 	}
 
@@ -463,14 +954,25 @@ mod tests {
 		}
 	}
 
+	#[derive(Clone)]
 	enum DataType {
 		DataI32(i32),
+		DataI64(i64),
+		DataBool(bool),
+		DataBytes(Vec<u8>),
 		DataString(String),
 	}
 	impl DataType {
 		fn to_vec(&self) -> Vec<u8> {
 			match self {
 				DataType::DataI32(i) => Vec::from(i.to_be_bytes()),
+				DataType::DataI64(i) => Vec::from(i.to_be_bytes()),
+				DataType::DataBool(b) => vec![*b as u8],
+				DataType::DataBytes(b) => {
+					let mut v = Vec::from((b.len() as u32).to_be_bytes());
+					v.append(&mut b.clone());
+					v
+				},
 				DataType::DataString(s) => {
 					let mut v = Vec::from((s.len() as u32).to_be_bytes());
 					v.append(&mut Vec::from(s.clone().into_bytes()));
@@ -487,6 +989,7 @@ mod tests {
 		block_id: u32,
 		offset: u32,
 		data: DataType,
+		new_data: DataType,
 	}
 	impl TestRecordCreator for TestDataRecordCreator {
 		fn create(&self) -> (Vec<u8>, TxType, i32) {
@@ -499,15 +1002,16 @@ mod tests {
 			v.append(&mut Vec::from(self.block_id.to_be_bytes()));
 			// offset
 			v.append(&mut Vec::from(self.offset.to_be_bytes()));
-			// value
+			// old and new value
 			v.append(&mut self.data.to_vec());
+			v.append(&mut self.new_data.to_vec());
 
 			(v, self.txtype, self.txnum)
 		}
 	}
 
 	impl TestDataRecordCreator {
-		fn new(txtype: TxType, filename: &str, data: DataType) -> Self {
+		fn new(txtype: TxType, filename: &str, data: DataType, new_data: DataType) -> Self {
 			Self {
 				txtype,
 				txnum: rand::random::<i32>(),
@@ -515,15 +1019,53 @@ mod tests {
 				block_id: rand::random::<u32>(),
 				offset: 0,
 				data,
+				new_data,
 			}
 		}
 
 		fn new_test_i32_record(filename: &str, data: i32) -> Self {
-			TestDataRecordCreator::new(TxType::SETI32, filename, DataType::DataI32(data))
+			TestDataRecordCreator::new(
+				TxType::SETI32,
+				filename,
+				DataType::DataI32(data),
+				DataType::DataI32(rand::random::<i32>()),
+			)
+		}
+
+		fn new_test_i64_record(filename: &str, data: i64) -> Self {
+			TestDataRecordCreator::new(
+				TxType::SETI64,
+				filename,
+				DataType::DataI64(data),
+				DataType::DataI64(rand::random::<i64>()),
+			)
+		}
+
+		fn new_test_bool_record(filename: &str, data: bool) -> Self {
+			TestDataRecordCreator::new(
+				TxType::SETBOOL,
+				filename,
+				DataType::DataBool(data),
+				DataType::DataBool(!data),
+			)
+		}
+
+		fn new_test_bytes_record(filename: &str, data: &[u8]) -> Self {
+			TestDataRecordCreator::new(
+				TxType::SETBYTES,
+				filename,
+				DataType::DataBytes(data.to_vec()),
+				DataType::DataBytes(vec![0xAB; 5]),
+			)
 		}
 
 		fn new_test_string_record(filename: &str, data: &str) -> Self {
-			TestDataRecordCreator::new(TxType::SETSTRING, filename, DataType::DataString(String::from(data)))
+			TestDataRecordCreator::new(
+				TxType::SETSTRING,
+				filename,
+				DataType::DataString(String::from(data)),
+				DataType::DataString(String::from("new value")),
+			)
 		}
 	}
 
@@ -537,6 +1079,18 @@ mod tests {
 				"testfile_seti32_record",
 				rand::random::<i32>(),
 			)),
+			Box::new(TestDataRecordCreator::new_test_i64_record(
+				"testfile_seti64_record",
+				rand::random::<i64>(),
+			)),
+			Box::new(TestDataRecordCreator::new_test_bool_record(
+				"testfile_setbool_record",
+				rand::random::<bool>(),
+			)),
+			Box::new(TestDataRecordCreator::new_test_bytes_record(
+				"testfile_setbytes_record",
+				&rand::random::<[u8; 16]>(),
+			)),
 			Box::new(TestDataRecordCreator::new_test_string_record(
 				"testfile_setstring_record",
 				"A database system is a common, visible tool in the corporate world--employees frequently interact directly with database systems to submit data or create reports.",
@@ -551,7 +1105,7 @@ mod tests {
 		let tests_list = create_tests_list();
 
 		tests_list.iter().for_each(|(bytes, expected_txtype, expected_txnum)| {
-			let actual: Box<dyn LogRecord> = create_log_record(bytes.to_vec()).unwrap();
+			let actual: LogRecord = create_log_record(bytes.to_vec()).unwrap();
 			assert_eq!(*expected_txtype, actual.op());
 			assert_eq!(*expected_txnum, actual.tx_number());
 		});
@@ -559,6 +1113,119 @@ mod tests {
 		Ok(())
 	}
 
+	#[test]
+	fn test_create_log_record_variants() -> Result<()> {
+		let mut tests_list = create_tests_list();
+		let clr = create_log_record(tests_list[4].0.clone())?.compensation_bytes()?;
+		tests_list.push((clr, TxType::COMPENSATION, 0));
+
+		for (bytes, expected_txtype, _) in tests_list {
+			let rec = create_log_record(bytes)?;
+			let matched = matches!(
+				(&rec, expected_txtype),
+				(LogRecord::Checkpoint(_), TxType::CHECKPOINT)
+				| (LogRecord::Start(_), TxType::START)
+				| (LogRecord::Commit(_), TxType::COMMIT)
+				| (LogRecord::Rollback(_), TxType::ROLLBACK)
+				| (LogRecord::SetI32(_), TxType::SETI32)
+				| (LogRecord::SetString(_), TxType::SETSTRING)
+				| (LogRecord::SetI64(_), TxType::SETI64)
+				| (LogRecord::Compensation(_), TxType::COMPENSATION)
+				| (LogRecord::SetBool(_), TxType::SETBOOL)
+				| (LogRecord::SetBytes(_), TxType::SETBYTES)
+			);
+			assert!(matched, "{} is not a {:?} record", rec, expected_txtype);
+		}
+		assert!(create_log_record(vec![0x00, 0x00, 0x00, 0x7F]).is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_serialize_round_trip() -> Result<()> {
+		let mut tests_list = create_tests_list();
+		tests_list.push((
+			CheckpointRecord::to_bytes(&[4, 8, 15])?,
+			TxType::CHECKPOINT,
+			-1,
+		));
+		// a CLR for each type of data record
+		for (rec_bytes, _, txnum) in create_tests_list().into_iter().skip(4) {
+			let clr = create_log_record(rec_bytes)?.compensation_bytes()?;
+			tests_list.push((clr, TxType::COMPENSATION, txnum));
+		}
+
+		for (bytes, expected_txtype, expected_txnum) in tests_list {
+			let rec = create_log_record(bytes.clone())?;
+			let serialized = rec.serialize();
+			assert_eq!(serialized, bytes);
+
+			let actual = create_log_record(serialized)?;
+			assert_eq!(actual.op(), expected_txtype);
+			assert_eq!(actual.tx_number(), expected_txnum);
+			assert_eq!(actual.serialize(), bytes);
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_compensation_record_is_never_longer() -> Result<()> {
+		for (bytes, _, _) in create_tests_list().into_iter().skip(4) {
+			let len = bytes.len();
+			let clr = create_log_record(bytes)?.compensation_bytes()?;
+			assert!(clr.len() <= len);
+		}
+		assert!(create_log_record(create_tests_list()[1].0.clone())?.compensation_bytes().is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_rollback_of_record_filling_a_block() -> Result<()> {
+		let (lm, mut tx) = setup_tx("txtest/clrsize");
+		let blk = BlockId::new("clrsizefile", 1);
+		tx.pin(&blk)?;
+		tx.set_string(&blk, 0, &"a".repeat(353), true)?;
+		// the largest record a 400-byte log block holds
+		assert_eq!(last_log_record(&lm)?.len(), 392);
+
+		tx.rollback()?;
+		assert_eq!(create_log_record(last_log_record(&lm)?)?.op(), TxType::ROLLBACK);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_checkpoint_record_new() -> Result<()> {
+		let bytes = vec![
+			0x00, 0x00, 0x00, 0x00,
+			0x00, 0x00, 0x00, 0x02,
+			0x00, 0x00, 0x00, 0x07,
+			0xFF, 0xFF, 0xFF, 0xFE,
+		];
+		let rec = CheckpointRecord::new(Page::new_from_bytes(bytes))?;
+		assert_eq!(rec.active_txs(), &[7, -2]);
+		assert_eq!(rec.to_string(), "<CHECKPOINT 7 -2>");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_checkpoint_record_write_to_log() -> Result<()> {
+		let fm = FileMgr::new("txtest/logrecordtest", 400).unwrap();
+		let fm_arc = Arc::new(Mutex::new(fm));
+		let lm = LogMgr::new(Arc::clone(&fm_arc), "simpledb4.log").unwrap();
+		let lm_arc = Arc::new(Mutex::new(lm));
+		for active_txs in [vec![], vec![3], vec![1, 5, 9]] {
+			CheckpointRecord::write_to_log(Arc::clone(&lm_arc), &active_txs)?;
+			let rec = CheckpointRecord::new(Page::new_from_bytes(last_log_record(&lm_arc)?))?;
+			assert_eq!(rec.active_txs(), active_txs.as_slice());
+		}
+
+		Ok(())
+	}
+
 	#[test]
 	fn test_set_i32_record_new() -> Result<()> {
 		let test_rec = TestDataRecordCreator::new_test_i32_record(
@@ -568,11 +1235,47 @@ mod tests {
 		let (bytes, _, _) = test_rec.create();
 
 		let rec = SetI32Record::new(Page::new_from_bytes(bytes)).unwrap();
-		let expected = match test_rec.data {
-			DataType::DataI32(i) => Some(i),
+		let expected = match (test_rec.data, test_rec.new_data) {
+			(DataType::DataI32(i), DataType::DataI32(n)) => Some((i, n)),
 			_ => None, // よくないでしょこれ
 		};
-		assert_eq!(rec.val, expected.unwrap());
+		assert_eq!((rec.val, rec.new_val), expected.unwrap());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_set_i64_record_new() -> Result<()> {
+		let test_rec = TestDataRecordCreator::new_test_i64_record(
+			"testfile_seti64_record",
+			rand::random::<i64>(),
+		);
+		let (bytes, _, _) = test_rec.create();
+
+		let rec = SetI64Record::new(Page::new_from_bytes(bytes)).unwrap();
+		let expected = match (test_rec.data, test_rec.new_data) {
+			(DataType::DataI64(i), DataType::DataI64(n)) => Some((i, n)),
+			_ => None,
+		};
+		assert_eq!((rec.val, rec.new_val), expected.unwrap());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_set_bool_record_new() -> Result<()> {
+		let test_rec = TestDataRecordCreator::new_test_bool_record(
+			"testfile_setbool_record",
+			rand::random::<bool>(),
+		);
+		let (bytes, _, _) = test_rec.create();
+
+		let rec = SetBoolRecord::new(Page::new_from_bytes(bytes)).unwrap();
+		let expected = match (test_rec.data, test_rec.new_data) {
+			(DataType::DataBool(b), DataType::DataBool(n)) => Some((b, n)),
+			_ => None,
+		};
+		assert_eq!((rec.val, rec.new_val), expected.unwrap());
 
 		Ok(())
 	}
@@ -585,11 +1288,11 @@ mod tests {
 		);
 		let (bytes, _, _) = test_rec.create();
 		let rec = SetStringRecord::new(Page::new_from_bytes(bytes)).unwrap();
-		let expected = match test_rec.data {
-			DataType::DataString(s) => Some(s),
+		let expected = match (test_rec.data, test_rec.new_data) {
+			(DataType::DataString(s), DataType::DataString(n)) => Some((s, n)),
 			_ => None,
 		};
-		assert_eq!(rec.val, expected.unwrap());
+		assert_eq!((rec.val, rec.new_val), expected.unwrap());
 
 		Ok(())
 	}
@@ -601,15 +1304,50 @@ mod tests {
 		let lm = LogMgr::new(Arc::clone(&fm_arc), "simpledb1.log").unwrap();
 		let lm_arc = Arc::new(Mutex::new(lm));
 		let block_id = BlockId::new("testfile", 2);
-		let _ = SetI32Record::write_to_log(Arc::clone(&lm_arc), 10, &block_id, 2, 0xFF);
+		let _ = SetI32Record::write_to_log(Arc::clone(&lm_arc), 10, &block_id, 2, 0xFF, 0x100);
 		let rec = SetI32Record::new(Page::new_from_bytes(lm_arc.lock().unwrap().iterator()?.next().unwrap())).unwrap();
 		assert_eq!(rec.val, 0xFF);
+		assert_eq!(rec.new_val, 0x100);
 		assert_eq!(rec.txnum, 10);
 		assert_eq!(rec.offset, 2);
 
 		Ok(())
 	}
 
+	#[test]
+	fn test_set_i64_record_write_to_log() -> Result<()> {
+		let fm = FileMgr::new("txtest/logrecordtest", 400).unwrap();
+		let fm_arc = Arc::new(Mutex::new(fm));
+		let lm = LogMgr::new(Arc::clone(&fm_arc), "simpledb3.log").unwrap();
+		let lm_arc = Arc::new(Mutex::new(lm));
+		let block_id = BlockId::new("testfile", 4);
+		let _ = SetI64Record::write_to_log(Arc::clone(&lm_arc), 20, &block_id, 8, 0x1122334455667788, -1);
+		let rec = SetI64Record::new(Page::new_from_bytes(lm_arc.lock().unwrap().iterator()?.next().unwrap())).unwrap();
+		assert_eq!(rec.val, 0x1122334455667788);
+		assert_eq!(rec.new_val, -1);
+		assert_eq!(rec.txnum, 20);
+		assert_eq!(rec.offset, 8);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_set_bool_record_write_to_log() -> Result<()> {
+		let fm = FileMgr::new("txtest/logrecordtest", 400).unwrap();
+		let fm_arc = Arc::new(Mutex::new(fm));
+		let lm = LogMgr::new(Arc::clone(&fm_arc), "simpledb6.log").unwrap();
+		let lm_arc = Arc::new(Mutex::new(lm));
+		let block_id = BlockId::new("testfile", 6);
+		let _ = SetBoolRecord::write_to_log(Arc::clone(&lm_arc), 50, &block_id, 3, true, false);
+		let rec = SetBoolRecord::new(Page::new_from_bytes(lm_arc.lock().unwrap().iterator()?.next().unwrap())).unwrap();
+		assert!(rec.val);
+		assert!(!rec.new_val);
+		assert_eq!(rec.txnum, 50);
+		assert_eq!(rec.offset, 3);
+
+		Ok(())
+	}
+
 	#[test]
 	fn test_set_string_record_write_to_log() -> Result<()> {
 		let fm = FileMgr::new("txtest/logrecordtest", 400).unwrap();
@@ -617,12 +1355,120 @@ mod tests {
 		let lm = LogMgr::new(Arc::clone(&fm_arc), "simpledb2.log").unwrap();
 		let lm_arc = Arc::new(Mutex::new(lm));
 		let block_id = BlockId::new("testfile", 3);
-		let _ = SetStringRecord::write_to_log(Arc::clone(&lm_arc), 30, &block_id, 5, String::from("teststring"));
+		let _ = SetStringRecord::write_to_log(
+			Arc::clone(&lm_arc),
+			30,
+			&block_id,
+			5,
+			String::from("teststring"),
+			String::from("newstring"),
+		);
 		let rec = SetStringRecord::new(Page::new_from_bytes(lm_arc.lock().unwrap().iterator()?.next().unwrap())).unwrap();
 		assert_eq!(rec.val, "teststring");
+		assert_eq!(rec.new_val, "newstring");
 		assert_eq!(rec.txnum, 30);
 		assert_eq!(rec.offset, 5);
 
 		Ok(())
 	}
+
+	#[test]
+	fn test_written_records_keep_their_type() -> Result<()> {
+		let fm = FileMgr::new("txtest/logrecordtest", 400).unwrap();
+		let fm_arc = Arc::new(Mutex::new(fm));
+		let lm = LogMgr::new(Arc::clone(&fm_arc), "simpledb5.log").unwrap();
+		let lm_arc = Arc::new(Mutex::new(lm));
+		let block_id = BlockId::new("testfile", 5);
+
+		SetI32Record::write_to_log(Arc::clone(&lm_arc), 40, &block_id, 0, 1, 2)?;
+		assert_eq!(create_log_record(last_log_record(&lm_arc)?)?.op(), TxType::SETI32);
+		SetI64Record::write_to_log(Arc::clone(&lm_arc), 40, &block_id, 0, 1, 2)?;
+		assert_eq!(create_log_record(last_log_record(&lm_arc)?)?.op(), TxType::SETI64);
+		SetStringRecord::write_to_log(
+			Arc::clone(&lm_arc),
+			40,
+			&block_id,
+			0,
+			String::from("old"),
+			String::from("new"),
+		)?;
+		let rec = create_log_record(last_log_record(&lm_arc)?)?;
+		assert_eq!(rec.op(), TxType::SETSTRING);
+		assert_eq!(rec.tx_number(), 40);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_set_i32_record_undo() -> Result<()> {
+		let (lm, mut tx) = setup_tx("txtest/undoi32");
+		let blk = BlockId::new("undoi32file", 1);
+		tx.pin(&blk)?;
+		tx.set_i32(&blk, 80, 100, true)?;
+		tx.set_i32(&blk, 80, 200, true)?;
+
+		let rec = SetI32Record::new(Page::new_from_bytes(last_log_record(&lm)?))?;
+		assert_eq!(rec.val, 100);
+		rec.undo(&mut tx)?;
+		assert_eq!(tx.get_i32(&blk, 80)?, 100);
+		tx.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_set_bool_record_undo() -> Result<()> {
+		let (lm, mut tx) = setup_tx("txtest/undobool");
+		let blk = BlockId::new("undoboolfile", 1);
+		tx.pin(&blk)?;
+		tx.set_bool(&blk, 80, true, true)?;
+		tx.set_bool(&blk, 80, false, true)?;
+
+		let rec = SetBoolRecord::new(Page::new_from_bytes(last_log_record(&lm)?))?;
+		assert!(rec.val);
+		rec.undo(&mut tx)?;
+		assert!(tx.get_bool(&blk, 80)?);
+		tx.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_set_bytes_record_undo() -> Result<()> {
+		let (lm, mut tx) = setup_tx("txtest/undobytes");
+		let blk = BlockId::new("undobytesfile", 1);
+		tx.pin(&blk)?;
+		tx.set_bytes(&blk, 40, &[1, 2, 3], true)?;
+		tx.set_bytes(&blk, 40, &[9, 9, 9, 9, 9, 9], true)?;
+
+		let rec = SetBytesRecord::new(Page::new_from_bytes(last_log_record(&lm)?))?;
+		assert_eq!(rec.val, [1, 2, 3]);
+		assert_eq!(rec.new_val, [9, 9, 9, 9, 9, 9]);
+		assert_eq!(create_log_record(rec.serialize())?.op(), TxType::SETBYTES);
+		rec.undo(&mut tx)?;
+		assert_eq!(tx.get_bytes(&blk, 40)?, [1, 2, 3]);
+		tx.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_set_string_record_undo() -> Result<()> {
+		let (lm, mut tx) = setup_tx("txtest/undostring");
+		let blk = BlockId::new("undostringfile", 1);
+		tx.pin(&blk)?;
+		tx.set_string(&blk, 40, "short", true)?;
+
+		// the old value is just written back, whatever is in the slot now
+		for newval in ["a much longer string", "s"] {
+			tx.set_string(&blk, 40, newval, true)?;
+			let rec = SetStringRecord::new(Page::new_from_bytes(last_log_record(&lm)?))?;
+			assert_eq!(rec.val, "short");
+			rec.undo(&mut tx)?;
+			assert_eq!(tx.get_string(&blk, 40)?, "short");
+		}
+		tx.commit()?;
+
+		Ok(())
+	}
 }
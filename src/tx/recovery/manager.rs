@@ -1,11 +1,13 @@
 use anyhow::Result;
 use core::fmt;
 use std::{
+	collections::{BTreeSet, HashMap},
 	sync::{Arc, Mutex},
 };
 
 use crate::{
 	buffer::{buffer::Buffer, manager::BufferMgr},
+	file::page::Page,
 	log::manager::LogMgr,
 	tx::transaction::Transaction,
 };
@@ -14,9 +16,14 @@ use super::logrecord::{
 	create_log_record,
 	CheckpointRecord,
 	CommitRecord,
+	CompensationRecord,
+	LogRecord,
 	RollbackRecord,
 	StartRecord,
 	SetI32Record,
+	SetI64Record,
+	SetBoolRecord,
+	SetBytesRecord,
 	SetStringRecord,
 	AbstractDataRecord,
 	TxType,
@@ -38,52 +45,171 @@ impl fmt::Display for RecoveryMgrError {
 	}
 }
 
-macro_rules! lock {
+// Flushes the tx's buffers, writes the record, then forces the log up to it.
+// Evaluates to the lsn of the record.
+// The managers are locked one at a time: buffers lock the log manager
+// themselves when they flush, and so does write_to_log.
+macro_rules! flush_and_log {
 	($self:ident, $wtl:expr) => ({
-		let mut lm = $self.lm.lock().unwrap();
-		let mut bm = $self.bm.lock().unwrap();
-
-		bm.flush_all($self.txnum)?;
+		$self.bm.lock().unwrap().flush_all($self.txnum)?;
 		let lsn = $wtl;
-		lm.flush(lsn)
+		$self.lm.lock().unwrap().flush(lsn).map(|_| lsn)
 	})
 }
 
+// Which passes recovery runs.
+// - UndoOnly: a committed tx's pages are flushed by its commit, so only the
+//   changes of unfinished txs have to be undone.
+// - UndoRedo: the changes of committed txs are also reapplied, for buffer
+//   policies that don't force pages to disk at commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecoveryPolicy {
+	#[default]
+	UndoOnly,
+	UndoRedo,
+}
+
+// What recovery did at startup, by tx number.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RecoveryReport {
+	// unfinished txs whose changes were undone
+	pub undone: BTreeSet<i32>,
+	// committed txs whose changes were reapplied
+	pub redone: BTreeSet<i32>,
+}
+
+impl fmt::Display for RecoveryReport {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "recovered: undid tx {:?}, redid tx {:?}", self.undone, self.redone)
+	}
+}
+
+// The transaction being recovered is passed to rollback/recover rather than owned,
+// since the transaction itself owns its RecoveryMgr.
 pub struct RecoveryMgr {
 	lm: Arc<Mutex<LogMgr>>,
 	bm: Arc<Mutex<BufferMgr>>,
-	tx: Transaction,
+	// shared by the txs of one log; new txs register under its lock,
+	// so none can start during a checkpoint
+	active_txs: Arc<Mutex<BTreeSet<i32>>>,
 	txnum: i32,
+	policy: RecoveryPolicy,
 }
 
 impl RecoveryMgr {
 	pub fn new(
-		tx: Transaction,
+		txnum: i32,
+		lm: Arc<Mutex<LogMgr>>,
+		bm: Arc<Mutex<BufferMgr>>,
+	) -> Result<Self> {
+		let active_txs = lm.lock().unwrap().active_txs();
+		{
+			let mut active = active_txs.lock().unwrap();
+			StartRecord::write_to_log(Arc::clone(&lm), txnum)?;
+			active.insert(txnum);
+		}
+
+		Ok(Self {
+			lm,
+			bm,
+			active_txs,
+			txnum,
+			policy: RecoveryPolicy::default(),
+		})
+	}
+
+	// A read-only tx never writes to the log, not even its START record,
+	// so recovery has nothing to do for it.
+	pub fn new_read_only(
 		txnum: i32,
 		lm: Arc<Mutex<LogMgr>>,
 		bm: Arc<Mutex<BufferMgr>>,
 	) -> Self {
-		StartRecord::write_to_log(Arc::clone(&lm), txnum).unwrap();
+		let active_txs = lm.lock().unwrap().active_txs();
+		Self {
+			lm,
+			bm,
+			active_txs,
+			txnum,
+			policy: RecoveryPolicy::default(),
+		}
+	}
 
-		Self { lm, bm, tx, txnum }
+	pub fn set_policy(&mut self, policy: RecoveryPolicy) {
+		self.policy = policy;
 	}
 
-	pub fn commit(&mut self) -> Result<()> {
-		lock!(self, CommitRecord::write_to_log(Arc::clone(&self.lm), self.txnum)?)
+	pub fn commit(&mut self) -> Result<u64> {
+		let lsn = flush_and_log!(self, CommitRecord::write_to_log(Arc::clone(&self.lm), self.txnum)?)?;
+		self.active_txs.lock().unwrap().remove(&self.txnum);
+		self.checkpoint_if_due()?;
+
+		Ok(lsn)
 	}
 
-	pub fn rollback(&mut self) -> Result<()> {
-		self.do_rollback()?;
-		lock!(self, RollbackRecord::write_to_log(Arc::clone(&self.lm), self.txnum)?)
+	pub fn rollback(&mut self, tx: &mut Transaction) -> Result<()> {
+		self.do_rollback(tx)?;
+		flush_and_log!(self, RollbackRecord::write_to_log(Arc::clone(&self.lm), self.txnum)?)?;
+		self.active_txs.lock().unwrap().remove(&self.txnum);
+		self.checkpoint_if_due()?;
+
+		Ok(())
 	}
 
-	pub fn recover(&mut self) -> Result<()> {
-		self.do_recover()?;
-		lock!(self, CheckpointRecord::write_to_log(Arc::clone(&self.lm))?)
+	// Writes a nonquiescent checkpoint while other txs keep running.
+	// Every dirty page goes to disk first, so recovery only has to look
+	// before the checkpoint for the changes of the txs it lists.
+	pub fn checkpoint(lm: Arc<Mutex<LogMgr>>, bm: Arc<Mutex<BufferMgr>>) -> Result<u64> {
+		let active = lm.lock().unwrap().active_txs();
+		let active_txs = active.lock().unwrap();
+		bm.lock().unwrap().flush_all_dirty()?;
+		let txs: Vec<i32> = active_txs.iter().copied().collect();
+		let lsn = CheckpointRecord::write_to_log(Arc::clone(&lm), &txs)?;
+		let mut lm = lm.lock().unwrap();
+		lm.flush(lsn)?;
+		lm.checkpointed(lsn);
+
+		Ok(lsn)
+	}
+
+	// Checked when a tx finishes, since no buffer is locked then
+	// and flushing them for the checkpoint can't deadlock.
+	fn checkpoint_if_due(&self) -> Result<()> {
+		if self.lm.lock().unwrap().needs_checkpoint() {
+			Self::checkpoint(Arc::clone(&self.lm), Arc::clone(&self.bm))?;
+		}
+
+		Ok(())
 	}
 
-	pub fn set_i32(&mut self, buff: &mut Buffer, offset: i32, _new_val: i32) -> Result<u64> {
+	// Undoes this tx's records with an lsn greater than the given one,
+	// writing a CLR for each as a full rollback does.
+	pub fn rollback_to(&mut self, tx: &mut Transaction, lsn: u64) -> Result<()> {
+		self.undo_back_to(tx, Some(lsn))
+	}
+
+	// Recovery is idempotent: running it again, even after a crash in the middle of it,
+	// leaves the same data. Undo and redo write whole values rather than deltas, and
+	// an undo cut short is finished from its CLRs instead of being logged twice.
+	pub fn recover(&mut self, tx: &mut Transaction) -> Result<RecoveryReport> {
+		let report = self.do_recover(tx)?;
+		// a quiescent checkpoint needs every dirty page on disk, not only this tx's
+		self.bm.lock().unwrap().flush_all_dirty()?;
+		// no other tx runs during recovery, so nothing is listed as active
+		let lsn = CheckpointRecord::write_to_log(Arc::clone(&self.lm), &[])?;
+		let mut lm = self.lm.lock().unwrap();
+		lm.flush(lsn)?;
+		lm.checkpointed(lsn);
+
+		Ok(report)
+	}
+
+	// None means the new value equals the old one, so no record is written.
+	pub fn set_i32(&mut self, buff: &mut Buffer, offset: i32, new_val: i32) -> Result<Option<u64>> {
 		let old_val = buff.contents().get_i32(offset as usize)?;
+		if old_val == new_val {
+			return Ok(None);
+		}
 		if let Some(blk) = buff.block() {
 			return SetI32Record::write_to_log(
 				Arc::clone(&self.lm),
@@ -91,7 +217,8 @@ impl RecoveryMgr {
 				blk,
 				offset,
 				old_val,
-			);
+				new_val,
+			).map(Some);
 		}
 
 		Err(From::from(RecoveryMgrError::BufferFailed(
@@ -99,8 +226,74 @@ impl RecoveryMgr {
 		)))
 	}
 
-	pub fn set_string(&mut self, buff: &mut Buffer, offset: i32, _new_val: &str) -> Result<u64> {
+	pub fn set_i64(&mut self, buff: &mut Buffer, offset: i32, new_val: i64) -> Result<Option<u64>> {
+		let old_val = buff.contents().get_i64(offset as usize)?;
+		if old_val == new_val {
+			return Ok(None);
+		}
+		if let Some(blk) = buff.block() {
+			return SetI64Record::write_to_log(
+				Arc::clone(&self.lm),
+				self.txnum,
+				blk,
+				offset,
+				old_val,
+				new_val,
+			).map(Some);
+		}
+
+		Err(From::from(RecoveryMgrError::BufferFailed(
+			"set_i64".to_string(),
+		)))
+	}
+
+	pub fn set_bool(&mut self, buff: &mut Buffer, offset: i32, new_val: bool) -> Result<Option<u64>> {
+		let old_val = buff.contents().get_bool(offset as usize)?;
+		if old_val == new_val {
+			return Ok(None);
+		}
+		if let Some(blk) = buff.block() {
+			return SetBoolRecord::write_to_log(
+				Arc::clone(&self.lm),
+				self.txnum,
+				blk,
+				offset,
+				old_val,
+				new_val,
+			).map(Some);
+		}
+
+		Err(From::from(RecoveryMgrError::BufferFailed(
+			"set_bool".to_string(),
+		)))
+	}
+
+	pub fn set_bytes(&mut self, buff: &mut Buffer, offset: i32, new_val: &[u8]) -> Result<Option<u64>> {
+		let old_val = buff.contents().get_bytes_vec(offset as usize)?;
+		if old_val == new_val {
+			return Ok(None);
+		}
+		if let Some(blk) = buff.block() {
+			return SetBytesRecord::write_to_log(
+				Arc::clone(&self.lm),
+				self.txnum,
+				blk,
+				offset,
+				old_val,
+				new_val.to_vec(),
+			).map(Some);
+		}
+
+		Err(From::from(RecoveryMgrError::BufferFailed(
+			"set_bytes".to_string(),
+		)))
+	}
+
+	pub fn set_string(&mut self, buff: &mut Buffer, offset: i32, new_val: &str) -> Result<Option<u64>> {
 		let old_val = buff.contents().get_string(offset as usize)?;
+		if old_val == new_val {
+			return Ok(None);
+		}
 
 		if let Some(blk) = buff.block() {
 			return SetStringRecord::write_to_log(
@@ -109,7 +302,8 @@ impl RecoveryMgr {
 				blk,
 				offset,
 				old_val,
-			);
+				new_val.to_string(),
+			).map(Some);
 		}
 
 		Err(From::from(RecoveryMgrError::BufferFailed(
@@ -117,43 +311,157 @@ impl RecoveryMgr {
 		)))
 	}
 
-	fn do_rollback(&mut self) -> Result<()> {
-		let mut lm = self.lm.lock().unwrap();
-		
-		let iter = lm.iterator()?;
-		// この辺map等の処理に変えたい
+	fn do_rollback(&mut self, tx: &mut Transaction) -> Result<()> {
+		self.undo_back_to(tx, None)
+	}
+
+	// Undoes this tx's records from the end of the log back to its START, or to the
+	// given lsn. A partial rollback has left CLRs for the records it undid. Undo is
+	// last-in first-out, so each data record met while CLRs are stacked up is one of
+	// them and is skipped; the CLRs of the log stay nested the way recovery expects.
+	fn undo_back_to(&mut self, tx: &mut Transaction, lsn: Option<u64>) -> Result<()> {
+		// the log manager must not stay locked while undoing, since pinning may flush buffers
+		let (iter, mut reclsn) = {
+			let mut lm = self.lm.lock().unwrap();
+			(lm.iterator()?, lm.latest_lsn())
+		};
+		let mut clrs = 0;
 		for bytes in iter {
-			let rec = create_log_record(bytes)?;
-			if rec.tx_number() == self.txnum {
-				if rec.op() == TxType::START {
-					return Ok(())
-				}
+			if lsn.is_some_and(|lsn| reclsn <= lsn) {
+				break;
+			}
+			reclsn -= 1;
 
-				rec.undo(&mut self.tx)?;
+			let rec = create_log_record(bytes)?;
+			if rec.tx_number() != self.txnum {
+				continue;
+			}
+			match rec.op() {
+				TxType::START => break,
+				TxType::COMPENSATION => clrs += 1,
+				_ if clrs > 0 => clrs -= 1,
+				_ => self.undo_and_compensate(tx, rec)?,
 			}
 		}
 
 		Ok(())
 	}
-	fn do_recover(&mut self) -> Result<()> {
-		let mut finished_txs = vec![];
-		let mut lm = self.lm.lock().unwrap();
-		let iter = lm.iterator()?;
+	// The log is scanned backward from the end to the last checkpoint, undoing the
+	// changes of unfinished txs. Then, with UndoRedo, the changes of committed txs,
+	// which may not have reached the disk, are redone in log order. Their CLRs are
+	// redone along with them, so the writes undone by a partial rollback stay undone.
+	// The txs listed in the checkpoint may have changes before it, so the scan goes on
+	// until their START records, undoing only their changes. Their changes made before
+	// the checkpoint are already on disk, so none of them need to be redone.
+	// A rollback cut short by the crash has left CLRs for the records it undid. Undo is
+	// last-in first-out, so each data record met while CLRs of its tx are stacked up is
+	// one of them; it is redone from its CLR instead of being undone and logged again.
+	fn do_recover(&mut self, tx: &mut Transaction) -> Result<RecoveryReport> {
+		let mut report = RecoveryReport::default();
+		let mut committed_txs = vec![];
+		let mut rolledback_txs = vec![];
+		let mut redo_records = vec![];
+		let mut clrs: HashMap<i32, Vec<LogRecord>> = HashMap::new();
+		// the unfinished txs of the checkpoint whose START is not yet reached
+		let mut pending_txs: Option<Vec<i32>> = None;
+		let iter = self.lm.lock().unwrap().iterator()?;
 		for bytes in iter {
-			let rec = create_log_record(bytes)?;
-			match rec.op() {
-				TxType::CHECKPOINT => return Ok(()),
-				TxType::COMMIT | TxType::ROLLBACK => {
-					finished_txs.push(rec.tx_number());
+			let rec = create_log_record(bytes.clone())?;
+			let txnum = rec.tx_number();
+			let finished = committed_txs.contains(&txnum) || rolledback_txs.contains(&txnum);
+			match (rec.op(), pending_txs.as_mut()) {
+				(TxType::CHECKPOINT, None) => {
+					let ckpt = CheckpointRecord::new(Page::new_from_bytes(bytes))?;
+					let txs: Vec<i32> = ckpt
+						.active_txs()
+						.iter()
+						.copied()
+						.filter(|t| !committed_txs.contains(t) && !rolledback_txs.contains(t))
+						.collect();
+					if txs.is_empty() {
+						break;
+					}
+					pending_txs = Some(txs);
+				}
+				(TxType::START, None) => {
+					// the recovering tx itself has started but has nothing to undo
+					if !finished && txnum != self.txnum {
+						report.undone.insert(txnum);
+					}
+				}
+				(TxType::START, Some(txs)) => {
+					if txs.contains(&txnum) {
+						report.undone.insert(txnum);
+					}
+					txs.retain(|t| *t != txnum);
+					if txs.is_empty() {
+						break;
+					}
+				}
+				(TxType::COMPENSATION, None) => {
+					if committed_txs.contains(&txnum) {
+						if self.policy == RecoveryPolicy::UndoRedo {
+							redo_records.push(rec);
+						}
+					} else if !finished {
+						clrs.entry(txnum).or_default().push(rec);
+					}
+				}
+				(TxType::COMPENSATION, Some(txs)) => {
+					if txs.contains(&txnum) {
+						clrs.entry(txnum).or_default().push(rec);
+					}
 				}
-				_ => {
-					if !finished_txs.contains(&rec.tx_number()) {
-						rec.undo(&mut self.tx)?;
+				(TxType::COMMIT, None) => committed_txs.push(txnum),
+				(TxType::ROLLBACK, None) => rolledback_txs.push(txnum),
+				(TxType::CHECKPOINT | TxType::COMMIT | TxType::ROLLBACK, _) => {}
+				(_, None) => {
+					if committed_txs.contains(&txnum) {
+						if self.policy == RecoveryPolicy::UndoRedo {
+							report.redone.insert(txnum);
+							redo_records.push(rec);
+						}
+					} else if !finished {
+						report.undone.insert(txnum);
+						self.undo_unless_compensated(tx, rec, &mut clrs)?;
+					}
+				}
+				(_, Some(txs)) => {
+					if txs.contains(&txnum) {
+						report.undone.insert(txnum);
+						self.undo_unless_compensated(tx, rec, &mut clrs)?;
 					}
 				}
 			}
 		}
 
-		Ok(())
+		for rec in redo_records.iter().rev() {
+			rec.redo(tx)?;
+		}
+
+		Ok(report)
+	}
+
+	// the CLR is logged first, so the undo is redone if the crash hits right after it
+	fn undo_and_compensate(
+		&mut self,
+		tx: &mut Transaction,
+		rec: LogRecord,
+	) -> Result<()> {
+		CompensationRecord::write_to_log(Arc::clone(&self.lm), &rec)?;
+		rec.undo(tx)
+	}
+
+	fn undo_unless_compensated(
+		&mut self,
+		tx: &mut Transaction,
+		rec: LogRecord,
+		clrs: &mut HashMap<i32, Vec<LogRecord>>,
+	) -> Result<()> {
+		match clrs.get_mut(&rec.tx_number()).and_then(|stack| stack.pop()) {
+			// the undone value may not have reached the disk
+			Some(clr) => clr.redo(tx),
+			None => self.undo_and_compensate(tx, rec),
+		}
 	}
 }
@@ -1,10 +1,18 @@
 use anyhow::Result;
-use std::sync::{Arc, Mutex, Once};
+use core::fmt;
+use std::{
+	sync::{
+		atomic::{AtomicI32, Ordering},
+		Arc, Mutex,
+	},
+	time::Duration,
+};
 
 use crate::{
-	buffer::manager::BufferMgr,
-	file::{block_id::BlockId, manager::FileMgr, page::PageSetter},
+	buffer::{buffer::Buffer, manager::BufferMgr},
+	file::{block_id::BlockId, manager::FileMgr, page::{Page, PageSetter}},
 	log::manager::LogMgr,
+	types::page_bytes::ToPageBytes,
 };
 
 use super::{
@@ -13,24 +21,88 @@ use super::{
 		manager::ConcurrencyMgr,
 		locktable::LockTableKey,
 	},
-	recovery::manager::RecoveryMgr,
+	recovery::manager::{RecoveryMgr, RecoveryPolicy, RecoveryReport},
 };
 
 // block_idをunsignedのままにしておきたいが，オーバーフローの検知とかができるi32のが良い？
 static END_OF_FILE: u64 = std::u64::MAX;
 // next_tx_num をTransactionのメンバ変数にしない
-static mut NEXT_TX_NUM: Option<Arc<Mutex<i32>>> = None;
-static ONCE: Once = Once::new();
+static NEXT_TX_NUM: AtomicI32 = AtomicI32::new(0);
+
+#[derive(Debug)]
+pub enum TransactionError {
+	ReadOnly,
+	// the tx could not get a lock and has been rolled back
+	Aborted { reason: String },
+}
+
+impl std::error::Error for TransactionError {}
+impl fmt::Display for TransactionError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			TransactionError::ReadOnly => {
+				write!(f, "write on a read-only transaction")
+			}
+			TransactionError::Aborted { reason } => {
+				write!(f, "transaction aborted: {}", reason)
+			}
+		}
+	}
+}
+
+// How long a transaction holds its shared locks.
+// - ReadCommitted: S-locks are released right after each read, so another tx
+//   may change a value between two reads (non-repeatable reads, phantoms).
+// - RepeatableRead: S-locks on blocks are held until commit, but the
+//   end-of-file lock is released after size(), so appended blocks may appear (phantoms).
+// - Serializable: every lock is held until commit.
+// Uncommitted values are never read, because X-locks are always held until commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IsolationLevel {
+	ReadCommitted,
+	RepeatableRead,
+	#[default]
+	Serializable,
+}
+
+// Options of a transaction. The default is a serializable read-write tx
+// that waits as long as the lock table allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TransactionConfig {
+	pub isolation_level: IsolationLevel,
+	pub read_only: bool,
+	// None leaves it to the lock table
+	pub lock_timeout: Option<Duration>,
+}
+
+// Counts of what a transaction has done, for profiling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TxStats {
+	pub reads: u64,
+	pub writes: u64,
+	pub pins: u64,
+	// locks newly acquired; upgrading an S-lock to an X-lock doesn't count
+	pub locks: u64,
+}
+
+// The log position a transaction can partially roll back to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SavepointId(u64);
 
 // 参考元のだとMutexにしてないが，必要だと思うので追加
 pub struct Transaction {
-	recovery_mgr: Option<Arc<Mutex<RecoveryMgr>>>,
+	recovery_mgr: Arc<Mutex<RecoveryMgr>>,
 	concur_mgr: ConcurrencyMgr,
 	fm: Arc<Mutex<FileMgr>>,
 	lm: Arc<Mutex<LogMgr>>,
 	bm: Arc<Mutex<BufferMgr>>,
 	txnum: i32,
 	mybuffers: BufferList,
+	isolation_level: IsolationLevel,
+	read_only: bool,
+	// set once commit or rollback has finished
+	completed: bool,
+	stats: TxStats,
 }
 
 impl Transaction {
@@ -38,81 +110,182 @@ impl Transaction {
 		fm: Arc<Mutex<FileMgr>>,
 		lm: Arc<Mutex<LogMgr>>,
 		bm: Arc<Mutex<BufferMgr>>,
-	) -> Self {
-
-		unsafe {
-			ONCE.call_once(|| {
-				let singleton = Arc::new(Mutex::new(0));
-				NEXT_TX_NUM = Some(singleton);
-			});
-			Self {
-				recovery_mgr: None, // dummy
-				concur_mgr: ConcurrencyMgr::new(),
-				fm,
-				lm,
-				bm: bm.clone(),
-				txnum: Self::next_tx_number(),
-				mybuffers: BufferList::new(bm),
-			}
+	) -> Result<Self> {
+		Self::with_config(fm, lm, bm, TransactionConfig::default())
+	}
+
+	// A read-only tx takes only S-locks and writes no log records.
+	pub fn new_read_only(
+		fm: Arc<Mutex<FileMgr>>,
+		lm: Arc<Mutex<LogMgr>>,
+		bm: Arc<Mutex<BufferMgr>>,
+	) -> Result<Self> {
+		let config = TransactionConfig {
+			read_only: true,
+			..Default::default()
+		};
+		Self::with_config(fm, lm, bm, config)
+	}
+
+	pub fn with_config(
+		fm: Arc<Mutex<FileMgr>>,
+		lm: Arc<Mutex<LogMgr>>,
+		bm: Arc<Mutex<BufferMgr>>,
+		config: TransactionConfig,
+	) -> Result<Self> {
+		let txnum = Self::next_tx_number();
+		let recovery_mgr = if config.read_only {
+			RecoveryMgr::new_read_only(txnum, Arc::clone(&lm), Arc::clone(&bm))
+		} else {
+			RecoveryMgr::new(txnum, Arc::clone(&lm), Arc::clone(&bm))?
+		};
+		let mut concur_mgr = ConcurrencyMgr::new(txnum);
+		if let Some(timeout) = config.lock_timeout {
+			concur_mgr.set_lock_timeout(timeout);
 		}
+
+		Ok(Self {
+			recovery_mgr: Arc::new(Mutex::new(recovery_mgr)),
+			concur_mgr,
+			fm,
+			lm,
+			bm: bm.clone(),
+			txnum,
+			mybuffers: BufferList::new(bm),
+			isolation_level: config.isolation_level,
+			read_only: config.read_only,
+			completed: false,
+			stats: TxStats::default(),
+		})
 	}
 
-	pub fn commit(&mut self) -> Result<()> {
-		self.recovery_mgr
-			.as_ref()
-			.unwrap()
-			.lock()
-			.unwrap()
-			.commit()?;
-		self.concur_mgr.release()?;
-		self.mybuffers.unpin_all()?;
-		println!("transaction {} committed", self.txnum);
+	// Commits in this order:
+	// 1. the tx's buffers and the COMMIT record are made durable,
+	// 2. the locks are released,
+	// 3. the buffers are unpinned.
+	// The tx is committed once step 1 succeeds; if it fails, nothing is released
+	// and the tx can still be rolled back. Steps 2 and 3 are both attempted
+	// even if one fails, and the first error is returned.
+	// Returns the lsn of the COMMIT record; a read-only tx writes no record and returns 0.
+	pub fn commit(&mut self) -> Result<u64> {
+		let mut lsn = 0;
+		if !self.read_only {
+			lsn = self.recovery_mgr.lock().unwrap().commit()?;
+		}
+		self.completed = true;
+		self.release_all()?;
+		println!("transaction {} committed: {:?}", self.txnum, self.stats);
 
-		Ok(())
+		Ok(lsn)
 	}
 
 	pub fn rollback(&mut self) -> Result<()> {
-		self.recovery_mgr
-			.as_ref()
-			.unwrap()
-			.lock()
-			.unwrap()
-			.rollback()?;
-		self.concur_mgr.release()?;
-		self.mybuffers.unpin_all()?;
+		// undo writes through self, so hold the manager through a separate handle
+		if !self.read_only {
+			let rm = Arc::clone(&self.recovery_mgr);
+			rm.lock().unwrap().rollback(self)?;
+		}
+		self.completed = true;
+		self.release_all()?;
 		println!("transaction {} rolled back", self.txnum);
 
 		Ok(())
 	}
 
-	pub fn recover(&mut self) -> Result<()> {
+	pub fn stats(&self) -> TxStats {
+		self.stats
+	}
+
+	pub fn tx_number(&self) -> i32 {
+		self.txnum
+	}
+
+	pub fn isolation_level(&self) -> IsolationLevel {
+		self.isolation_level
+	}
+
+	pub fn is_read_only(&self) -> bool {
+		self.read_only
+	}
+
+	pub fn set_isolation_level(&mut self, level: IsolationLevel) {
+		self.isolation_level = level;
+	}
+
+	// only matters to a tx that runs recover()
+	pub fn set_recovery_policy(&mut self, policy: RecoveryPolicy) {
+		self.recovery_mgr.lock().unwrap().set_policy(policy);
+	}
+
+	pub fn set_savepoint(&mut self) -> SavepointId {
+		SavepointId(self.lm.lock().unwrap().latest_lsn())
+	}
+
+	// Undoes the changes made after sp. Locks are kept until the tx finishes.
+	pub fn rollback_to(&mut self, sp: SavepointId) -> Result<()> {
+		let rm = Arc::clone(&self.recovery_mgr);
+		let result = rm.lock().unwrap().rollback_to(self, sp.0);
+		result
+	}
+
+	// Recovers the database at system startup, before any other transaction runs.
+	// Changes of transactions that never finished are undone and a quiescent
+	// checkpoint is written, so running it again finds nothing more to undo.
+	pub fn recover_system(
+		fm: Arc<Mutex<FileMgr>>,
+		lm: Arc<Mutex<LogMgr>>,
+		bm: Arc<Mutex<BufferMgr>>,
+	) -> Result<RecoveryReport> {
+		let mut tx = Self::new(fm, lm, bm)?;
+		let report = tx.recover()?;
+		tx.commit()?;
+		println!("{}", report);
+
+		Ok(report)
+	}
+
+	pub fn recover(&mut self) -> Result<RecoveryReport> {
 		self.bm.lock().unwrap().flush_all(self.txnum)?;
-		self.recovery_mgr
-			.as_ref()
-			.unwrap()
-			.lock()
-			.unwrap()
-			.recover()
+		let rm = Arc::clone(&self.recovery_mgr);
+		let result = rm.lock().unwrap().recover(self);
+		result
 	}
 
 	pub fn pin(&mut self, blk: &BlockId) -> Result<()> {
-		self.mybuffers.pin(blk)
+		self.mybuffers.pin(blk)?;
+		self.stats.pins += 1;
+
+		Ok(())
 	}
 
 	pub fn unpin(&mut self, blk: &BlockId) -> Result<()> {
 		self.mybuffers.unpin(blk)
 	}
 
+	// The buffer of a block pinned by this tx, for reading several values
+	// under one buffer lock. Locking the block is left to the caller.
+	pub fn get_buffer(&mut self, blk: &BlockId) -> Option<Arc<Mutex<Buffer>>> {
+		self.mybuffers.get_buffer(blk).cloned()
+	}
+
 	pub fn get_i32(&mut self, blk: &BlockId, offset: i32) -> Result<i32> {
-		self.concur_mgr.s_lock(&LockTableKey::BID(blk.clone()))?;
-		let mut buff = self.mybuffers.get_buffer(blk).unwrap().lock().unwrap();
-		buff.contents().get_i32(offset as usize)
+		self.read(blk, |p| p.get_i32(offset as usize))
+	}
+
+	pub fn get_i64(&mut self, blk: &BlockId, offset: i32) -> Result<i64> {
+		self.read(blk, |p| p.get_i64(offset as usize))
+	}
+
+	pub fn get_bool(&mut self, blk: &BlockId, offset: i32) -> Result<bool> {
+		self.read(blk, |p| p.get_bool(offset as usize))
+	}
+
+	pub fn get_bytes(&mut self, blk: &BlockId, offset: i32) -> Result<Vec<u8>> {
+		self.read(blk, |p| p.get_bytes_vec(offset as usize))
 	}
 
 	pub fn get_string(&mut self, blk: &BlockId, offset: i32) -> Result<String> {
-		self.concur_mgr.s_lock(&LockTableKey::BID(blk.clone()))?;
-		let mut buff = self.mybuffers.get_buffer(blk).unwrap().lock().unwrap();
-		buff.contents().get_string(offset as usize)
+		self.read(blk, |p| p.get_string(offset as usize))
 	}
 
 	pub fn set_i32(
@@ -122,18 +295,37 @@ impl Transaction {
 		val: i32,
 		ok_to_log: bool,
 	) -> Result<()> {
-		self.concur_mgr.x_lock(&LockTableKey::BID(blk.clone()))?;
-		let mut buff = self.mybuffers.get_buffer(blk).unwrap().lock().unwrap();
-		let mut lsn: i32 = -1;
-		if ok_to_log {
-			let mut rm = self.recovery_mgr.as_ref().unwrap().lock().unwrap();
-			lsn = rm.set_i32(&mut buff, offset, val)?.try_into().unwrap();
-		}
-		let p = buff.contents();
-		p.set(offset as usize, val)?;
-		buff.set_modified(self.txnum, lsn);
+		self.write(blk, offset, val, ok_to_log, |rm, buff| rm.set_i32(buff, offset, val))
+	}
 
-		Ok(())
+	pub fn set_i64(
+		&mut self,
+		blk: &BlockId,
+		offset: i32,
+		val: i64,
+		ok_to_log: bool,
+	) -> Result<()> {
+		self.write(blk, offset, val, ok_to_log, |rm, buff| rm.set_i64(buff, offset, val))
+	}
+
+	pub fn set_bool(
+		&mut self,
+		blk: &BlockId,
+		offset: i32,
+		val: bool,
+		ok_to_log: bool,
+	) -> Result<()> {
+		self.write(blk, offset, val, ok_to_log, |rm, buff| rm.set_bool(buff, offset, val))
+	}
+
+	pub fn set_bytes(
+		&mut self,
+		blk: &BlockId,
+		offset: i32,
+		val: &[u8],
+		ok_to_log: bool,
+	) -> Result<()> {
+		self.write(blk, offset, val, ok_to_log, |rm, buff| rm.set_bytes(buff, offset, val))
 	}
 
 	pub fn set_string(
@@ -143,27 +335,23 @@ impl Transaction {
 		val: &str,
 		ok_to_log: bool,
 	) -> Result<()> {
-		self.concur_mgr.x_lock(&LockTableKey::BID(blk.clone()))?;
-		let mut buff = self.mybuffers.get_buffer(blk).unwrap().lock().unwrap();
-		let mut lsn: i32 = -1;
-		if ok_to_log {
-			let mut rm = self.recovery_mgr.as_ref().unwrap().lock().unwrap();
-			lsn = rm.set_string(&mut buff, offset, val)?.try_into().unwrap();
-		}
-		let p = buff.contents();
-		p.set(offset as usize, val.to_string())?;
-		buff.set_modified(self.txnum, lsn);
-
-		Ok(())
+		self.write(blk, offset, val.to_string(), ok_to_log, |rm, buff| rm.set_string(buff, offset, val))
 	}
 
 	pub fn size(&mut self, filename: &str) -> Result<u64> {
-		self.concur_mgr.s_lock(&LockTableKey::DUMMY(END_OF_FILE))?;
-		self.fm.lock().unwrap().length(filename)
+		let key = LockTableKey::DUMMY(END_OF_FILE);
+		self.s_lock(&key)?;
+		let size = self.fm.lock().unwrap().length(filename)?;
+		if self.isolation_level != IsolationLevel::Serializable {
+			self.concur_mgr.release_s_lock(&key)?;
+		}
+
+		Ok(size)
 	}
 
 	pub fn append(&mut self, filename: &str) -> Result<BlockId> {
-		self.concur_mgr.x_lock(&LockTableKey::DUMMY(END_OF_FILE))?;
+		self.check_writable()?;
+		self.x_lock(&LockTableKey::DUMMY(END_OF_FILE))?;
 		self.fm.lock().unwrap().append(filename)
 	}
 
@@ -175,14 +363,104 @@ impl Transaction {
 		self.bm.lock().unwrap().available()
 	}
 
+	fn release_all(&mut self) -> Result<()> {
+		let released = self.concur_mgr.release();
+		let unpinned = self.mybuffers.unpin_all();
+		released.and(unpinned)
+	}
+
+	fn s_lock(&mut self, key: &LockTableKey) -> Result<()> {
+		let num_locks = self.concur_mgr.num_locks();
+		let result = self.concur_mgr.s_lock(key);
+		self.abort_on_lock_error(result)?;
+		self.stats.locks += (self.concur_mgr.num_locks() - num_locks) as u64;
+
+		Ok(())
+	}
+
+	fn x_lock(&mut self, key: &LockTableKey) -> Result<()> {
+		let num_locks = self.concur_mgr.num_locks();
+		let result = self.concur_mgr.x_lock(key);
+		self.abort_on_lock_error(result)?;
+		self.stats.locks += (self.concur_mgr.num_locks() - num_locks) as u64;
+
+		Ok(())
+	}
+
+	// A lock wait that timed out is most likely a deadlock, so the tx gives up
+	// its changes and locks before reporting it.
+	fn abort_on_lock_error(&mut self, result: Result<()>) -> Result<()> {
+		if let Err(e) = result {
+			self.rollback()?;
+			return Err(From::from(TransactionError::Aborted {
+				reason: e.to_string(),
+			}));
+		}
+
+		Ok(())
+	}
+
+	fn check_writable(&self) -> Result<()> {
+		if self.read_only {
+			return Err(From::from(TransactionError::ReadOnly));
+		}
+
+		Ok(())
+	}
+
+	fn read<T>(&mut self, blk: &BlockId, get: impl FnOnce(&Page) -> Result<T>) -> Result<T> {
+		let key = LockTableKey::BID(blk.clone());
+		self.s_lock(&key)?;
+		let contents = self.mybuffers.get_buffer(blk).unwrap().lock().unwrap().shared_contents();
+		let val = get(&contents.read().unwrap())?;
+		self.stats.reads += 1;
+		if self.isolation_level == IsolationLevel::ReadCommitted {
+			self.concur_mgr.release_s_lock(&key)?;
+		}
+
+		Ok(val)
+	}
+
+	// The log record is written by log, which returns None when the value is unchanged;
+	// then neither the log nor the buffer is written.
+	fn write<T: ToPageBytes>(
+		&mut self,
+		blk: &BlockId,
+		offset: i32,
+		val: T,
+		ok_to_log: bool,
+		log: impl FnOnce(&mut RecoveryMgr, &mut Buffer) -> Result<Option<u64>>,
+	) -> Result<()> {
+		self.check_writable()?;
+		self.x_lock(&LockTableKey::BID(blk.clone()))?;
+		let mut buff = self.mybuffers.get_buffer(blk).unwrap().lock().unwrap();
+		let mut lsn: i32 = -1;
+		if ok_to_log {
+			match log(&mut self.recovery_mgr.lock().unwrap(), &mut buff)? {
+				Some(reclsn) => lsn = reclsn.try_into()?,
+				None => return Ok(()),
+			}
+		}
+		buff.contents().set(offset as usize, val)?;
+		buff.set_modified(self.txnum, lsn);
+		drop(buff);
+		self.stats.writes += 1;
+
+		Ok(())
+	}
+
 	fn next_tx_number() -> i32 {
 		// next_tx_num をTransactionのメンバ変数にしないため，引数にselfを用いない
-		unsafe {
-			let next_tx_num_tmp = NEXT_TX_NUM.clone().unwrap();
-			let mut next_tx_num = next_tx_num_tmp.lock().unwrap();
-			*(next_tx_num) += 1;
+		NEXT_TX_NUM.fetch_add(1, Ordering::SeqCst) + 1
+	}
+}
 
-			*next_tx_num
+// A tx dropped before it finished (e.g. by an early return through `?`)
+// is rolled back, so that its changes are undone and its locks and pins released.
+impl Drop for Transaction {
+	fn drop(&mut self) {
+		if !self.completed {
+			let _ = self.rollback();
 		}
 	}
 }
@@ -191,38 +469,784 @@ impl Transaction {
 mod tests {
 	use super::*;
 
+	use std::{collections::{BTreeSet, HashSet}, fs, thread};
+
 	use crate::{
-		file::manager::FileMgr,
+		file::{manager::FileMgr, page::Page},
 		buffer::manager::BufferMgr,
 		log::manager::LogMgr,
+		tx::recovery::{
+			logrecord::{create_log_record, CheckpointRecord, CompensationRecord, TxType},
+			manager::RecoveryMgr,
+		},
 	};
 
+	type Managers = (Arc<Mutex<FileMgr>>, Arc<Mutex<LogMgr>>, Arc<Mutex<BufferMgr>>);
+
+	fn setup(dir: &str) -> Managers {
+		let _ = fs::remove_dir_all(dir);
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400).unwrap()));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log").unwrap()));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 10).unwrap()));
+		(fm, lm, bm)
+	}
+
 	#[test]
 	fn test_next_tx_number_is_singleton() {
-		let fm = Arc::new(Mutex::new(FileMgr::new("txtest/transactiontest", 200).unwrap()));
-		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "testfile").unwrap()));
-		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 10)));
-		// マルチスレッドでシングルトンであるかどうかが確認できていない
-		unsafe {
-			let _ = Transaction::new(fm.clone(), lm.clone(), bm.clone());
-			let p1 = NEXT_TX_NUM.clone().unwrap();
-			let _ = Transaction::new(fm.clone(), lm.clone(), bm.clone());
-			let p2 = NEXT_TX_NUM.clone().unwrap();
-			assert!(Arc::ptr_eq(&p1, &p2));
-		}
+		let (fm, lm, bm) = setup("txtest/singleton");
+
+		let handles: Vec<_> = (0..10)
+			.map(|_| {
+				let (fm, lm, bm) = (fm.clone(), lm.clone(), bm.clone());
+				thread::spawn(move || Transaction::new(fm, lm, bm).unwrap().txnum)
+			})
+			.collect();
+		let txnums: HashSet<i32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+		assert_eq!(txnums.len(), 10);
 	}
 
 	#[test]
 	fn test_txnum_is_increment() {
-		let fm = Arc::new(Mutex::new(FileMgr::new("txtest/transactiontest", 200).unwrap()));
-		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "testfile").unwrap()));
-		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 10)));
+		let (fm, lm, bm) = setup("txtest/increment");
 
-		let tx_base = Transaction::new(fm.clone(), lm.clone(), bm.clone());
-		let base = tx_base.txnum;
-		for i in 1..11 {
-			let tx = Transaction::new(fm.clone(), lm.clone(), bm.clone());
-			assert_eq!(tx.txnum, i + base);
+		// Other tests create transactions concurrently and share the counter,
+		// so numbers are increasing but not necessarily consecutive here.
+		let tx_base = Transaction::new(fm.clone(), lm.clone(), bm.clone()).unwrap();
+		let mut prev = tx_base.txnum;
+		for _ in 1..11 {
+			let tx = Transaction::new(fm.clone(), lm.clone(), bm.clone()).unwrap();
+			assert!(tx.txnum > prev);
+			prev = tx.txnum;
 		}
 	}
+
+	// Abandons tx as a crash would: its log records stay unfinished,
+	// but its locks and pins go away with the process.
+	fn crash(mut tx: Transaction) -> Result<()> {
+		tx.concur_mgr.release()?;
+		tx.mybuffers.unpin_all()?;
+		// keeps Drop from rolling the tx back
+		tx.completed = true;
+		Ok(())
+	}
+
+	#[test]
+	fn test_commit_writes_commit_record() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/commit");
+		let blk = BlockId::new("commitfile", 1);
+
+		let mut tx = Transaction::new(fm, lm.clone(), bm)?;
+		let start_lsn = lm.lock().unwrap().latest_lsn();
+		tx.pin(&blk)?;
+		tx.set_i32(&blk, 80, 1234, true)?;
+		let commit_lsn = tx.commit()?;
+		assert!(commit_lsn > start_lsn);
+		assert!(lm.lock().unwrap().last_saved_lsn() >= commit_lsn);
+
+		let rec = create_log_record(lm.lock().unwrap().iterator()?.next().unwrap())?;
+		assert_eq!(rec.op(), TxType::COMMIT);
+		assert_eq!(rec.tx_number(), tx.tx_number());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_tx_number_matches_start_record() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/txnumber");
+
+		let tx = Transaction::new(fm, lm.clone(), bm)?;
+		let rec = create_log_record(lm.lock().unwrap().iterator()?.next().unwrap())?;
+		assert_eq!(rec.op(), TxType::START);
+		assert_eq!(rec.tx_number(), tx.tx_number());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_commit_releases_locks_and_pins() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/commitorder");
+		let blk = BlockId::new("commitorderfile", 1);
+		let available = bm.lock().unwrap().available()?;
+
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.pin(&blk)?;
+		tx1.pin(&blk)?;
+		tx1.set_i32(&blk, 80, 77, true)?;
+		tx1.commit()?;
+		assert_eq!(bm.lock().unwrap().available()?, available);
+		// a committed tx is not rolled back when dropped
+		drop(tx1);
+
+		let mut tx2 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx2.pin(&blk)?;
+		tx2.set_i32(&blk, 80, 78, true)?;
+		assert_eq!(tx2.get_i32(&blk, 80)?, 78);
+		tx2.rollback()?;
+
+		let mut tx3 = Transaction::new_read_only(fm, lm, bm)?;
+		tx3.pin(&blk)?;
+		assert_eq!(tx3.get_i32(&blk, 80)?, 77);
+		tx3.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_with_config() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/config");
+		let blk = BlockId::new("configfile", 1);
+
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.pin(&blk)?;
+		tx1.set_i32(&blk, 80, 1, true)?;
+
+		let config = TransactionConfig {
+			isolation_level: IsolationLevel::RepeatableRead,
+			read_only: true,
+			lock_timeout: Some(Duration::from_millis(100)),
+		};
+		let mut tx2 = Transaction::with_config(fm, lm, bm, config)?;
+		assert_eq!(tx2.isolation_level(), IsolationLevel::RepeatableRead);
+		assert!(tx2.is_read_only());
+
+		// gives up on tx1's X-lock after its own timeout
+		tx2.pin(&blk)?;
+		let err = tx2.get_i32(&blk, 80).unwrap_err();
+		assert!(matches!(
+			err.downcast_ref(),
+			Some(TransactionError::Aborted { .. })
+		));
+		tx1.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_stats_count_operations() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/stats");
+		let blk1 = BlockId::new("statsfile", 1);
+		let blk2 = BlockId::new("statsfile", 2);
+
+		let mut tx = Transaction::new(fm, lm, bm)?;
+		tx.pin(&blk1)?;
+		tx.pin(&blk2)?;
+		tx.set_i32(&blk1, 80, 1, true)?;
+		tx.set_string(&blk1, 40, "stats", true)?;
+		tx.get_i32(&blk1, 80)?;
+		tx.get_i32(&blk2, 80)?;
+		tx.get_string(&blk2, 40)?;
+
+		assert_eq!(
+			tx.stats(),
+			TxStats {
+				reads: 3,
+				writes: 2,
+				pins: 2,
+				locks: 2,
+			}
+		);
+		tx.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_unchanged_value_is_not_logged() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/unchanged");
+		let blk = BlockId::new("unchangedfile", 1);
+
+		let mut tx = Transaction::new(fm, lm.clone(), bm)?;
+		tx.pin(&blk)?;
+		tx.set_i32(&blk, 80, 7, true)?;
+		tx.set_i64(&blk, 0, 7, true)?;
+		tx.set_string(&blk, 40, "same", true)?;
+		let lsn = lm.lock().unwrap().latest_lsn();
+
+		tx.set_i32(&blk, 80, 7, true)?;
+		tx.set_i64(&blk, 0, 7, true)?;
+		tx.set_string(&blk, 40, "same", true)?;
+		assert_eq!(lm.lock().unwrap().latest_lsn(), lsn);
+		assert_eq!(tx.stats().writes, 3);
+		assert_eq!(tx.get_string(&blk, 40)?, "same");
+		tx.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_read_then_write_same_block() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/upgrade");
+		let blk = BlockId::new("upgradefile", 1);
+
+		let mut tx = Transaction::new(fm, lm, bm)?;
+		tx.pin(&blk)?;
+		assert_eq!(tx.get_i32(&blk, 80)?, 0);
+		tx.set_i32(&blk, 80, 10, true)?;
+		assert_eq!(tx.get_i32(&blk, 80)?, 10);
+		tx.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_drop_rolls_back_uncommitted() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/drop");
+		let blk = BlockId::new("dropfile", 1);
+		let available = bm.lock().unwrap().available()?;
+
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.pin(&blk)?;
+		tx1.set_i32(&blk, 80, 123, true)?;
+		drop(tx1);
+		assert_eq!(bm.lock().unwrap().available()?, available);
+
+		// tx1's X-lock is gone, so this doesn't wait
+		let mut tx2 = Transaction::new(fm, lm, bm)?;
+		tx2.pin(&blk)?;
+		assert_eq!(tx2.get_i32(&blk, 80)?, 0);
+		tx2.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_rollback_restores_committed_values() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/rollback");
+		let blk = BlockId::new("rollbackfile", 1);
+
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.pin(&blk)?;
+		tx1.set_i32(&blk, 80, 1, true)?;
+		tx1.set_string(&blk, 40, "one", true)?;
+		tx1.commit()?;
+
+		let mut tx2 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx2.pin(&blk)?;
+		tx2.set_i32(&blk, 80, 2, true)?;
+		tx2.set_string(&blk, 40, "two", true)?;
+		tx2.set_i32(&blk, 80, 3, true)?;
+		assert_eq!(tx2.get_i32(&blk, 80)?, 3);
+		tx2.rollback()?;
+
+		let mut tx3 = Transaction::new(fm, lm, bm)?;
+		tx3.pin(&blk)?;
+		assert_eq!(tx3.get_i32(&blk, 80)?, 1);
+		assert_eq!(tx3.get_string(&blk, 40)?, "one");
+		tx3.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_pin_and_get_buffer() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/getbuffer");
+		let blk = BlockId::new("getbufferfile", 1);
+
+		let mut tx = Transaction::new(fm, lm, bm)?;
+		assert!(tx.get_buffer(&blk).is_none());
+		tx.pin(&blk)?;
+		tx.set_i32(&blk, 80, 42, true)?;
+
+		let buff = tx.get_buffer(&blk).unwrap();
+		let mut buff = buff.lock().unwrap();
+		assert_eq!(buff.block(), Some(&blk));
+		assert_eq!(buff.contents().get_i32(80)?, 42);
+		drop(buff);
+
+		tx.unpin(&blk)?;
+		assert!(tx.get_buffer(&blk).is_none());
+		tx.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_set_i64_and_read_back() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/i64");
+		let blk = BlockId::new("i64file", 1);
+		let val = 0x0123456789ABCDEF;
+
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.pin(&blk)?;
+		tx1.set_i64(&blk, 80, val, true)?;
+		tx1.commit()?;
+
+		let mut tx2 = Transaction::new(fm, lm, bm)?;
+		tx2.pin(&blk)?;
+		assert_eq!(tx2.get_i64(&blk, 80)?, val);
+		tx2.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_rollback_to_savepoint() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/savepoint");
+		let blk = BlockId::new("savepointfile", 1);
+
+		let mut tx = Transaction::new(fm, lm, bm)?;
+		tx.pin(&blk)?;
+		tx.set_i32(&blk, 80, 1, true)?;
+		tx.set_string(&blk, 40, "before", true)?;
+		let sp = tx.set_savepoint();
+		tx.set_i32(&blk, 80, 2, true)?;
+		tx.set_i32(&blk, 80, 3, true)?;
+		tx.set_string(&blk, 40, "after", true)?;
+		tx.set_i32(&blk, 120, 4, true)?;
+
+		tx.rollback_to(sp)?;
+		assert_eq!(tx.get_i32(&blk, 80)?, 1);
+		assert_eq!(tx.get_string(&blk, 40)?, "before");
+		assert_eq!(tx.get_i32(&blk, 120)?, 0);
+
+		// the tx goes on after a partial rollback
+		tx.set_i32(&blk, 80, 5, true)?;
+		tx.rollback_to(sp)?;
+		assert_eq!(tx.get_i32(&blk, 80)?, 1);
+		tx.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_recover_system_after_nested_rollback_to() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/nestedsavepoint");
+		let blk = BlockId::new("nestedsavepointfile", 1);
+
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.pin(&blk)?;
+		tx1.set_i32(&blk, 80, 1, true)?;
+		let sp1 = tx1.set_savepoint();
+		tx1.set_i32(&blk, 120, 2, true)?;
+		let sp2 = tx1.set_savepoint();
+		tx1.set_i32(&blk, 160, 3, true)?;
+		tx1.rollback_to(sp2)?;
+		// the write to offset 160 is already compensated, so it isn't undone twice
+		tx1.rollback_to(sp1)?;
+		assert_eq!(count_records(&lm, TxType::COMPENSATION)?, 2);
+		bm.lock().unwrap().flush_all(tx1.txnum)?;
+		crash(tx1)?;
+		drop(bm);
+
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 10).unwrap()));
+		Transaction::recover_system(fm.clone(), lm.clone(), bm.clone())?;
+		let mut tx2 = Transaction::new(fm, lm, bm)?;
+		tx2.pin(&blk)?;
+		for offset in [80, 120, 160] {
+			assert_eq!(tx2.get_i32(&blk, offset)?, 0);
+		}
+		tx2.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_read_committed_releases_s_lock() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/readcommitted");
+		let blk = BlockId::new("readcommittedfile", 1);
+
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.set_isolation_level(IsolationLevel::ReadCommitted);
+		tx1.pin(&blk)?;
+		assert_eq!(tx1.get_i32(&blk, 80)?, 0);
+
+		// tx1 is still running, but no longer blocks the writer
+		let mut tx2 = Transaction::new(fm, lm, bm)?;
+		tx2.pin(&blk)?;
+		tx2.set_i32(&blk, 80, 999, true)?;
+		tx2.commit()?;
+
+		// a non-repeatable read, which ReadCommitted permits
+		assert_eq!(tx1.get_i32(&blk, 80)?, 999);
+		tx1.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_read_only_rejects_writes() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/readonly");
+		let blk = BlockId::new("readonlyfile", 1);
+		let lsn = lm.lock().unwrap().latest_lsn();
+
+		let mut tx = Transaction::new_read_only(fm, lm.clone(), bm)?;
+		tx.pin(&blk)?;
+		assert_eq!(tx.get_i32(&blk, 80)?, 0);
+		for err in [
+			tx.set_i32(&blk, 80, 1, true).unwrap_err(),
+			tx.set_string(&blk, 40, "read-only", true).unwrap_err(),
+			tx.append("readonlyfile").unwrap_err(),
+		] {
+			assert!(matches!(err.downcast_ref(), Some(TransactionError::ReadOnly)));
+		}
+		assert_eq!(tx.get_i32(&blk, 80)?, 0);
+		assert_eq!(tx.commit()?, 0);
+
+		assert_eq!(lm.lock().unwrap().latest_lsn(), lsn);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_lock_conflict_aborts_and_rolls_back() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/abort");
+		let blk1 = BlockId::new("abortfile", 1);
+		let blk2 = BlockId::new("abortfile", 2);
+
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.pin(&blk1)?;
+		tx1.set_i32(&blk1, 80, 111, true)?;
+
+		let config = TransactionConfig {
+			lock_timeout: Some(Duration::from_millis(100)),
+			..Default::default()
+		};
+		let mut tx2 = Transaction::with_config(fm, lm, bm.clone(), config)?;
+		tx2.pin(&blk2)?;
+		tx2.set_i32(&blk2, 80, 222, true)?;
+		tx2.pin(&blk1)?;
+		let available = bm.lock().unwrap().available()?;
+
+		// waits for tx1's X-lock until its timeout runs out
+		let err = tx2.get_i32(&blk1, 80).unwrap_err();
+		assert!(matches!(
+			err.downcast_ref(),
+			Some(TransactionError::Aborted { reason }) if reason.contains(&blk1.to_string())
+		));
+		// tx2's buffers are unpinned and its write to blk2 is undone
+		assert_eq!(bm.lock().unwrap().available()?, available + 1);
+		tx1.pin(&blk2)?;
+		assert_eq!(tx1.get_i32(&blk2, 80)?, 0);
+		tx1.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_recover_with_each_policy() -> Result<()> {
+		let cases = [
+			("txtest/redo", RecoveryPolicy::UndoRedo, 300, "redone"),
+			// without redo the lost block stays lost
+			("txtest/undoonly", RecoveryPolicy::UndoOnly, 0, ""),
+		];
+		for (dir, policy, expected_i32, expected_string) in cases {
+			let (fm, lm, bm) = setup(dir);
+			let blk = BlockId::new("redofile", 1);
+
+			let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+			tx1.pin(&blk)?;
+			tx1.set_i32(&blk, 80, 300, true)?;
+			tx1.set_string(&blk, 40, "redone", true)?;
+			tx1.commit()?;
+
+			// the committed block is lost as if its flush never happened,
+			// and the restarted system has an empty buffer pool
+			let mut p = Page::new_from_size(400);
+			fm.lock().unwrap().write(&blk, &mut p)?;
+			drop(bm);
+			let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 10).unwrap()));
+
+			let mut tx2 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+			tx2.set_recovery_policy(policy);
+			tx2.recover()?;
+			tx2.commit()?;
+
+			let mut tx3 = Transaction::new(fm, lm, bm)?;
+			tx3.pin(&blk)?;
+			assert_eq!(tx3.get_i32(&blk, 80)?, expected_i32);
+			assert_eq!(tx3.get_string(&blk, 40)?, expected_string);
+			tx3.commit()?;
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_redo_keeps_partial_rollback() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/redosavepoint");
+		let blk = BlockId::new("redosavepointfile", 1);
+
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.pin(&blk)?;
+		tx1.set_i32(&blk, 80, 1, true)?;
+		let sp = tx1.set_savepoint();
+		tx1.set_i32(&blk, 80, 2, true)?;
+		tx1.rollback_to(sp)?;
+		tx1.commit()?;
+
+		// the committed block is lost, so redo rebuilds it from the log alone
+		let mut p = Page::new_from_size(400);
+		fm.lock().unwrap().write(&blk, &mut p)?;
+		drop(bm);
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 10).unwrap()));
+
+		let mut tx2 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx2.set_recovery_policy(RecoveryPolicy::UndoRedo);
+		tx2.recover()?;
+		tx2.commit()?;
+
+		let mut tx3 = Transaction::new(fm, lm, bm)?;
+		tx3.pin(&blk)?;
+		assert_eq!(tx3.get_i32(&blk, 80)?, 1);
+		tx3.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_recover_system_undoes_uncommitted() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/recover");
+		let blk = BlockId::new("recoverfile", 1);
+
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.pin(&blk)?;
+		tx1.set_i32(&blk, 80, 100, true)?;
+		tx1.set_string(&blk, 40, "committed", true)?;
+		tx1.commit()?;
+
+		let mut tx2 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx2.pin(&blk)?;
+		tx2.set_i32(&blk, 80, 200, true)?;
+		tx2.set_string(&blk, 40, "uncommitted", true)?;
+		// the uncommitted changes reach disk before the crash
+		bm.lock().unwrap().flush_all(tx2.txnum)?;
+		let txnum2 = tx2.tx_number();
+		crash(tx2)?;
+
+		// the second recovery starts from the checkpoint the first one wrote
+		let expected = [
+			// tx1's commit has forced its changes, so the default policy doesn't redo them
+			RecoveryReport {
+				undone: [txnum2].into(),
+				redone: BTreeSet::new(),
+			},
+			RecoveryReport::default(),
+		];
+		for expected in expected {
+			let report = Transaction::recover_system(fm.clone(), lm.clone(), bm.clone())?;
+			assert_eq!(report, expected);
+
+			let mut tx3 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+			tx3.pin(&blk)?;
+			assert_eq!(tx3.get_i32(&blk, 80)?, 100);
+			assert_eq!(tx3.get_string(&blk, 40)?, "committed");
+			tx3.commit()?;
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_recover_system_looks_past_nonquiescent_checkpoint() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/nqckpt");
+		let blk1 = BlockId::new("nqckptfile", 1);
+		let blk2 = BlockId::new("nqckptfile", 2);
+
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.pin(&blk1)?;
+		tx1.set_i32(&blk1, 80, 100, true)?;
+
+		let mut tx2 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx2.pin(&blk2)?;
+		tx2.set_i32(&blk2, 80, 200, true)?;
+		tx2.commit()?;
+
+		// tx1 is still active, and its first change reaches disk with the checkpoint
+		RecoveryMgr::checkpoint(lm.clone(), bm.clone())?;
+		tx1.set_string(&blk1, 40, "after checkpoint", true)?;
+		crash(tx1)?;
+		drop(bm);
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 10).unwrap()));
+
+		Transaction::recover_system(fm.clone(), lm.clone(), bm.clone())?;
+		let mut tx3 = Transaction::new(fm, lm, bm)?;
+		tx3.pin(&blk1)?;
+		tx3.pin(&blk2)?;
+		assert_eq!(tx3.get_i32(&blk1, 80)?, 0);
+		assert_eq!(tx3.get_string(&blk1, 40)?, "");
+		assert_eq!(tx3.get_i32(&blk2, 80)?, 200);
+		tx3.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_checkpoint_lists_only_txs_of_its_log() -> Result<()> {
+		let (fm1, lm1, bm1) = setup("txtest/ckptdb1");
+		let (_fm2, lm2, bm2) = setup("txtest/ckptdb2");
+
+		// still active, but against the other database
+		let mut tx1 = Transaction::new(fm1, lm1.clone(), bm1)?;
+		RecoveryMgr::checkpoint(lm2.clone(), bm2)?;
+
+		let bytes = lm2.lock().unwrap().iterator()?.next().unwrap();
+		let ckpt = CheckpointRecord::new(Page::new_from_bytes(bytes))?;
+		assert!(ckpt.active_txs().is_empty());
+		assert!(lm1.lock().unwrap().active_txs().lock().unwrap().contains(&tx1.tx_number()));
+		tx1.commit()?;
+
+		Ok(())
+	}
+
+	fn count_records(lm: &Arc<Mutex<LogMgr>>, op: TxType) -> Result<usize> {
+		let mut count = 0;
+		for bytes in lm.lock().unwrap().iterator()? {
+			if create_log_record(bytes)?.op() == op {
+				count += 1;
+			}
+		}
+		Ok(count)
+	}
+
+	#[test]
+	fn test_recover_system_finishes_interrupted_rollback() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/clr");
+		let blk = BlockId::new("clrfile", 1);
+
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.pin(&blk)?;
+		tx1.set_i64(&blk, 0, 10, true)?;
+		tx1.set_i32(&blk, 80, 20, true)?;
+		tx1.set_string(&blk, 40, "interrupted", true)?;
+		bm.lock().unwrap().flush_all(tx1.txnum)?;
+
+		// the rollback undoes the last change, then the system crashes
+		// before the undone value reaches the disk
+		let rec = create_log_record(lm.lock().unwrap().iterator()?.next().unwrap())?;
+		let lsn = CompensationRecord::write_to_log(lm.clone(), &rec)?;
+		rec.undo(&mut tx1)?;
+		lm.lock().unwrap().flush(lsn)?;
+		crash(tx1)?;
+		drop(bm);
+
+		for _ in 0..2 {
+			let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 10).unwrap()));
+			Transaction::recover_system(fm.clone(), lm.clone(), bm.clone())?;
+			// only the two changes left are undone and compensated
+			assert_eq!(count_records(&lm, TxType::COMPENSATION)?, 3);
+
+			let mut tx2 = Transaction::new(fm.clone(), lm.clone(), bm)?;
+			tx2.pin(&blk)?;
+			assert_eq!(tx2.get_i64(&blk, 0)?, 0);
+			assert_eq!(tx2.get_i32(&blk, 80)?, 0);
+			assert_eq!(tx2.get_string(&blk, 40)?, "");
+			tx2.commit()?;
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_recover_system_is_idempotent() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/idempotent");
+		let blk1 = BlockId::new("idempotentfile", 1);
+		let blk2 = BlockId::new("idempotentfile", 2);
+
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.pin(&blk1)?;
+		tx1.set_i32(&blk1, 80, 1, true)?;
+		tx1.set_string(&blk1, 40, "committed", true)?;
+		tx1.commit()?;
+
+		let mut tx2 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx2.pin(&blk1)?;
+		tx2.pin(&blk2)?;
+		tx2.set_i32(&blk1, 80, 2, true)?;
+		tx2.set_i64(&blk2, 0, 3, true)?;
+		bm.lock().unwrap().flush_all(tx2.txnum)?;
+		crash(tx2)?;
+		drop(bm);
+
+		let mut contents = vec![];
+		for _ in 0..3 {
+			let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 10).unwrap()));
+			Transaction::recover_system(fm.clone(), lm.clone(), bm)?;
+
+			let mut pages = vec![];
+			for blk in [&blk1, &blk2] {
+				let mut p = Page::new_from_size(400);
+				fm.lock().unwrap().read(blk, &mut p)?;
+				pages.push(p.contents().clone());
+			}
+			contents.push(pages);
+		}
+		assert_eq!(contents[0], contents[1]);
+		assert_eq!(contents[1], contents[2]);
+		assert_eq!(Page::new_from_bytes(contents[0][0].clone()).get_i32(80)?, 1);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_checkpoint_after_interval() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/autockpt");
+		let blk = BlockId::new("autockptfile", 1);
+		lm.lock().unwrap().set_checkpoint_interval(5);
+
+		// START, 2 set records and COMMIT stay below the interval
+		let mut tx1 = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx1.pin(&blk)?;
+		tx1.set_i32(&blk, 80, 1, true)?;
+		tx1.set_i32(&blk, 80, 2, true)?;
+		tx1.commit()?;
+		assert_eq!(count_records(&lm, TxType::CHECKPOINT)?, 0);
+
+		let mut tx2 = Transaction::new(fm, lm.clone(), bm)?;
+		tx2.pin(&blk)?;
+		tx2.set_i32(&blk, 80, 3, true)?;
+		tx2.commit()?;
+		assert_eq!(count_records(&lm, TxType::CHECKPOINT)?, 1);
+		assert_eq!(lm.lock().unwrap().record_count(), 0);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_recover_system_undoes_only_interleaved_uncommitted() -> Result<()> {
+		let (fm, lm, bm) = setup("txtest/interleaved");
+		let blk1 = BlockId::new("interleavedfile", 1);
+		let blk2 = BlockId::new("interleavedfile", 2);
+
+		// the log is: <START a> <START b> <SETI32 a> <SETI32 b> <COMMIT a>
+		let mut tx_a = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		let mut tx_b = Transaction::new(fm.clone(), lm.clone(), bm.clone())?;
+		tx_a.pin(&blk1)?;
+		tx_b.pin(&blk2)?;
+		tx_a.set_i32(&blk1, 80, 111, true)?;
+		tx_b.set_i32(&blk2, 80, 222, true)?;
+		tx_a.commit()?;
+		bm.lock().unwrap().flush_all(tx_b.txnum)?;
+		let txnum_b = tx_b.tx_number();
+		crash(tx_b)?;
+
+		let report = Transaction::recover_system(fm.clone(), lm.clone(), bm.clone())?;
+		assert_eq!(report.undone, [txnum_b].into());
+
+		let mut tx = Transaction::new(fm, lm, bm)?;
+		tx.pin(&blk1)?;
+		tx.pin(&blk2)?;
+		assert_eq!(tx.get_i32(&blk1, 80)?, 111);
+		assert_eq!(tx.get_i32(&blk2, 80)?, 0);
+		tx.commit()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_new_fails_when_start_cannot_be_logged() -> Result<()> {
+		let dir = "txtest/tinylog";
+		let _ = fs::remove_dir_all(dir);
+		// a START record and its length don't fit in an 8-byte log block
+		let fm = Arc::new(Mutex::new(FileMgr::new(dir, 8)?));
+		let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log")?));
+		let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 2)?));
+
+		assert!(Transaction::new(fm.clone(), lm.clone(), bm.clone()).is_err());
+		// a read-only tx writes no START record
+		let mut tx = Transaction::new_read_only(fm, lm, bm)?;
+		tx.commit()?;
+
+		Ok(())
+	}
 }
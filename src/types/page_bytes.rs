@@ -6,6 +6,16 @@ impl ToPageBytes for i32 {
 		self.to_be_bytes().to_vec()
 	}
 }
+impl ToPageBytes for i64 {
+	fn to_page_bytes(&self) -> Vec<u8> {
+		self.to_be_bytes().to_vec()
+	}
+}
+impl ToPageBytes for bool {
+	fn to_page_bytes(&self) -> Vec<u8> {
+		vec![*self as u8]
+	}
+}
 impl ToPageBytes for &[u8] {
 	fn to_page_bytes(&self) -> Vec<u8> {
 		let mut v = (self.len() as i32).to_page_bytes();
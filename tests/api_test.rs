@@ -0,0 +1,51 @@
+use std::fs;
+
+use simpledb::api::connection::Connection;
+
+#[test]
+fn test_create_insert_select() {
+	let dir = "apitest/embedded";
+	let _ = fs::remove_dir_all(dir);
+	let mut conn = Connection::new(dir).unwrap();
+	let mut stmt = conn.create_statement();
+
+	assert_eq!(stmt.execute_update("create table student (sid int, sname varchar(10), gradyear int)").unwrap(), 0);
+	let students = [(1, "joe", 2021), (2, "amy", 2020), (3, "max", 2022), (4, "sue", 2022), (5, "bob", 2020)];
+	for (sid, sname, gradyear) in students {
+		let cmd = format!("insert into student (sid, sname, gradyear) values ({}, '{}', {})", sid, sname, gradyear);
+		assert_eq!(stmt.execute_update(&cmd).unwrap(), 1);
+	}
+	assert_eq!(stmt.execute_update("update student set gradyear = 2023 where sname = 'bob'").unwrap(), 1);
+	// a failed statement is rolled back, and the connection is still usable
+	assert!(stmt.execute_update("insert into student (sid) values ('x')").is_err());
+	assert!(stmt.execute_query("select sid from nosuchtable").is_err());
+	// so is a query that fails mid-scan
+	let mut rs = stmt.execute_query("select sname from student").unwrap();
+	assert!(rs.next().unwrap());
+	assert!(rs.get_i32("sname").is_err());
+	assert!(rs.next().is_err());
+	rs.close().unwrap();
+
+	let mut rs = stmt.execute_query("select SName, sid from student where gradyear = 2022").unwrap();
+	assert_eq!(rs.schema().fields(), ["sname", "sid"]);
+	let mut rows = vec![];
+	while rs.next().unwrap() {
+		rows.push((rs.get_i32("sid").unwrap(), rs.get_string("SNAME").unwrap()));
+	}
+	rs.close().unwrap();
+	rows.sort();
+	assert_eq!(rows, [(3, "max".to_string()), (4, "sue".to_string())]);
+	conn.close().unwrap();
+
+	// the committed records are there when the database is reopened
+	let mut conn = Connection::new(dir).unwrap();
+	let mut stmt = conn.create_statement();
+	let mut rs = stmt.execute_query("select sname, gradyear from student where sid = 5").unwrap();
+	assert!(rs.next().unwrap());
+	assert_eq!(rs.get_string("sname").unwrap(), "bob");
+	assert_eq!(rs.get_i32("gradyear").unwrap(), 2023);
+	assert!(!rs.next().unwrap());
+	rs.close().unwrap();
+	assert_eq!(stmt.execute_update("delete from student where gradyear = 2020").unwrap(), 1);
+	conn.close().unwrap();
+}
@@ -0,0 +1,43 @@
+use std::{
+	collections::HashSet,
+	fs,
+	sync::{Arc, Mutex},
+	thread,
+};
+
+use simpledb::{
+	buffer::manager::BufferMgr,
+	file::manager::FileMgr,
+	log::manager::LogMgr,
+	tx::transaction::Transaction,
+};
+
+// Runs in its own process, so no other test takes numbers from the tx counter
+// and the numbers handed out here have to be contiguous.
+#[test]
+fn test_concurrent_tx_numbers_are_contiguous() {
+	let dir = "txtest/concurrent";
+	let _ = fs::remove_dir_all(dir);
+	let fm = Arc::new(Mutex::new(FileMgr::new(dir, 400).unwrap()));
+	let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), "simpledb.log").unwrap()));
+	let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 10).unwrap()));
+
+	let handles: Vec<_> = (0..50)
+		.map(|_| {
+			let (fm, lm, bm) = (fm.clone(), lm.clone(), bm.clone());
+			thread::spawn(move || {
+				let mut tx = Transaction::new(fm, lm, bm).unwrap();
+				// S-locks the end of file through the shared lock table
+				tx.size("concurrentfile").unwrap();
+				tx.commit().unwrap();
+				tx.tx_number()
+			})
+		})
+		.collect();
+	let txnums: HashSet<i32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+	assert_eq!(txnums.len(), 50);
+	let min = *txnums.iter().min().unwrap();
+	let max = *txnums.iter().max().unwrap();
+	assert_eq!(max - min, 49);
+}